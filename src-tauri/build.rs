@@ -1,3 +1,61 @@
 fn main() {
-    tauri_build::build()
+    // Commands listed here get `allow-*` permissions and are denied to the
+    // webview unless a capability grants them (see `permissions/`).
+    tauri_build::try_build(tauri_build::Attributes::new().app_manifest(
+        tauri_build::AppManifest::new().commands(&[
+            "get_categories",
+            "create_category",
+            "update_category",
+            "delete_category",
+            "get_entries",
+            "get_entries_for_period",
+            "search_entries",
+            "get_entry_by_id",
+            "create_entry",
+            "update_entry",
+            "delete_entry",
+            "get_time_entries",
+            "get_time_entries_for_entry",
+            "create_time_entry",
+            "update_time_entry",
+            "delete_time_entry",
+            "find_overlaps",
+            "get_conflicting_time_entries",
+            "backfill_time_ranges",
+            "get_entries_with_total_time",
+            "get_entries_with_total_time_including_empty",
+            "get_entries_with_total_time_for_period",
+            "calculate_time_for_period",
+            "get_active_timer",
+            "start_timer",
+            "pause_timer",
+            "resume_timer",
+            "stop_timer",
+            "resolve_idle",
+            "get_idle_threshold",
+            "set_idle_threshold",
+            "export_csv",
+            "import_csv",
+            "import_toggl",
+            "import_clockify",
+            "import_harvest",
+            "import_timewarrior",
+            "export_timewarrior",
+            "export_ical",
+            "list_calendar_events",
+            "import_calendar_events",
+            "export_backup",
+            "import_backup",
+            "list_backups",
+            "restore_backup",
+            "downgrade_database",
+            "check_database",
+            "get_api_config",
+            "set_api_enabled",
+            "reset_api_token",
+            "get_shortcuts",
+            "set_shortcut",
+        ]),
+    ))
+    .expect("failed to run tauri-build");
 }
@@ -0,0 +1,309 @@
+//! Opt-in HTTP/JSON API on `127.0.0.1`, for scripts and editor plugins to log
+//! time while the app runs.
+//!
+//! Its settings are kept in `api.json` in the app data directory, with the
+//! token every request must send as `Authorization: Bearer <token>`. The
+//! routes are listed in [`routes`].
+
+pub mod routes;
+
+use std::convert::Infallible;
+use std::io::{ErrorKind, Write};
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::pin::pin;
+use std::sync::{Arc, Mutex, RwLock};
+
+use http_body_util::{BodyExt, Full, Limited};
+use hyper::body::{Bytes, Incoming};
+use hyper::header::{AUTHORIZATION, CONTENT_TYPE};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::async_runtime::JoinHandle;
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+
+use crate::error::{Error, Result};
+
+/// Name of the settings file, in the app data directory.
+pub const CONFIG_FILE: &str = "api.json";
+
+pub const DEFAULT_PORT: u16 = 7373;
+
+/// Largest request body read, in bytes.
+const BODY_LIMIT: usize = 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiConfig {
+    pub enabled: bool,
+    pub port: u16,
+    pub token: String,
+}
+
+impl ApiConfig {
+    /// Reads the settings in `dir`, saving them disabled with a new token
+    /// if there are none yet so that the token stays the same from then on.
+    pub fn load(dir: &Path) -> Result<Self> {
+        match std::fs::read_to_string(dir.join(CONFIG_FILE)) {
+            Ok(json) => Ok(serde_json::from_str(&json)?),
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                let config = Self {
+                    enabled: false,
+                    port: DEFAULT_PORT,
+                    token: new_token()?,
+                };
+                config.save(dir)?;
+                Ok(config)
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Writes the settings to `dir`, readable by the user only.
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        std::fs::create_dir_all(dir)?;
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(dir.join(CONFIG_FILE))?;
+        file.write_all(serde_json::to_string_pretty(self)?.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// 32 random bytes, hex-encoded.
+fn new_token() -> Result<String> {
+    let mut bytes = [0u8; 32];
+    getrandom::fill(&mut bytes).map_err(|err| std::io::Error::other(err.to_string()))?;
+    Ok(bytes.iter().map(|byte| format!("{byte:02x}")).collect())
+}
+
+/// The API server, managed as Tauri state so that it can be turned on and
+/// off while the app runs.
+pub struct ApiServer {
+    dir: PathBuf,
+    pool: SqlitePool,
+    /// Called after each request that may have changed the timer.
+    on_change: Arc<dyn Fn() + Send + Sync>,
+    /// Read on every request, so that a new token takes effect on open
+    /// connections too.
+    token: Arc<RwLock<String>>,
+    running: Mutex<Option<Running>>,
+}
+
+struct Running {
+    /// Stops the server and closes its connections when sent to or dropped.
+    shutdown: watch::Sender<()>,
+    /// The task accepting connections, which holds the port until it ends.
+    task: JoinHandle<()>,
+}
+
+impl ApiServer {
+    /// A stopped server whose settings are kept in `dir`.
+    pub fn new(dir: PathBuf, pool: SqlitePool, on_change: Arc<dyn Fn() + Send + Sync>) -> Self {
+        Self {
+            dir,
+            pool,
+            on_change,
+            token: Arc::default(),
+            running: Mutex::new(None),
+        }
+    }
+
+    pub fn config(&self) -> Result<ApiConfig> {
+        ApiConfig::load(&self.dir)
+    }
+
+    /// Starts the server if the settings have it enabled.
+    pub async fn start_if_enabled(&self) -> Result<Option<SocketAddr>> {
+        let config = self.config()?;
+        if !config.enabled {
+            return Ok(None);
+        }
+        self.start(&config).await.map(Some)
+    }
+
+    /// Saves whether the server is enabled, starting or stopping it.
+    pub async fn set_enabled(&self, enabled: bool) -> Result<ApiConfig> {
+        let config = ApiConfig {
+            enabled,
+            ..self.config()?
+        };
+        self.stop().await;
+        if enabled {
+            self.start(&config).await?;
+        }
+        config.save(&self.dir)?;
+        Ok(config)
+    }
+
+    /// Replaces the token, which requests on connections already open must
+    /// send from then on too.
+    pub async fn reset_token(&self) -> Result<ApiConfig> {
+        let config = ApiConfig {
+            token: new_token()?,
+            ..self.config()?
+        };
+        config.save(&self.dir)?;
+        *self.token.write().expect("API token lock poisoned") = config.token.clone();
+        Ok(config)
+    }
+
+    async fn start(&self, config: &ApiConfig) -> Result<SocketAddr> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, config.port)).await?;
+        let address = listener.local_addr()?;
+        *self.token.write().expect("API token lock poisoned") = config.token.clone();
+        let (shutdown, receiver) = watch::channel(());
+        let task = tauri::async_runtime::spawn(serve(
+            listener,
+            self.pool.clone(),
+            self.on_change.clone(),
+            self.token.clone(),
+            receiver,
+        ));
+        *self.running.lock().expect("API server lock poisoned") = Some(Running { shutdown, task });
+        Ok(address)
+    }
+
+    /// Stops the server and waits for it to let go of its port, returning
+    /// whether it was running.
+    async fn stop(&self) -> bool {
+        let running = self
+            .running
+            .lock()
+            .expect("API server lock poisoned")
+            .take();
+        let Some(Running { shutdown, task }) = running else {
+            return false;
+        };
+        let _ = shutdown.send(());
+        let _ = task.await;
+        true
+    }
+}
+
+/// Answers connections on `listener` until `shutdown` fires, then lets the
+/// open ones finish the request they are answering and closes them.
+async fn serve(
+    listener: TcpListener,
+    pool: SqlitePool,
+    on_change: Arc<dyn Fn() + Send + Sync>,
+    token: Arc<RwLock<String>>,
+    mut shutdown: watch::Receiver<()>,
+) {
+    loop {
+        let stream = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(err) => {
+                    eprintln!("timeloop: API connection failed: {err}");
+                    continue;
+                }
+            },
+            _ = shutdown.changed() => break,
+        };
+        let (pool, on_change, token) = (pool.clone(), on_change.clone(), token.clone());
+        let mut shutdown = shutdown.clone();
+        tauri::async_runtime::spawn(async move {
+            let service = service_fn(move |request| {
+                handle(pool.clone(), on_change.clone(), token.clone(), request)
+            });
+            let mut connection =
+                pin!(http1::Builder::new().serve_connection(TokioIo::new(stream), service));
+            let served = tokio::select! {
+                served = connection.as_mut() => served,
+                _ = shutdown.changed() => {
+                    connection.as_mut().graceful_shutdown();
+                    connection.await
+                }
+            };
+            if let Err(err) = served {
+                eprintln!("timeloop: API connection failed: {err}");
+            }
+        });
+    }
+}
+
+async fn handle(
+    pool: SqlitePool,
+    on_change: Arc<dyn Fn() + Send + Sync>,
+    token: Arc<RwLock<String>>,
+    request: Request<Incoming>,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let authorized = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|given| {
+            let token = token.read().expect("API token lock poisoned");
+            same(given.trim().as_bytes(), token.as_bytes())
+        });
+    if !authorized {
+        return Ok(reply(
+            StatusCode::UNAUTHORIZED,
+            serde_json::json!({ "error": "missing or wrong token" }),
+        ));
+    }
+
+    let (parts, body) = request.into_parts();
+    let body = match Limited::new(body, BODY_LIMIT).collect().await {
+        Ok(body) => body.to_bytes(),
+        Err(_) => {
+            return Ok(reply(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                serde_json::json!({ "error": "request body too large" }),
+            ))
+        }
+    };
+    let (path, query) = (parts.uri.path(), parts.uri.query().unwrap_or_default());
+    Ok(
+        match routes::route(&pool, &parts.method, path, query, &body).await {
+            Ok((status, value)) => {
+                if parts.method == Method::POST && path.starts_with("/v1/timer/") {
+                    on_change();
+                }
+                reply(status, value)
+            }
+            Err(err) => reply(
+                status(&err),
+                serde_json::json!({ "error": err.to_string() }),
+            ),
+        },
+    )
+}
+
+fn status(err: &Error) -> StatusCode {
+    match err {
+        Error::NotFound(_) => StatusCode::NOT_FOUND,
+        Error::Overlap(_) => StatusCode::CONFLICT,
+        Error::Invalid { .. } | Error::Json(_) | Error::Csv(_) | Error::Row { .. } => {
+            StatusCode::BAD_REQUEST
+        }
+        Error::Sql(_) | Error::Migrate(_) | Error::Io(_) | Error::Remote(_) => {
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+fn reply(status: StatusCode, value: serde_json::Value) -> Response<Full<Bytes>> {
+    let body = match status {
+        StatusCode::NO_CONTENT => Bytes::new(),
+        _ => Bytes::from(value.to_string()),
+    };
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Full::new(body))
+        .expect("response parts are valid")
+}
+
+/// Compares the token in time independent of where they first differ.
+fn same(given: &[u8], token: &[u8]) -> bool {
+    given.len() == token.len() && given.iter().zip(token).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
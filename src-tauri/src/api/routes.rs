@@ -0,0 +1,300 @@
+//! Routes of the API, mirroring the commands of the app.
+//!
+//! | Method | Path | |
+//! |---|---|---|
+//! | `GET`, `POST` | `/v1/categories` | list, create |
+//! | `PUT`, `DELETE` | `/v1/categories/{id}` | update, delete |
+//! | `GET`, `POST` | `/v1/entries` | list (`?q=` to search, `?from=&to=`), create |
+//! | `GET`, `PUT`, `DELETE` | `/v1/entries/{id}` | get, update, delete |
+//! | `GET` | `/v1/entries/{id}/time-entries` | time logged on the entry |
+//! | `GET`, `POST` | `/v1/time-entries` | list (`?limit=&offset=` or `?from=&to=`), create |
+//! | `PUT`, `DELETE` | `/v1/time-entries/{id}` | update, delete |
+//! | `GET` | `/v1/timer` | the running or paused timer, `null` if none |
+//! | `POST` | `/v1/timer/{start,pause,resume,stop}` | control the timer |
+//! | `GET` | `/v1/reports/totals` | totals per entry (`?from=&to=`) |
+//! | `GET` | `/v1/reports/time` | minutes logged (`?from=&to=&entry_ids=1,2`) |
+//!
+//! Bodies are JSON with the fields of the matching command, in snake case.
+//! Creating answers `201` with the new `id`, updating and deleting `204`.
+//! Updating a category without a `color` keeps the one it has.
+
+use chrono::{DateTime, FixedOffset, Utc};
+use hyper::{Method, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sqlx::SqlitePool;
+
+use crate::db::overlaps::OverlapPolicy;
+use crate::db::{categories, entries, reports, time_entries, timer, TimeRange};
+use crate::error::{Error, Result};
+
+#[derive(Deserialize)]
+struct CategoryBody {
+    name: String,
+    color: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct EntryBody {
+    title: String,
+    category_id: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct TimeEntryBody {
+    entry_id: Option<i64>,
+    duration: i64,
+    date: String,
+    note: Option<String>,
+    started_at: Option<DateTime<FixedOffset>>,
+    ended_at: Option<DateTime<FixedOffset>>,
+    #[serde(default)]
+    overlap: OverlapPolicy,
+}
+
+#[derive(Deserialize)]
+struct StartBody {
+    entry_id: i64,
+    note: Option<String>,
+}
+
+/// Answers a request, already authorized, with its status and JSON body.
+pub async fn route(
+    pool: &SqlitePool,
+    method: &Method,
+    path: &str,
+    query: &str,
+    body: &[u8],
+) -> Result<(StatusCode, Value)> {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let query = Query::parse(query);
+    let ok = |value: Value| Ok((StatusCode::OK, value));
+    let created = |id: i64| Ok((StatusCode::CREATED, json!({ "id": id })));
+    let done = || Ok((StatusCode::NO_CONTENT, Value::Null));
+
+    match (method, &segments[..]) {
+        (&Method::GET, ["v1", "categories"]) => ok(json!(categories::list(pool).await?)),
+        (&Method::POST, ["v1", "categories"]) => {
+            let category: CategoryBody = parse(body)?;
+            let color = category
+                .color
+                .as_deref()
+                .unwrap_or(categories::DEFAULT_COLOR);
+            created(categories::create(pool, &category.name, color).await?)
+        }
+        (&Method::PUT, ["v1", "categories", id]) => {
+            let category: CategoryBody = parse(body)?;
+            let id = id_from(id)?;
+            let color = match category.color {
+                Some(color) => color,
+                None => {
+                    categories::get(pool, id)
+                        .await?
+                        .ok_or(Error::NotFound("category"))?
+                        .color
+                }
+            };
+            categories::update(pool, id, &category.name, &color).await?;
+            done()
+        }
+        (&Method::DELETE, ["v1", "categories", id]) => {
+            categories::delete(pool, id_from(id)?).await?;
+            done()
+        }
+
+        (&Method::GET, ["v1", "entries"]) => match (query.get("q"), query.period()?) {
+            (Some(q), _) => ok(json!(entries::search(pool, q).await?)),
+            (None, Some((from, to))) => ok(json!(entries::list_for_period(pool, from, to).await?)),
+            (None, None) => ok(json!(entries::list(pool).await?)),
+        },
+        (&Method::POST, ["v1", "entries"]) => {
+            let entry: EntryBody = parse(body)?;
+            created(entries::create(pool, &entry.title, entry.category_id).await?)
+        }
+        (&Method::GET, ["v1", "entries", id]) => match entries::get(pool, id_from(id)?).await? {
+            Some(entry) => ok(json!(entry)),
+            None => Err(Error::NotFound("entry")),
+        },
+        (&Method::PUT, ["v1", "entries", id]) => {
+            let entry: EntryBody = parse(body)?;
+            entries::update(pool, id_from(id)?, &entry.title, entry.category_id).await?;
+            done()
+        }
+        (&Method::DELETE, ["v1", "entries", id]) => {
+            entries::delete(pool, id_from(id)?).await?;
+            done()
+        }
+        (&Method::GET, ["v1", "entries", id, "time-entries"]) => ok(json!(
+            time_entries::list_for_entry(pool, id_from(id)?).await?
+        )),
+
+        (&Method::GET, ["v1", "time-entries"]) => match query.period()? {
+            Some((from, to)) => ok(json!(time_entries::list_for_period(pool, from, to).await?)),
+            None => {
+                let limit = query.number("limit")?.unwrap_or(50);
+                let offset = query.number("offset")?.unwrap_or(0);
+                ok(json!(time_entries::list(pool, limit, offset).await?))
+            }
+        },
+        (&Method::POST, ["v1", "time-entries"]) => {
+            let te: TimeEntryBody = parse(body)?;
+            let entry_id = te
+                .entry_id
+                .ok_or_else(|| Error::invalid("entry_id", "is required"))?;
+            let range = TimeRange::from_bounds(te.started_at, te.ended_at)?;
+            let id = time_entries::create(
+                pool,
+                entry_id,
+                te.duration,
+                &te.date,
+                te.note.as_deref(),
+                range,
+                te.overlap,
+            )
+            .await?;
+            created(id)
+        }
+        (&Method::PUT, ["v1", "time-entries", id]) => {
+            let te: TimeEntryBody = parse(body)?;
+            let range = TimeRange::from_bounds(te.started_at, te.ended_at)?;
+            time_entries::update(
+                pool,
+                id_from(id)?,
+                te.duration,
+                &te.date,
+                te.note.as_deref(),
+                range,
+                te.overlap,
+            )
+            .await?;
+            done()
+        }
+        (&Method::DELETE, ["v1", "time-entries", id]) => {
+            time_entries::delete(pool, id_from(id)?).await?;
+            done()
+        }
+
+        (&Method::GET, ["v1", "timer"]) => ok(json!(timer::get(pool).await?)),
+        (&Method::POST, ["v1", "timer", "start"]) => {
+            let start: StartBody = parse(body)?;
+            let logged =
+                timer::start(pool, start.entry_id, start.note.as_deref(), Utc::now()).await?;
+            ok(json!({ "logged": logged }))
+        }
+        (&Method::POST, ["v1", "timer", "pause"]) => {
+            timer::pause(pool, Utc::now()).await?;
+            done()
+        }
+        (&Method::POST, ["v1", "timer", "resume"]) => {
+            timer::resume(pool, Utc::now()).await?;
+            done()
+        }
+        (&Method::POST, ["v1", "timer", "stop"]) => {
+            let logged = timer::stop(pool, Utc::now()).await?;
+            ok(json!({ "logged": logged.map(|logged| logged.id) }))
+        }
+
+        (&Method::GET, ["v1", "reports", "totals"]) => match query.period()? {
+            Some((from, to)) => ok(json!(
+                reports::entry_totals_for_period(pool, from, to).await?
+            )),
+            None => ok(json!(reports::entry_totals(pool).await?)),
+        },
+        (&Method::GET, ["v1", "reports", "time"]) => {
+            let (from, to) = query
+                .period()?
+                .ok_or_else(|| Error::invalid("from", "from and to are required"))?;
+            let entry_ids = query
+                .get("entry_ids")
+                .unwrap_or_default()
+                .split(',')
+                .filter(|id| !id.is_empty())
+                .map(id_from)
+                .collect::<Result<Vec<_>>>()?;
+            let minutes = reports::time_for_period(pool, &entry_ids, from, to).await?;
+            ok(json!({ "minutes": minutes }))
+        }
+
+        _ => Err(Error::NotFound("route")),
+    }
+}
+
+fn parse<T: DeserializeOwned>(body: &[u8]) -> Result<T> {
+    Ok(serde_json::from_slice(body)?)
+}
+
+fn id_from(segment: &str) -> Result<i64> {
+    segment
+        .parse()
+        .map_err(|_| Error::invalid("id", format!("expected a number, got {segment:?}")))
+}
+
+/// Decoded query string parameters.
+struct Query(Vec<(String, String)>);
+
+impl Query {
+    fn parse(query: &str) -> Self {
+        Self(
+            query
+                .split('&')
+                .filter(|pair| !pair.is_empty())
+                .map(|pair| {
+                    let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+                    (decode(key), decode(value))
+                })
+                .collect(),
+        )
+    }
+
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn number(&self, key: &'static str) -> Result<Option<i64>> {
+        self.get(key)
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|_| Error::invalid(key, format!("expected a number, got {value:?}")))
+            })
+            .transpose()
+    }
+
+    /// The `from` and `to` dates, which go together.
+    fn period(&self) -> Result<Option<(&str, &str)>> {
+        match (self.get("from"), self.get("to")) {
+            (Some(from), Some(to)) => Ok(Some((from, to))),
+            (None, None) => Ok(None),
+            _ => Err(Error::invalid("from", "from and to go together")),
+        }
+    }
+}
+
+/// Undoes the percent-encoding of a query string component.
+fn decode(text: &str) -> String {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut rest = text.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        rest = tail;
+        match byte {
+            b'+' => bytes.push(b' '),
+            b'%' => match rest
+                .get(..2)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                Some(decoded) => {
+                    bytes.push(decoded);
+                    rest = &rest[2..];
+                }
+                None => bytes.push(byte),
+            },
+            byte => bytes.push(byte),
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
@@ -0,0 +1,3 @@
+fn main() -> std::process::ExitCode {
+    timeloop_lib::cli::run()
+}
@@ -0,0 +1,72 @@
+//! Durations as typed and shown in a terminal, in whole minutes.
+
+/// Parses `90`, `45m`, `45min`, `2h`, `1h30`, `1h30m`, `1.5h` or `1:30`.
+///
+/// Every part is unsigned, and minutes after hours stay under 60 whichever
+/// way the hours are written.
+pub fn parse(input: &str) -> Result<i64, String> {
+    let invalid = || format!("invalid duration {input:?}, expected e.g. 90, 45m, 1h30 or 1.5h");
+    let text: String = input
+        .to_ascii_lowercase()
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    // Digits only: `parse` alone would take signs, and `f64` also `inf` or `1e3`.
+    let whole = |text: &str| -> Result<i64, String> {
+        if text.is_empty() || !text.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        text.parse().map_err(|_| invalid())
+    };
+    let minutes_after_hours = |text: &str| -> Result<i64, String> {
+        let minutes = whole(text)?;
+        if minutes >= 60 {
+            return Err(invalid());
+        }
+        Ok(minutes)
+    };
+
+    let minutes = if let Some((hours, minutes)) = text.split_once(':') {
+        let (hours, minutes) = (whole(hours)?, minutes_after_hours(minutes)?);
+        hours
+            .checked_mul(60)
+            .and_then(|hours| hours.checked_add(minutes))
+            .ok_or_else(invalid)?
+    } else if let Some((hours, rest)) = text.split_once('h') {
+        let (units, fraction) = hours.split_once('.').unwrap_or((hours, "0"));
+        whole(units)?;
+        whole(fraction)?;
+        let minutes = (hours.parse::<f64>().map_err(|_| invalid())? * 60.0).round();
+        // `as` would saturate.
+        if minutes >= i64::MAX as f64 {
+            return Err(invalid());
+        }
+        let rest = rest.trim_end_matches("min").trim_end_matches('m');
+        let rest = if rest.is_empty() {
+            0
+        } else {
+            minutes_after_hours(rest)?
+        };
+        (minutes as i64).checked_add(rest).ok_or_else(invalid)?
+    } else {
+        whole(text.trim_end_matches("min").trim_end_matches('m'))?
+    };
+
+    if minutes <= 0 {
+        return Err(invalid());
+    }
+    Ok(minutes)
+}
+
+/// Formats minutes the way the app does: `45min`, `2h`, `1h30`.
+pub fn format(minutes: i64) -> String {
+    if minutes < 60 {
+        return format!("{minutes}min");
+    }
+    let (hours, minutes) = (minutes / 60, minutes % 60);
+    if minutes > 0 {
+        format!("{hours}h{minutes}")
+    } else {
+        format!("{hours}h")
+    }
+}
@@ -0,0 +1,575 @@
+//! `timeloop-cli`: logs time and reports on it from a terminal, working on the
+//! same database as the app.
+
+pub mod duration;
+pub mod status;
+
+use std::io::{ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use chrono::{Datelike, Local, Months, NaiveDate, TimeDelta, Utc};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use sqlx::SqlitePool;
+
+use crate::db::imports::{self, ImportReport};
+use crate::db::{self, backup, doctor, entries, overlaps::OverlapPolicy, reports, time_entries};
+use crate::error::{Error, Result};
+use crate::exchange::csv::{self, CsvColumns};
+use crate::exchange::{clockify, harvest, ical, timewarrior, toggl};
+use crate::rpc;
+use status::StatusFormat;
+
+#[derive(Parser)]
+#[command(version, about = "Track time in the Timeloop database")]
+struct Cli {
+    /// Database to use instead of the app's.
+    #[arg(long, global = true, env = "TIMELOOP_DB")]
+    db: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Log time on an entry, creating the entry if needed.
+    Log {
+        title: String,
+        /// Minutes, or e.g. 45m, 1h30, 1.5h, 1:30.
+        #[arg(value_parser = duration::parse)]
+        duration: i64,
+        /// Day worked, YYYY-MM-DD. Defaults to today.
+        #[arg(long)]
+        date: Option<NaiveDate>,
+        /// Category of the entry if it is created, created if needed.
+        #[arg(long)]
+        category: Option<String>,
+        #[arg(long)]
+        note: Option<String>,
+    },
+    /// Start a timer on an entry, stopping the running one.
+    Start {
+        title: String,
+        /// Category of the entry if it is created, created if needed.
+        #[arg(long)]
+        category: Option<String>,
+        #[arg(long)]
+        note: Option<String>,
+    },
+    /// Pause the timer.
+    Pause,
+    /// Resume the paused timer.
+    Resume,
+    /// Stop the timer and log its time.
+    Stop,
+    /// The timer and today's total, formatted for a status bar.
+    Status {
+        #[arg(long, value_enum, default_value_t = StatusFormat::Text)]
+        format: StatusFormat,
+        /// Keep running, printing the status again whenever it changes.
+        #[arg(long)]
+        watch: bool,
+    },
+    /// Time logged per entry over a period, today by default.
+    Report(Period),
+    /// Write the time entries of a period to a file, today's by default.
+    Export {
+        #[arg(long, value_enum, default_value_t = ExportFormat::Csv)]
+        format: ExportFormat,
+        /// File to write, standard output if omitted. For Timewarrior, its
+        /// data directory, whose files are merged with the exported intervals.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        #[command(flatten)]
+        period: Period,
+    },
+    /// Log the time entries of a file, creating missing entries and categories.
+    Import {
+        /// File to read, `-` for standard input. For Timewarrior, a data file
+        /// or its data directory.
+        file: PathBuf,
+        #[arg(long, value_enum, default_value_t = ImportFormat::Csv)]
+        format: ImportFormat,
+        /// Show what would be imported without changing the database.
+        #[arg(long)]
+        dry_run: bool,
+        /// CSV header of a field, e.g. `--column entry=Task`. Fields are date,
+        /// start, end, duration, entry, category and note.
+        #[arg(long = "column", value_name = "FIELD=HEADER")]
+        columns: Vec<String>,
+    },
+    /// Write every category, entry and time entry to a JSON backup.
+    Backup {
+        /// File to write, standard output if omitted.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Replace all data with that of a JSON backup.
+    Restore {
+        /// File to read, `-` for standard input.
+        file: PathBuf,
+    },
+    /// Check the database for damage and data the app cannot make sense of.
+    Doctor {
+        /// Apply the fixes, after snapshotting the database.
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Take the schema back to an older version, for an older build of the
+    /// app, after snapshotting the database.
+    Downgrade {
+        /// Schema version to go back to.
+        version: i64,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportFormat {
+    Csv,
+    /// Timewarrior data files.
+    Timewarrior,
+    /// iCalendar events.
+    Ical,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ImportFormat {
+    Csv,
+    /// Toggl Track detailed report, CSV or JSON.
+    Toggl,
+    /// Clockify detailed report, CSV.
+    Clockify,
+    /// Harvest detailed time report, CSV.
+    Harvest,
+    /// Timewarrior data files.
+    Timewarrior,
+}
+
+/// A range of days picked on the command line.
+#[derive(Args)]
+struct Period {
+    /// This week, Monday to Sunday.
+    #[arg(long, conflicts_with_all = ["today", "month", "from", "to"])]
+    week: bool,
+    /// This calendar month.
+    #[arg(long, conflicts_with_all = ["today", "from", "to"])]
+    month: bool,
+    /// Today only.
+    #[arg(long, conflicts_with_all = ["from", "to"])]
+    today: bool,
+    /// First day, YYYY-MM-DD.
+    #[arg(long)]
+    from: Option<NaiveDate>,
+    /// Last day, YYYY-MM-DD. Defaults to today.
+    #[arg(long)]
+    to: Option<NaiveDate>,
+}
+
+impl Period {
+    /// First and last day, inclusive.
+    fn bounds(&self, today: NaiveDate) -> (NaiveDate, NaiveDate) {
+        if self.week {
+            let monday = today - TimeDelta::days(today.weekday().num_days_from_monday().into());
+            (monday, monday + TimeDelta::days(6))
+        } else if self.month {
+            let first = today.with_day(1).unwrap_or(today);
+            let last = first + Months::new(1) - TimeDelta::days(1);
+            (first, last)
+        } else {
+            let to = self.to.unwrap_or(today);
+            (self.from.unwrap_or(to), to)
+        }
+    }
+}
+
+/// Where timer commands run: in the app when it is running, so that it shows
+/// the change at once, or on the database.
+enum Timer<'a> {
+    #[cfg(unix)]
+    App(rpc::Client),
+    Database(&'a SqlitePool),
+}
+
+impl Timer<'_> {
+    async fn call<T: DeserializeOwned>(&mut self, method: &str, params: Value) -> Result<T> {
+        match self {
+            #[cfg(unix)]
+            Self::App(client) => client.call(method, params).await,
+            Self::Database(pool) => {
+                let result = rpc::dispatch(pool, method, params, Utc::now()).await?;
+                Ok(serde_json::from_value(result)?)
+            }
+        }
+    }
+}
+
+async fn timer(pool: &SqlitePool, via_app: bool) -> Timer<'_> {
+    #[cfg(unix)]
+    if via_app {
+        if let Some(client) = rpc::Client::connect(&rpc::socket_path()).await {
+            return Timer::App(client);
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = via_app;
+    Timer::Database(pool)
+}
+
+/// Entry point of the `timeloop-cli` binary.
+pub fn run() -> ExitCode {
+    let cli = Cli::parse();
+    match tauri::async_runtime::block_on(execute(cli)) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("timeloop: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn execute(cli: Cli) -> Result<()> {
+    // The app only runs on the default database.
+    let via_app = cli.db.is_none();
+    let path = match cli.db {
+        Some(path) => path,
+        None => db::default_path().ok_or(Error::NotFound("config directory"))?,
+    };
+    // Opening the database would migrate it up first.
+    if let Command::Downgrade { version } = cli.command {
+        let downgrade = db::downgrade(&path, version, Local::now()).await?;
+        println!(
+            "Downgraded the schema from version {} to {}, snapshot {} taken beforehand",
+            downgrade.from, downgrade.to, downgrade.snapshot
+        );
+        return Ok(());
+    }
+    let database = db::Database::open(&path).await?;
+    let pool = database.pool();
+    let today = Local::now().date_naive();
+
+    match cli.command {
+        Command::Log {
+            title,
+            duration,
+            date,
+            category,
+            note,
+        } => {
+            let date = date.unwrap_or(today).to_string();
+            let entry_id = entries::find_or_create_named(pool, &title, category.as_deref()).await?;
+            time_entries::create(
+                pool,
+                entry_id,
+                duration,
+                &date,
+                note.as_deref(),
+                None,
+                OverlapPolicy::Allow,
+            )
+            .await?;
+            println!("Logged {} on {title} ({date})", duration::format(duration));
+        }
+        Command::Start {
+            title,
+            category,
+            note,
+        } => {
+            let params = json!({ "title": title, "category": category, "note": note });
+            let started: rpc::Started = timer(pool, via_app)
+                .await
+                .call("timer.start", params)
+                .await?;
+            if let Some(stopped) = started.stopped {
+                println!("Stopped the timer on {stopped}");
+            }
+            println!("Started a timer on {}", started.entry_title);
+        }
+        Command::Pause => {
+            let _: Value = timer(pool, via_app)
+                .await
+                .call("timer.pause", Value::Null)
+                .await?;
+            println!("Timer paused");
+        }
+        Command::Resume => {
+            let _: Value = timer(pool, via_app)
+                .await
+                .call("timer.resume", Value::Null)
+                .await?;
+            println!("Timer resumed");
+        }
+        Command::Stop => {
+            let stopped: rpc::Stopped = timer(pool, via_app)
+                .await
+                .call("timer.stop", Value::Null)
+                .await?;
+            match stopped.logged {
+                Some(minutes) => println!(
+                    "Logged {} on {}",
+                    duration::format(minutes),
+                    stopped.entry_title
+                ),
+                None => println!("Timer discarded, it ran for less than half a minute"),
+            }
+        }
+        Command::Status { format, watch } => status::print(pool, format, watch).await?,
+        Command::Report(period) => {
+            let (from, to) = period.bounds(today);
+            report(pool, from, to).await?;
+        }
+        Command::Export {
+            format,
+            output,
+            period,
+        } => {
+            let (from, to) = period.bounds(today);
+            let time_entries =
+                time_entries::list_for_period(pool, &from.to_string(), &to.to_string()).await?;
+            match format {
+                ExportFormat::Csv => write_output(output, &csv::export(&time_entries)?)?,
+                ExportFormat::Ical => {
+                    write_output(output, &ical::export(&time_entries, Utc::now())?)?
+                }
+                ExportFormat::Timewarrior => {
+                    let export = timewarrior::export(&time_entries);
+                    let mut overlapping = 0;
+                    for file in &export.files {
+                        match &output {
+                            Some(dir) => {
+                                let path = dir.join(&file.name);
+                                let existing = match std::fs::read_to_string(&path) {
+                                    Err(err) if err.kind() == ErrorKind::NotFound => String::new(),
+                                    existing => existing?,
+                                };
+                                let merged = timewarrior::merge(&existing, &file.contents);
+                                std::fs::write(path, merged.contents)?;
+                                overlapping += merged.overlapping;
+                            }
+                            None => print!("{}", file.contents),
+                        }
+                    }
+                    if export.skipped > 0 {
+                        eprintln!(
+                            "Left out {} time entries without start and end times",
+                            export.skipped
+                        );
+                    }
+                    if overlapping > 0 {
+                        eprintln!(
+                            "Left out {overlapping} time entries overlapping intervals already tracked"
+                        );
+                    }
+                }
+            }
+        }
+        Command::Import {
+            file,
+            format,
+            dry_run,
+            columns,
+        } => {
+            let contents = match format {
+                ImportFormat::Timewarrior if file.is_dir() => read_data_files(&file)?,
+                _ => read_input(&file)?,
+            };
+            let (source, rows) = match format {
+                ImportFormat::Csv => (csv::SOURCE, csv::parse(&contents, &csv_columns(&columns)?)?),
+                ImportFormat::Toggl => (toggl::SOURCE, toggl::parse(&contents)?),
+                ImportFormat::Clockify => (clockify::SOURCE, clockify::parse(&contents)?),
+                ImportFormat::Harvest => (harvest::SOURCE, harvest::parse(&contents)?),
+                ImportFormat::Timewarrior => (timewarrior::SOURCE, timewarrior::parse(&contents)?),
+            };
+            let report = imports::import(pool, source, &rows, dry_run).await?;
+            print_import(&report);
+        }
+        Command::Backup { output } => {
+            let backup = backup::create(pool, Utc::now()).await?;
+            write_output(output, &serde_json::to_string_pretty(&backup)?)?;
+        }
+        Command::Restore { file } => {
+            let backup = backup::parse(&read_input(&file)?)?;
+            let summary = backup::restore(pool, &backup).await?;
+            println!(
+                "Restored {} categories, {} entries and {} time entries",
+                summary.categories, summary.entries, summary.time_entries
+            );
+        }
+        Command::Doctor { fix } => doctor(&database, fix).await?,
+        Command::Downgrade { .. } => unreachable!("downgraded before opening the database"),
+    }
+    Ok(())
+}
+
+async fn doctor(database: &db::Database, fix: bool) -> Result<()> {
+    let report = if fix {
+        doctor::repair(database, Local::now()).await?
+    } else {
+        doctor::Report {
+            issues: doctor::check(database.pool()).await?,
+            fixed: false,
+            snapshot: None,
+        }
+    };
+    if report.issues.is_empty() {
+        println!("No problems found");
+        return Ok(());
+    }
+    let fixable = report
+        .issues
+        .iter()
+        .filter(|issue| issue.fix.is_some())
+        .count();
+    let count = report.issues.len();
+    let problems = if count == 1 { "problem" } else { "problems" };
+    match &report.snapshot {
+        Some(snapshot) => {
+            println!("Fixed {fixable} of {count} {problems}, snapshot {snapshot} taken beforehand:")
+        }
+        None => println!("{count} {problems} found:"),
+    }
+    for issue in &report.issues {
+        println!("  {}", issue.problem);
+        match &issue.fix {
+            Some(fix) if report.fixed => println!("    fixed: {fix}"),
+            Some(fix) => println!("    fix: {fix}"),
+            None => println!("    to fix by hand"),
+        }
+    }
+    if !report.fixed && fixable > 0 {
+        println!("Run with --fix to apply the fixes, after snapshotting the database.");
+    }
+    Ok(())
+}
+
+async fn report(pool: &SqlitePool, from: NaiveDate, to: NaiveDate) -> Result<()> {
+    let mut totals =
+        reports::entry_totals_for_period(pool, &from.to_string(), &to.to_string()).await?;
+    if from == to {
+        println!("{from}");
+    } else {
+        println!("{from} to {to}");
+    }
+    if totals.is_empty() {
+        println!("No time logged");
+        return Ok(());
+    }
+    totals.sort_by(|a, b| {
+        b.total_duration
+            .cmp(&a.total_duration)
+            .then_with(|| a.title.cmp(&b.title))
+    });
+
+    let label = |total: &db::EntryTotal| match &total.category_name {
+        Some(category) => format!("{} [{category}]", total.title),
+        None => total.title.clone(),
+    };
+    let width = totals
+        .iter()
+        .map(|total| label(total).chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("Total".len());
+    for total in &totals {
+        println!(
+            "{:<width$}  {:>7}",
+            label(total),
+            duration::format(total.total_duration)
+        );
+    }
+    let sum = totals.iter().map(|total| total.total_duration).sum();
+    println!("{:<width$}  {:>7}", "Total", duration::format(sum));
+    Ok(())
+}
+
+/// The contents of `path`, or of standard input for `-`.
+fn read_input(path: &Path) -> Result<String> {
+    if path.as_os_str() == "-" {
+        let mut contents = String::new();
+        std::io::stdin().read_to_string(&mut contents)?;
+        return Ok(contents);
+    }
+    Ok(std::fs::read_to_string(path)?)
+}
+
+/// The `*.data` files of a Timewarrior data directory, oldest first.
+fn read_data_files(dir: &Path) -> Result<String> {
+    let mut paths = Vec::new();
+    for item in std::fs::read_dir(dir)? {
+        let path = item?.path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "data")
+        {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    let mut contents = String::new();
+    for path in paths {
+        contents.push_str(&std::fs::read_to_string(path)?);
+    }
+    Ok(contents)
+}
+
+/// Writes `contents` to `path`, or to standard output without one.
+fn write_output(path: Option<PathBuf>, contents: &str) -> Result<()> {
+    match path {
+        Some(path) => std::fs::write(path, contents)?,
+        None => std::io::stdout().write_all(contents.as_bytes())?,
+    }
+    Ok(())
+}
+
+/// Default CSV columns overridden by `FIELD=HEADER` arguments.
+fn csv_columns(overrides: &[String]) -> Result<CsvColumns> {
+    let mut columns = CsvColumns::default();
+    for item in overrides {
+        let Some((field, header)) = item.split_once('=') else {
+            return Err(Error::invalid(
+                "column",
+                format!("expected FIELD=HEADER, got {item:?}"),
+            ));
+        };
+        let column = match field.trim() {
+            "date" => &mut columns.date,
+            "start" => &mut columns.start,
+            "end" => &mut columns.end,
+            "duration" => &mut columns.duration,
+            "entry" => &mut columns.entry,
+            "category" => &mut columns.category,
+            "note" => &mut columns.note,
+            other => return Err(Error::invalid("column", format!("unknown field {other:?}"))),
+        };
+        *column = header.to_string();
+    }
+    Ok(columns)
+}
+
+fn print_import(report: &ImportReport) {
+    let verb = if report.dry_run {
+        "Would import"
+    } else {
+        "Imported"
+    };
+    println!(
+        "{verb} {} time entries ({})",
+        report.time_entries,
+        duration::format(report.total_duration)
+    );
+    if report.skipped_duplicates > 0 {
+        println!("Skipped {} already imported", report.skipped_duplicates);
+    }
+    let lists = [
+        ("New categories", &report.created_categories),
+        ("Existing categories", &report.matched_categories),
+        ("New entries", &report.created_entries),
+        ("Existing entries", &report.matched_entries),
+    ];
+    for (label, names) in lists {
+        if !names.is_empty() {
+            println!("{label}: {}", names.join(", "));
+        }
+    }
+}
@@ -0,0 +1,185 @@
+//! `timeloop-cli status`: the timer and today's total as status bars read
+//! them.
+//!
+//! Waybar gets a JSON object per line for a `return-type: json` custom
+//! module, polybar a line of text for a `custom/script` module, and i3blocks
+//! a JSON object per line for a block with `format=json`. With `--watch`,
+//! each is printed again whenever it changes, for waybar's continuous
+//! scripts, polybar's `tail = true` and i3blocks' `interval=persist`.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Local, NaiveTime, TimeDelta, Utc};
+use clap::ValueEnum;
+use serde_json::json;
+use sqlx::SqlitePool;
+
+use super::duration;
+use crate::db::reports;
+use crate::db::timer::{self, ActiveTimer};
+use crate::error::Result;
+
+/// How often `--watch` reads the database.
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Colour i3blocks shows a paused timer in.
+const PAUSED_COLOR: &str = "#888888";
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum StatusFormat {
+    Text,
+    Waybar,
+    Polybar,
+    I3blocks,
+}
+
+/// The timer and today's total at a given time.
+pub struct Status {
+    timer: Option<ActiveTimer>,
+    /// Minutes logged today, the timer's excluded.
+    logged: i64,
+    now: DateTime<Utc>,
+    /// Local midnight starting the day of `now`.
+    today_since: DateTime<Utc>,
+}
+
+impl Status {
+    pub fn new(timer: Option<ActiveTimer>, logged: i64, now: DateTime<Utc>) -> Self {
+        let midnight = now
+            .with_timezone(&Local)
+            .date_naive()
+            .and_time(NaiveTime::MIN);
+        // Where clocks skip midnight, the day starts when they land.
+        let today_since = [midnight, midnight + TimeDelta::hours(1)]
+            .into_iter()
+            .find_map(|start| start.and_local_timezone(Local).earliest())
+            .map_or(now, |start| start.to_utc());
+        Self {
+            timer,
+            logged,
+            now,
+            today_since,
+        }
+    }
+
+    pub async fn read(pool: &SqlitePool, now: DateTime<Utc>) -> Result<Self> {
+        let today = now.with_timezone(&Local).date_naive().to_string();
+        Ok(Self::new(
+            timer::get(pool).await?,
+            reports::total_for_period(pool, &today, &today).await?,
+            now,
+        ))
+    }
+
+    pub fn timer(&self) -> Option<&ActiveTimer> {
+        self.timer.as_ref()
+    }
+
+    /// `running`, `paused` or `stopped`, the CSS class waybar is given.
+    fn state(&self) -> &'static str {
+        match &self.timer {
+            Some(timer) if timer.is_running() => "running",
+            Some(_) => "paused",
+            None => "stopped",
+        }
+    }
+
+    /// The entry and time on the timer, e.g. `Client call 0:25`.
+    fn timer_label(&self) -> String {
+        match &self.timer {
+            Some(timer) => {
+                let label = format!(
+                    "{} {}",
+                    timer.entry_title,
+                    clock(timer.elapsed_seconds(self.now))
+                );
+                if timer.is_running() {
+                    label
+                } else {
+                    format!("{label} (paused)")
+                }
+            }
+            None => "No timer".to_string(),
+        }
+    }
+
+    /// Minutes logged today, counting the timer's time since midnight.
+    fn today(&self) -> i64 {
+        let timer = self.timer.as_ref().map_or(0, |timer| {
+            timer.elapsed_seconds_since(self.today_since, self.now) / 60
+        });
+        self.logged + timer
+    }
+
+    fn line(&self) -> String {
+        format!(
+            "{} · {} today",
+            self.timer_label(),
+            duration::format(self.today())
+        )
+    }
+
+    pub fn render(&self, format: StatusFormat) -> String {
+        match format {
+            StatusFormat::Text => format!(
+                "{}\nToday: {}",
+                self.timer_label(),
+                duration::format(self.today())
+            ),
+            StatusFormat::Polybar => self.line(),
+            StatusFormat::Waybar => json!({
+                "text": markup(&self.line()),
+                "tooltip": markup(&format!(
+                    "{}\nToday: {}",
+                    self.timer_label(),
+                    duration::format(self.today())
+                )),
+                "alt": self.state(),
+                "class": self.state(),
+            })
+            .to_string(),
+            StatusFormat::I3blocks => {
+                let mut block = json!({
+                    "full_text": self.line(),
+                    "short_text": match &self.timer {
+                        Some(timer) => clock(timer.elapsed_seconds(self.now)),
+                        None => duration::format(self.today()),
+                    },
+                });
+                if self.state() == "paused" {
+                    block["color"] = json!(PAUSED_COLOR);
+                }
+                block.to_string()
+            }
+        }
+    }
+}
+
+/// Prints the status, then with `watch` again whenever it changes.
+pub async fn print(pool: &SqlitePool, format: StatusFormat, watch: bool) -> Result<()> {
+    let mut printed = None;
+    loop {
+        let status = Status::read(pool, Utc::now()).await?.render(format);
+        if printed.as_ref() != Some(&status) {
+            println!("{status}");
+            printed = Some(status);
+        }
+        if !watch {
+            return Ok(());
+        }
+        tokio::time::sleep(WATCH_INTERVAL).await;
+    }
+}
+
+/// Seconds as hours and minutes, e.g. `1:05`.
+fn clock(seconds: i64) -> String {
+    let minutes = seconds / 60;
+    format!("{}:{:02}", minutes / 60, minutes % 60)
+}
+
+/// Escapes text for the Pango markup waybar parses.
+fn markup(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -0,0 +1,538 @@
+//! Commands invoked by the webview. Thin wrappers over [`crate::db`].
+
+use chrono::{DateTime, FixedOffset, Local, Utc};
+use tauri::{AppHandle, Emitter, State};
+
+use crate::api::{ApiConfig, ApiServer};
+use crate::db::backup::{self, RestoreSummary};
+use crate::db::doctor::{self, Report};
+use crate::db::imports::{self, ImportReport};
+use crate::db::overlaps::{self, Overlap, OverlapPolicy};
+use crate::db::snapshots::{self, Snapshot};
+use crate::db::timer::{self, ActiveTimer, IdleResolution};
+use crate::db::{
+    self as database, categories, entries, reports, time_entries, Category, Database, Downgrade,
+    Entry, EntryTotal, TimeEntry, TimeEntryWithDetails, TimeRange,
+};
+use crate::error::Result;
+use crate::exchange::csv::{self, CsvColumns};
+use crate::exchange::ical::{CalendarEvent, EventSelection};
+use crate::exchange::{clockify, harvest, ical, timewarrior, toggl};
+use crate::idle;
+use crate::shortcuts::{self, ShortcutAction, ShortcutBinding};
+
+// Categories
+
+#[tauri::command]
+pub async fn get_categories(db: State<'_, Database>) -> Result<Vec<Category>> {
+    categories::list(db.pool()).await
+}
+
+#[tauri::command]
+pub async fn create_category(
+    db: State<'_, Database>,
+    name: String,
+    color: Option<String>,
+) -> Result<i64> {
+    let color = color.as_deref().unwrap_or(categories::DEFAULT_COLOR);
+    categories::create(db.pool(), &name, color).await
+}
+
+#[tauri::command]
+pub async fn update_category(
+    db: State<'_, Database>,
+    id: i64,
+    name: String,
+    color: String,
+) -> Result<()> {
+    categories::update(db.pool(), id, &name, &color).await
+}
+
+#[tauri::command]
+pub async fn delete_category(db: State<'_, Database>, id: i64) -> Result<()> {
+    categories::delete(db.pool(), id).await
+}
+
+// Entries
+
+#[tauri::command]
+pub async fn get_entries(db: State<'_, Database>) -> Result<Vec<Entry>> {
+    entries::list(db.pool()).await
+}
+
+#[tauri::command]
+pub async fn get_entries_for_period(
+    db: State<'_, Database>,
+    start_date: String,
+    end_date: String,
+) -> Result<Vec<Entry>> {
+    entries::list_for_period(db.pool(), &start_date, &end_date).await
+}
+
+#[tauri::command]
+pub async fn search_entries(db: State<'_, Database>, query: String) -> Result<Vec<Entry>> {
+    entries::search(db.pool(), &query).await
+}
+
+#[tauri::command]
+pub async fn get_entry_by_id(db: State<'_, Database>, id: i64) -> Result<Option<Entry>> {
+    entries::get(db.pool(), id).await
+}
+
+#[tauri::command]
+pub async fn create_entry(
+    db: State<'_, Database>,
+    title: String,
+    category_id: Option<i64>,
+) -> Result<i64> {
+    entries::create(db.pool(), &title, category_id).await
+}
+
+#[tauri::command]
+pub async fn update_entry(
+    db: State<'_, Database>,
+    id: i64,
+    title: String,
+    category_id: Option<i64>,
+) -> Result<()> {
+    entries::update(db.pool(), id, &title, category_id).await
+}
+
+#[tauri::command]
+pub async fn delete_entry(db: State<'_, Database>, id: i64) -> Result<()> {
+    entries::delete(db.pool(), id).await
+}
+
+// Time entries
+
+#[tauri::command]
+pub async fn get_time_entries(
+    db: State<'_, Database>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+) -> Result<Vec<TimeEntryWithDetails>> {
+    time_entries::list(db.pool(), limit.unwrap_or(50), offset.unwrap_or(0)).await
+}
+
+#[tauri::command]
+pub async fn get_time_entries_for_entry(
+    db: State<'_, Database>,
+    entry_id: i64,
+) -> Result<Vec<TimeEntry>> {
+    time_entries::list_for_entry(db.pool(), entry_id).await
+}
+
+#[tauri::command]
+pub async fn create_time_entry(
+    db: State<'_, Database>,
+    entry_id: i64,
+    duration: i64,
+    date: String,
+    note: Option<String>,
+    started_at: Option<DateTime<FixedOffset>>,
+    ended_at: Option<DateTime<FixedOffset>>,
+    overlap: Option<OverlapPolicy>,
+) -> Result<i64> {
+    let range = TimeRange::from_bounds(started_at, ended_at)?;
+    time_entries::create(
+        db.pool(),
+        entry_id,
+        duration,
+        &date,
+        note.as_deref(),
+        range,
+        overlap.unwrap_or_default(),
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn update_time_entry(
+    db: State<'_, Database>,
+    id: i64,
+    duration: i64,
+    date: String,
+    note: Option<String>,
+    started_at: Option<DateTime<FixedOffset>>,
+    ended_at: Option<DateTime<FixedOffset>>,
+    overlap: Option<OverlapPolicy>,
+) -> Result<()> {
+    let range = TimeRange::from_bounds(started_at, ended_at)?;
+    time_entries::update(
+        db.pool(),
+        id,
+        duration,
+        &date,
+        note.as_deref(),
+        range,
+        overlap.unwrap_or_default(),
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn delete_time_entry(db: State<'_, Database>, id: i64) -> Result<()> {
+    time_entries::delete(db.pool(), id).await
+}
+
+#[tauri::command]
+pub async fn find_overlaps(
+    db: State<'_, Database>,
+    start_date: String,
+    end_date: String,
+) -> Result<Vec<Overlap>> {
+    overlaps::find(db.pool(), &start_date, &end_date).await
+}
+
+#[tauri::command]
+pub async fn get_conflicting_time_entries(
+    db: State<'_, Database>,
+    started_at: Option<DateTime<FixedOffset>>,
+    ended_at: Option<DateTime<FixedOffset>>,
+    exclude_id: Option<i64>,
+) -> Result<Vec<TimeEntryWithDetails>> {
+    let Some(range) = TimeRange::from_bounds(started_at, ended_at)? else {
+        return Ok(Vec::new());
+    };
+    overlaps::conflicting(db.pool(), range, exclude_id).await
+}
+
+#[tauri::command]
+pub async fn backfill_time_ranges(db: State<'_, Database>) -> Result<u64> {
+    time_entries::backfill_ranges(db.pool(), &Local).await
+}
+
+// Reports
+
+#[tauri::command]
+pub async fn get_entries_with_total_time(db: State<'_, Database>) -> Result<Vec<EntryTotal>> {
+    reports::entry_totals(db.pool()).await
+}
+
+#[tauri::command]
+pub async fn get_entries_with_total_time_including_empty(
+    db: State<'_, Database>,
+) -> Result<Vec<EntryTotal>> {
+    reports::entry_totals_including_empty(db.pool()).await
+}
+
+#[tauri::command]
+pub async fn get_entries_with_total_time_for_period(
+    db: State<'_, Database>,
+    start_date: String,
+    end_date: String,
+) -> Result<Vec<EntryTotal>> {
+    reports::entry_totals_for_period(db.pool(), &start_date, &end_date).await
+}
+
+#[tauri::command]
+pub async fn calculate_time_for_period(
+    db: State<'_, Database>,
+    entry_ids: Vec<i64>,
+    start_date: String,
+    end_date: String,
+) -> Result<i64> {
+    reports::time_for_period(db.pool(), &entry_ids, &start_date, &end_date).await
+}
+
+// Timer
+
+#[tauri::command]
+pub async fn get_active_timer(db: State<'_, Database>) -> Result<Option<ActiveTimer>> {
+    timer::get(db.pool()).await
+}
+
+#[tauri::command]
+pub async fn start_timer(
+    app: AppHandle,
+    db: State<'_, Database>,
+    entry_id: i64,
+    note: Option<String>,
+) -> Result<Option<i64>> {
+    let logged = timer::start(db.pool(), entry_id, note.as_deref(), Utc::now()).await?;
+    timer_changed(&app);
+    Ok(logged)
+}
+
+#[tauri::command]
+pub async fn pause_timer(app: AppHandle, db: State<'_, Database>) -> Result<()> {
+    timer::pause(db.pool(), Utc::now()).await?;
+    timer_changed(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn resume_timer(app: AppHandle, db: State<'_, Database>) -> Result<()> {
+    timer::resume(db.pool(), Utc::now()).await?;
+    timer_changed(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn stop_timer(app: AppHandle, db: State<'_, Database>) -> Result<Option<i64>> {
+    let logged = timer::stop(db.pool(), Utc::now()).await?;
+    timer_changed(&app);
+    Ok(logged.map(|logged| logged.id))
+}
+
+/// Keeps, discards or moves to another entry the idle time on the timer.
+#[tauri::command]
+pub async fn resolve_idle(
+    app: AppHandle,
+    db: State<'_, Database>,
+    resolution: IdleResolution,
+) -> Result<Option<i64>> {
+    let logged = timer::resolve_idle(db.pool(), resolution).await?;
+    timer_changed(&app);
+    Ok(logged)
+}
+
+/// Minutes without input after which the timer is idle, 0 when off.
+#[tauri::command]
+pub async fn get_idle_threshold(db: State<'_, Database>) -> Result<i64> {
+    idle::threshold_minutes(db.pool()).await
+}
+
+#[tauri::command]
+pub async fn set_idle_threshold(db: State<'_, Database>, minutes: i64) -> Result<()> {
+    idle::set_threshold_minutes(db.pool(), minutes).await
+}
+
+/// Tells the D-Bus service, and other listeners, that the timer changed.
+fn timer_changed(app: &AppHandle) {
+    let _ = app.emit(crate::TIMER_CHANGED, ());
+}
+
+// Import and export
+
+#[tauri::command]
+pub async fn export_csv(
+    db: State<'_, Database>,
+    start_date: String,
+    end_date: String,
+) -> Result<String> {
+    let time_entries = time_entries::list_for_period(db.pool(), &start_date, &end_date).await?;
+    csv::export(&time_entries)
+}
+
+#[tauri::command]
+pub async fn import_csv(
+    db: State<'_, Database>,
+    contents: String,
+    columns: Option<CsvColumns>,
+    dry_run: bool,
+) -> Result<ImportReport> {
+    let rows = csv::parse(&contents, &columns.unwrap_or_default())?;
+    imports::import(db.pool(), csv::SOURCE, &rows, dry_run).await
+}
+
+/// Imports a Toggl Track detailed report, as CSV or JSON.
+#[tauri::command]
+pub async fn import_toggl(
+    db: State<'_, Database>,
+    contents: String,
+    dry_run: bool,
+) -> Result<ImportReport> {
+    let rows = toggl::parse(&contents)?;
+    imports::import(db.pool(), toggl::SOURCE, &rows, dry_run).await
+}
+
+/// Imports a Clockify detailed report exported as CSV.
+#[tauri::command]
+pub async fn import_clockify(
+    db: State<'_, Database>,
+    contents: String,
+    dry_run: bool,
+) -> Result<ImportReport> {
+    let rows = clockify::parse(&contents)?;
+    imports::import(db.pool(), clockify::SOURCE, &rows, dry_run).await
+}
+
+/// Imports a Harvest detailed time report exported as CSV.
+#[tauri::command]
+pub async fn import_harvest(
+    db: State<'_, Database>,
+    contents: String,
+    dry_run: bool,
+) -> Result<ImportReport> {
+    let rows = harvest::parse(&contents)?;
+    imports::import(db.pool(), harvest::SOURCE, &rows, dry_run).await
+}
+
+/// Imports the intervals of Timewarrior data files, concatenated.
+#[tauri::command]
+pub async fn import_timewarrior(
+    db: State<'_, Database>,
+    contents: String,
+    dry_run: bool,
+) -> Result<ImportReport> {
+    let rows = timewarrior::parse(&contents)?;
+    imports::import(db.pool(), timewarrior::SOURCE, &rows, dry_run).await
+}
+
+#[tauri::command]
+pub async fn export_timewarrior(
+    db: State<'_, Database>,
+    start_date: String,
+    end_date: String,
+) -> Result<timewarrior::Export> {
+    let time_entries = time_entries::list_for_period(db.pool(), &start_date, &end_date).await?;
+    Ok(timewarrior::export(&time_entries))
+}
+
+#[tauri::command]
+pub async fn export_ical(
+    db: State<'_, Database>,
+    start_date: String,
+    end_date: String,
+) -> Result<String> {
+    let time_entries = time_entries::list_for_period(db.pool(), &start_date, &end_date).await?;
+    ical::export(&time_entries, Utc::now())
+}
+
+/// Lists the events of an iCalendar file starting between the two dates, to
+/// pick those to log with [`import_calendar_events`].
+#[tauri::command]
+pub async fn list_calendar_events(
+    contents: String,
+    start_date: String,
+    end_date: String,
+) -> Result<Vec<CalendarEvent>> {
+    ical::events(&contents, &start_date, &end_date)
+}
+
+/// Logs the time of calendar events on the entries chosen for them.
+#[tauri::command]
+pub async fn import_calendar_events(
+    db: State<'_, Database>,
+    events: Vec<EventSelection>,
+    dry_run: bool,
+) -> Result<ImportReport> {
+    let rows: Vec<_> = events.iter().map(EventSelection::row).collect();
+    imports::import(db.pool(), ical::SOURCE, &rows, dry_run).await
+}
+
+#[tauri::command]
+pub async fn export_backup(db: State<'_, Database>) -> Result<String> {
+    let backup = backup::create(db.pool(), Utc::now()).await?;
+    Ok(serde_json::to_string_pretty(&backup)?)
+}
+
+/// Replaces all data with that of a backup made by [`export_backup`].
+#[tauri::command]
+pub async fn import_backup(db: State<'_, Database>, contents: String) -> Result<RestoreSummary> {
+    backup::restore(db.pool(), &backup::parse(&contents)?).await
+}
+
+// Snapshots
+
+#[tauri::command]
+pub async fn list_backups(db: State<'_, Database>) -> Result<Vec<Snapshot>> {
+    snapshots::list(&snapshots::directory(db.path()))
+}
+
+#[tauri::command]
+pub async fn restore_backup(db: State<'_, Database>, name: String) -> Result<RestoreSummary> {
+    snapshots::restore(&db, &name, Local::now()).await
+}
+
+/// Finds problems in the database, and with `fix` applies the fixes that
+/// can be made.
+#[tauri::command]
+pub async fn check_database(app: AppHandle, db: State<'_, Database>, fix: bool) -> Result<Report> {
+    if !fix {
+        return Ok(Report {
+            issues: doctor::check(db.pool()).await?,
+            fixed: false,
+            snapshot: None,
+        });
+    }
+    let report = doctor::repair(&db, Local::now()).await?;
+    // Fixes can stop the timer or move it to another entry.
+    if report.fixed {
+        timer_changed(&app);
+    }
+    Ok(report)
+}
+
+/// Takes the schema back to `version` for an older build of the app, then
+/// quits, as this build's queries need the latest schema.
+///
+/// The app's pool is closed first, so that the timer, the tray, the servers
+/// and the snapshot schedule, which keep running until the app quits, cannot
+/// write to the database while its tables are rebuilt. Should the downgrade
+/// fail, the app stays open for the error to be seen, asking for a restart
+/// as its pool cannot be used again.
+#[tauri::command]
+pub async fn downgrade_database(
+    app: AppHandle,
+    db: State<'_, Database>,
+    version: i64,
+) -> Result<Downgrade> {
+    database::check_downgrade(db.pool(), version).await?;
+    db.close().await;
+    let downgrade = database::downgrade(db.path(), version, Local::now())
+        .await
+        .map_err(|err| {
+            std::io::Error::other(format!(
+                "{err}; restart Timeloop to open the database again"
+            ))
+        })?;
+    app.exit(0);
+    Ok(downgrade)
+}
+
+// Local API
+
+#[tauri::command]
+pub async fn get_api_config(api: State<'_, ApiServer>) -> Result<ApiConfig> {
+    api.config()
+}
+
+/// Turns the local API on or off, for this run and the next ones.
+#[tauri::command]
+pub async fn set_api_enabled(api: State<'_, ApiServer>, enabled: bool) -> Result<ApiConfig> {
+    api.set_enabled(enabled).await
+}
+
+/// Replaces the API token, so that clients given the old one are refused.
+#[tauri::command]
+pub async fn reset_api_token(api: State<'_, ApiServer>) -> Result<ApiConfig> {
+    api.reset_token().await
+}
+
+// Shortcuts
+
+#[tauri::command]
+pub async fn get_shortcuts(db: State<'_, Database>) -> Result<Vec<ShortcutBinding>> {
+    shortcuts::load(db.pool()).await
+}
+
+/// Binds `action` to `accelerator`, or removes its shortcut without one.
+#[tauri::command]
+pub async fn set_shortcut(
+    app: AppHandle,
+    db: State<'_, Database>,
+    action: ShortcutAction,
+    accelerator: Option<String>,
+) -> Result<Vec<ShortcutBinding>> {
+    shortcuts::set(&app, db.pool(), action, accelerator.as_deref()).await
+}
+
+// Tray
+
+/// Words the tray menu's own items in the webview's language.
+#[tauri::command]
+pub fn set_tray_labels(app: AppHandle, stop_timer: String, show: String, quit: String) {
+    #[cfg(desktop)]
+    crate::tray::set_labels(
+        &app,
+        crate::tray::Labels {
+            stop_timer,
+            show,
+            quit,
+        },
+    );
+    #[cfg(not(desktop))]
+    let _ = (app, stop_timer, show, quit);
+}
@@ -0,0 +1,196 @@
+//! Whole-database backups as a versioned JSON document.
+//!
+//! Rows are copied column for column, ids and timestamps included, so
+//! restoring a backup gives back exactly the data it was made from.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, SqlitePool};
+
+use super::migrations;
+use crate::error::{Error, Result};
+
+/// Value of [`Backup::format`].
+pub const FORMAT: &str = "timeloop";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Backup {
+    pub format: String,
+    /// Latest migration applied to the database the backup was made from.
+    pub schema_version: i64,
+    pub exported_at: DateTime<Utc>,
+    pub categories: Vec<CategoryRow>,
+    pub entries: Vec<EntryRow>,
+    pub time_entries: Vec<TimeEntryRow>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct CategoryRow {
+    pub id: i64,
+    pub name: String,
+    pub color: Option<String>,
+    pub created_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct EntryRow {
+    pub id: i64,
+    pub title: String,
+    pub category_id: Option<i64>,
+    pub created_at: Option<String>,
+}
+
+/// Timestamps are kept as stored rather than parsed, and columns added by
+/// later migrations default to `NULL` when restoring older backups.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TimeEntryRow {
+    pub id: i64,
+    pub entry_id: i64,
+    pub duration: i64,
+    pub date: String,
+    pub note: Option<String>,
+    pub created_at: Option<String>,
+    #[serde(default)]
+    pub started_at: Option<String>,
+    #[serde(default)]
+    pub ended_at: Option<String>,
+    #[serde(default)]
+    pub utc_offset: Option<i32>,
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default)]
+    pub range_estimated: bool,
+}
+
+/// Rows restored from a backup.
+#[derive(Debug, Clone, Serialize)]
+pub struct RestoreSummary {
+    pub categories: usize,
+    pub entries: usize,
+    pub time_entries: usize,
+}
+
+pub async fn create(pool: &SqlitePool, now: DateTime<Utc>) -> Result<Backup> {
+    // One read transaction so the three tables are consistent with each other.
+    let mut tx = pool.begin().await?;
+    let categories =
+        sqlx::query_as("SELECT id, name, color, created_at FROM categories ORDER BY id")
+            .fetch_all(&mut *tx)
+            .await?;
+    let entries =
+        sqlx::query_as("SELECT id, title, category_id, created_at FROM entries ORDER BY id")
+            .fetch_all(&mut *tx)
+            .await?;
+    let time_entries = sqlx::query_as(
+        "
+        SELECT
+            id, entry_id, duration, date, note, created_at, started_at, ended_at, utc_offset,
+            source, range_estimated
+        FROM time_entries
+        ORDER BY id
+        ",
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(Backup {
+        format: FORMAT.to_string(),
+        schema_version: migrations::latest_version(),
+        exported_at: now,
+        categories,
+        entries,
+        time_entries,
+    })
+}
+
+/// Reads a backup, checking its header before the rest so that files from a
+/// newer app are refused with a clear error.
+pub fn parse(json: &str) -> Result<Backup> {
+    #[derive(Deserialize)]
+    struct Header {
+        format: String,
+        schema_version: i64,
+    }
+    let header: Header = serde_json::from_str(json)?;
+    check(&header.format, header.schema_version)?;
+    Ok(serde_json::from_str(json)?)
+}
+
+/// Replaces all categories, entries and time entries with those of `backup`,
+/// in one transaction. The running timer, if any, is discarded.
+pub async fn restore(pool: &SqlitePool, backup: &Backup) -> Result<RestoreSummary> {
+    check(&backup.format, backup.schema_version)?;
+    let mut tx = pool.begin().await?;
+    for table in ["active_timers", "time_entries", "entries", "categories"] {
+        sqlx::query(&format!("DELETE FROM {table}"))
+            .execute(&mut *tx)
+            .await?;
+    }
+    for category in &backup.categories {
+        sqlx::query("INSERT INTO categories (id, name, color, created_at) VALUES ($1, $2, $3, $4)")
+            .bind(category.id)
+            .bind(&category.name)
+            .bind(&category.color)
+            .bind(&category.created_at)
+            .execute(&mut *tx)
+            .await?;
+    }
+    for entry in &backup.entries {
+        sqlx::query(
+            "INSERT INTO entries (id, title, category_id, created_at) VALUES ($1, $2, $3, $4)",
+        )
+        .bind(entry.id)
+        .bind(&entry.title)
+        .bind(entry.category_id)
+        .bind(&entry.created_at)
+        .execute(&mut *tx)
+        .await?;
+    }
+    for te in &backup.time_entries {
+        sqlx::query(
+            "
+            INSERT INTO time_entries (
+                id, entry_id, duration, date, note, created_at, started_at, ended_at, utc_offset,
+                source, range_estimated
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            ",
+        )
+        .bind(te.id)
+        .bind(te.entry_id)
+        .bind(te.duration)
+        .bind(&te.date)
+        .bind(&te.note)
+        .bind(&te.created_at)
+        .bind(&te.started_at)
+        .bind(&te.ended_at)
+        .bind(te.utc_offset)
+        .bind(&te.source)
+        .bind(te.range_estimated)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(RestoreSummary {
+        categories: backup.categories.len(),
+        entries: backup.entries.len(),
+        time_entries: backup.time_entries.len(),
+    })
+}
+
+fn check(format: &str, version: i64) -> Result<()> {
+    if format != FORMAT {
+        return Err(Error::invalid(
+            "format",
+            format!("expected {FORMAT:?}, got {format:?}"),
+        ));
+    }
+    let latest = migrations::latest_version();
+    if version > latest {
+        return Err(Error::invalid(
+            "schema_version",
+            format!("the backup uses schema version {version}, newer than this app's {latest}"),
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,89 @@
+use sqlx::{SqliteConnection, SqlitePool};
+
+use super::{non_empty, Category};
+use crate::error::{Error, Result};
+
+pub const DEFAULT_COLOR: &str = "#6366f1";
+
+pub async fn list(pool: &SqlitePool) -> Result<Vec<Category>> {
+    let categories = sqlx::query_as("SELECT * FROM categories ORDER BY name")
+        .fetch_all(pool)
+        .await?;
+    Ok(categories)
+}
+
+pub async fn get(pool: &SqlitePool, id: i64) -> Result<Option<Category>> {
+    let category = sqlx::query_as("SELECT * FROM categories WHERE id = $1")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(category)
+}
+
+pub async fn create(pool: &SqlitePool, name: &str, color: &str) -> Result<i64> {
+    let name = non_empty("name", name)?;
+    validate_color(color)?;
+    let result = sqlx::query("INSERT INTO categories (name, color) VALUES ($1, $2)")
+        .bind(name)
+        .bind(color)
+        .execute(pool)
+        .await?;
+    Ok(result.last_insert_rowid())
+}
+
+pub async fn update(pool: &SqlitePool, id: i64, name: &str, color: &str) -> Result<()> {
+    let name = non_empty("name", name)?;
+    validate_color(color)?;
+    let result = sqlx::query("UPDATE categories SET name = $1, color = $2 WHERE id = $3")
+        .bind(name)
+        .bind(color)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(Error::NotFound("category"));
+    }
+    Ok(())
+}
+
+/// Returns the id of the category named `name`, ignoring case, creating it
+/// if there is none, and whether it was created.
+pub async fn find_or_create(conn: &mut SqliteConnection, name: &str) -> Result<(i64, bool)> {
+    let name = non_empty("name", name)?;
+    let existing: Option<(i64,)> =
+        sqlx::query_as("SELECT id FROM categories WHERE name = $1 COLLATE NOCASE ORDER BY id")
+            .bind(&name)
+            .fetch_optional(&mut *conn)
+            .await?;
+    if let Some((id,)) = existing {
+        return Ok((id, false));
+    }
+    let result = sqlx::query("INSERT INTO categories (name, color) VALUES ($1, $2)")
+        .bind(&name)
+        .bind(DEFAULT_COLOR)
+        .execute(&mut *conn)
+        .await?;
+    Ok((result.last_insert_rowid(), true))
+}
+
+/// Deletes a category, leaving its entries uncategorized.
+pub async fn delete(pool: &SqlitePool, id: i64) -> Result<()> {
+    sqlx::query("DELETE FROM categories WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+fn validate_color(color: &str) -> Result<()> {
+    let valid = color.len() == 7
+        && color.starts_with('#')
+        && color[1..].chars().all(|c| c.is_ascii_hexdigit());
+    if !valid {
+        return Err(Error::invalid(
+            "color",
+            format!("expected #rrggbb, got {color:?}"),
+        ));
+    }
+    Ok(())
+}
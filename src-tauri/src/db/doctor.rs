@@ -0,0 +1,413 @@
+//! Checks the database for damage and data the app cannot make sense of,
+//! and fixes what it can.
+//!
+//! [`check`] only reads. [`repair`] snapshots the database, then finds the
+//! problems again and applies every fix in one transaction, so that either
+//! all of them are applied or none is. Problems without a fix, such as a
+//! corrupted file or an implausibly long time entry, are left to the user.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use chrono::{DateTime, FixedOffset, Local, NaiveDateTime};
+use serde::Serialize;
+use sqlx::{FromRow, SqliteConnection, SqlitePool};
+
+use super::{snapshots, validate_date, Database, TimeRange};
+use crate::error::Result;
+
+/// Longest time entry taken as plausible, a whole day.
+const MAX_DURATION: i64 = 24 * 60;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Problem {
+    /// A line of `PRAGMA integrity_check` other than `ok`.
+    Corrupted { message: String },
+    /// A row referring to a row that no longer exists, time entries aside.
+    MissingReference {
+        table: String,
+        id: i64,
+        parent: String,
+    },
+    /// A time entry whose entry no longer exists.
+    OrphanedTimeEntry { id: i64, entry_id: i64 },
+    /// A time entry whose date is not a `YYYY-MM-DD` calendar date.
+    InvalidDate { id: i64, date: String },
+    /// A time entry of no time, negative time or more than a day.
+    InvalidDuration { id: i64, duration: i64 },
+    /// Entries with the same title, ignoring case. Only those all in the
+    /// same category are merged; others may be told apart by it.
+    DuplicateEntries { title: String, entry_ids: Vec<i64> },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Corrupted { message } => write!(f, "database file damaged: {message}"),
+            Self::MissingReference { table, id, parent } => {
+                write!(f, "row {id} of {table} refers to a missing row of {parent}")
+            }
+            Self::OrphanedTimeEntry { id, entry_id } => {
+                write!(
+                    f,
+                    "time entry {id} belongs to entry {entry_id}, which no longer exists"
+                )
+            }
+            Self::InvalidDate { id, date } => {
+                write!(f, "time entry {id} has date {date:?}, which is not a date")
+            }
+            Self::InvalidDuration { id, duration } => {
+                write!(f, "time entry {id} lasts {duration} minutes")
+            }
+            Self::DuplicateEntries { title, entry_ids } => {
+                write!(f, "entries {entry_ids:?} are all titled {title:?}")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Fix {
+    DeleteTimeEntry {
+        id: i64,
+    },
+    SetDate {
+        id: i64,
+        date: String,
+    },
+    SetDuration {
+        id: i64,
+        duration: i64,
+    },
+    /// Leaves the entry without a category.
+    Uncategorize {
+        entry_id: i64,
+    },
+    DeleteTimer,
+    /// Moves the time and timer of the `from` entries onto `into`, then
+    /// deletes them.
+    MergeEntries {
+        into: i64,
+        from: Vec<i64>,
+    },
+}
+
+impl fmt::Display for Fix {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::DeleteTimeEntry { id } => write!(f, "delete time entry {id}"),
+            Self::SetDate { id, date } => write!(f, "set the date of time entry {id} to {date}"),
+            Self::SetDuration { id, duration } => {
+                write!(
+                    f,
+                    "set time entry {id} to {duration} minutes, as its range says"
+                )
+            }
+            Self::Uncategorize { entry_id } => write!(f, "remove entry {entry_id}'s category"),
+            Self::DeleteTimer => write!(f, "stop the timer without logging it"),
+            Self::MergeEntries { into, from } => {
+                write!(
+                    f,
+                    "move the time of entries {from:?} to entry {into} and delete them"
+                )
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Issue {
+    #[serde(flatten)]
+    pub problem: Problem,
+    /// None when the problem must be fixed by hand.
+    pub fix: Option<Fix>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    pub issues: Vec<Issue>,
+    /// Whether the fixes of `issues` were applied.
+    pub fixed: bool,
+    /// Snapshot taken before fixing, none when nothing was fixed.
+    pub snapshot: Option<String>,
+}
+
+/// Finds the problems of the database without changing it.
+pub async fn check(pool: &SqlitePool) -> Result<Vec<Issue>> {
+    let mut conn = pool.acquire().await?;
+    find(&mut conn).await
+}
+
+/// Applies the fixes of every problem found, after snapshotting the
+/// database if there is any.
+pub async fn repair(database: &Database, now: DateTime<Local>) -> Result<Report> {
+    let pool = database.pool();
+    let issues = check(pool).await?;
+    if !issues.iter().any(|issue| issue.fix.is_some()) {
+        return Ok(Report {
+            issues,
+            fixed: false,
+            snapshot: None,
+        });
+    }
+    let dir = snapshots::directory(database.path());
+    let snapshot = snapshots::before_repair(pool, &dir, now).await?;
+
+    let mut tx = pool.begin().await?;
+    let issues = find(&mut tx).await?;
+    for fix in issues.iter().filter_map(|issue| issue.fix.as_ref()) {
+        apply(&mut tx, fix).await?;
+    }
+    tx.commit().await?;
+    Ok(Report {
+        issues,
+        fixed: true,
+        snapshot: Some(snapshot),
+    })
+}
+
+async fn find(conn: &mut SqliteConnection) -> Result<Vec<Issue>> {
+    let mut issues = Vec::new();
+
+    let integrity: Vec<String> = sqlx::query_scalar("PRAGMA integrity_check")
+        .fetch_all(&mut *conn)
+        .await?;
+    // Time entries breaking a CHECK constraint are reported row by row below.
+    issues.extend(
+        integrity
+            .into_iter()
+            .filter(|message| {
+                message != "ok" && message != "CHECK constraint failed in time_entries"
+            })
+            .map(|message| Issue {
+                problem: Problem::Corrupted { message },
+                fix: None,
+            }),
+    );
+
+    // Time entries are reported as orphans below, with their entry.
+    let violations: Vec<(String, Option<i64>, String)> = sqlx::query_as(
+        "SELECT \"table\", rowid, parent FROM pragma_foreign_key_check()
+         WHERE \"table\" != 'time_entries'",
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+    for (table, id, parent) in violations {
+        let Some(id) = id else {
+            continue;
+        };
+        let fix = match table.as_str() {
+            "entries" => Some(Fix::Uncategorize { entry_id: id }),
+            "active_timers" => Some(Fix::DeleteTimer),
+            _ => None,
+        };
+        issues.push(Issue {
+            problem: Problem::MissingReference { table, id, parent },
+            fix,
+        });
+    }
+
+    let orphans: Vec<(i64, i64)> = sqlx::query_as(
+        "
+        SELECT te.id, te.entry_id FROM time_entries te
+        LEFT JOIN entries e ON te.entry_id = e.id
+        WHERE e.id IS NULL
+        ORDER BY te.id
+        ",
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+    let deleted: HashSet<i64> = orphans.iter().map(|(id, _)| *id).collect();
+    issues.extend(orphans.into_iter().map(|(id, entry_id)| Issue {
+        problem: Problem::OrphanedTimeEntry { id, entry_id },
+        fix: Some(Fix::DeleteTimeEntry { id }),
+    }));
+
+    let rows: Vec<TimeEntryRow> = sqlx::query_as(
+        "SELECT id, duration, date, created_at, started_at, ended_at, utc_offset
+         FROM time_entries ORDER BY id",
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+    for row in rows.iter().filter(|row| !deleted.contains(&row.id)) {
+        issues.extend(row.issues());
+    }
+
+    issues.extend(duplicates(conn).await?);
+    Ok(issues)
+}
+
+#[derive(FromRow)]
+struct TimeEntryRow {
+    id: i64,
+    duration: i64,
+    date: String,
+    created_at: Option<String>,
+    // Read as text, as a damaged value would fail to decode.
+    started_at: Option<String>,
+    ended_at: Option<String>,
+    utc_offset: Option<i32>,
+}
+
+impl TimeEntryRow {
+    /// The stored range if it reads as one.
+    fn range(&self) -> Option<TimeRange> {
+        let offset = FixedOffset::east_opt(self.utc_offset? * 60)?;
+        let parse = |value: &Option<String>| {
+            DateTime::parse_from_rfc3339(value.as_deref()?)
+                .ok()
+                .map(|time| time.with_timezone(&offset))
+        };
+        Some(TimeRange {
+            started_at: parse(&self.started_at)?,
+            ended_at: parse(&self.ended_at)?,
+        })
+    }
+
+    fn issues(&self) -> Vec<Issue> {
+        let mut issues = Vec::new();
+        let id = self.id;
+        if validate_date("date", &self.date).is_err() {
+            let fix = match self.date_guess() {
+                Some(date) => Fix::SetDate { id, date },
+                None => Fix::DeleteTimeEntry { id },
+            };
+            issues.push(Issue {
+                problem: Problem::InvalidDate {
+                    id,
+                    date: self.date.clone(),
+                },
+                fix: Some(fix),
+            });
+        }
+        if self.duration <= 0 || self.duration > MAX_DURATION {
+            let from_range = self
+                .range()
+                .map(|range| range.minutes())
+                .filter(|minutes| (1..=MAX_DURATION).contains(minutes));
+            let fix = match from_range {
+                Some(duration) => Some(Fix::SetDuration { id, duration }),
+                None if self.duration <= 0 => Some(Fix::DeleteTimeEntry { id }),
+                // Long days happen: only the user can tell.
+                None => None,
+            };
+            issues.push(Issue {
+                problem: Problem::InvalidDuration {
+                    id,
+                    duration: self.duration,
+                },
+                fix,
+            });
+        }
+        issues
+    }
+
+    /// The day the time was worked, from the range, else the day the row was
+    /// created.
+    fn date_guess(&self) -> Option<String> {
+        if let Some(range) = self.range() {
+            return Some(range.started_at.date_naive().to_string());
+        }
+        let created_at = self.created_at.as_deref()?;
+        NaiveDateTime::parse_from_str(created_at, "%Y-%m-%d %H:%M:%S")
+            .ok()
+            .map(|created_at| created_at.date().to_string())
+    }
+}
+
+async fn duplicates(conn: &mut SqliteConnection) -> Result<Vec<Issue>> {
+    let entries: Vec<(i64, String, Option<i64>)> =
+        sqlx::query_as("SELECT id, title, category_id FROM entries ORDER BY id")
+            .fetch_all(&mut *conn)
+            .await?;
+    let mut groups: HashMap<_, Vec<(i64, String, Option<i64>)>> = HashMap::new();
+    for (id, title, category_id) in entries {
+        groups
+            .entry(title.trim().to_lowercase())
+            .or_default()
+            .push((id, title, category_id));
+    }
+    let mut groups: Vec<_> = groups
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .collect();
+    groups.sort_by_key(|group| group[0].0);
+    let issues = groups
+        .into_iter()
+        .map(|group| {
+            // The oldest entry is kept.
+            let entry_ids: Vec<i64> = group.iter().map(|(id, ..)| *id).collect();
+            let same_category = group
+                .iter()
+                .all(|(.., category_id)| *category_id == group[0].2);
+            Issue {
+                fix: same_category.then(|| Fix::MergeEntries {
+                    into: entry_ids[0],
+                    from: entry_ids[1..].to_vec(),
+                }),
+                problem: Problem::DuplicateEntries {
+                    title: group[0].1.clone(),
+                    entry_ids,
+                },
+            }
+        })
+        .collect();
+    Ok(issues)
+}
+
+async fn apply(conn: &mut SqliteConnection, fix: &Fix) -> Result<()> {
+    match fix {
+        Fix::DeleteTimeEntry { id } => {
+            sqlx::query("DELETE FROM time_entries WHERE id = $1")
+                .bind(id)
+                .execute(&mut *conn)
+                .await?;
+        }
+        Fix::SetDate { id, date } => {
+            sqlx::query("UPDATE time_entries SET date = $1 WHERE id = $2")
+                .bind(date)
+                .bind(id)
+                .execute(&mut *conn)
+                .await?;
+        }
+        Fix::SetDuration { id, duration } => {
+            sqlx::query("UPDATE time_entries SET duration = $1 WHERE id = $2")
+                .bind(duration)
+                .bind(id)
+                .execute(&mut *conn)
+                .await?;
+        }
+        Fix::Uncategorize { entry_id } => {
+            sqlx::query("UPDATE entries SET category_id = NULL WHERE id = $1")
+                .bind(entry_id)
+                .execute(&mut *conn)
+                .await?;
+        }
+        Fix::DeleteTimer => {
+            sqlx::query("DELETE FROM active_timers")
+                .execute(&mut *conn)
+                .await?;
+        }
+        Fix::MergeEntries { into, from } => {
+            for id in from {
+                for query in [
+                    "UPDATE time_entries SET entry_id = $1 WHERE entry_id = $2",
+                    "UPDATE active_timers SET entry_id = $1 WHERE entry_id = $2",
+                ] {
+                    sqlx::query(query)
+                        .bind(into)
+                        .bind(id)
+                        .execute(&mut *conn)
+                        .await?;
+                }
+                sqlx::query("DELETE FROM entries WHERE id = $1")
+                    .bind(id)
+                    .execute(&mut *conn)
+                    .await?;
+            }
+        }
+    }
+    Ok(())
+}
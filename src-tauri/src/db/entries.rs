@@ -0,0 +1,178 @@
+use sqlx::{SqliteConnection, SqlitePool};
+
+use super::{non_empty, validate_date, Entry};
+use crate::error::{Error, Result};
+
+const SELECT_ENTRY: &str = "
+    SELECT e.*, c.name AS category_name, c.color AS category_color
+    FROM entries e
+    LEFT JOIN categories c ON e.category_id = c.id
+";
+
+pub async fn list(pool: &SqlitePool) -> Result<Vec<Entry>> {
+    let entries = sqlx::query_as(&format!("{SELECT_ENTRY} ORDER BY e.title"))
+        .fetch_all(pool)
+        .await?;
+    Ok(entries)
+}
+
+/// Entries with at least one time entry between the two dates, inclusive.
+pub async fn list_for_period(
+    pool: &SqlitePool,
+    start_date: &str,
+    end_date: &str,
+) -> Result<Vec<Entry>> {
+    validate_date("start_date", start_date)?;
+    validate_date("end_date", end_date)?;
+    let entries = sqlx::query_as(
+        "
+        SELECT DISTINCT e.*, c.name AS category_name, c.color AS category_color
+        FROM entries e
+        LEFT JOIN categories c ON e.category_id = c.id
+        INNER JOIN time_entries te ON e.id = te.entry_id
+        WHERE te.date >= $1 AND te.date <= $2
+        ORDER BY e.title
+        ",
+    )
+    .bind(start_date)
+    .bind(end_date)
+    .fetch_all(pool)
+    .await?;
+    Ok(entries)
+}
+
+pub async fn search(pool: &SqlitePool, query: &str) -> Result<Vec<Entry>> {
+    let entries = sqlx::query_as(&format!(
+        "{SELECT_ENTRY} WHERE e.title LIKE $1 ORDER BY e.title LIMIT 10"
+    ))
+    .bind(format!("%{query}%"))
+    .fetch_all(pool)
+    .await?;
+    Ok(entries)
+}
+
+/// The `limit` entries time was last logged on, most recent first.
+pub async fn recent(pool: &SqlitePool, limit: i64) -> Result<Vec<Entry>> {
+    let entries = sqlx::query_as(
+        "
+        SELECT e.*, c.name AS category_name, c.color AS category_color
+        FROM entries e
+        LEFT JOIN categories c ON e.category_id = c.id
+        INNER JOIN (
+            SELECT entry_id, MAX(date) AS last_date, MAX(id) AS last_id
+            FROM time_entries
+            GROUP BY entry_id
+        ) te ON e.id = te.entry_id
+        ORDER BY te.last_date DESC, te.last_id DESC
+        LIMIT $1
+        ",
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    Ok(entries)
+}
+
+pub async fn get(pool: &SqlitePool, id: i64) -> Result<Option<Entry>> {
+    let entry = sqlx::query_as(&format!("{SELECT_ENTRY} WHERE e.id = $1"))
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(entry)
+}
+
+pub async fn create(pool: &SqlitePool, title: &str, category_id: Option<i64>) -> Result<i64> {
+    let title = non_empty("title", title)?;
+    let result = sqlx::query("INSERT INTO entries (title, category_id) VALUES ($1, $2)")
+        .bind(title)
+        .bind(category_id)
+        .execute(pool)
+        .await?;
+    Ok(result.last_insert_rowid())
+}
+
+/// Returns the id of the entry titled `title`, ignoring case, in
+/// `category_id`, creating it if there is none, and whether it was created.
+///
+/// Entries of the same title in other categories, or without one, are told
+/// apart by it and not matched.
+pub async fn find_or_create(
+    conn: &mut SqliteConnection,
+    title: &str,
+    category_id: Option<i64>,
+) -> Result<(i64, bool)> {
+    let title = non_empty("title", title)?;
+    let existing: Option<(i64,)> = sqlx::query_as(
+        "SELECT id FROM entries WHERE title = $1 COLLATE NOCASE AND category_id IS $2 ORDER BY id",
+    )
+    .bind(&title)
+    .bind(category_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    if let Some((id,)) = existing {
+        return Ok((id, false));
+    }
+    let result = sqlx::query("INSERT INTO entries (title, category_id) VALUES ($1, $2)")
+        .bind(&title)
+        .bind(category_id)
+        .execute(&mut *conn)
+        .await?;
+    Ok((result.last_insert_rowid(), true))
+}
+
+/// The id of the entry titled `title` in the category named `category`,
+/// both created if needed. Without a category, the oldest entry of that
+/// title is used whatever its category, for a title typed in a terminal.
+pub async fn find_or_create_named(
+    pool: &SqlitePool,
+    title: &str,
+    category: Option<&str>,
+) -> Result<i64> {
+    let mut tx = pool.begin().await?;
+    let category_id = match category {
+        Some(name) => Some(super::categories::find_or_create(&mut tx, name).await?.0),
+        None => {
+            let existing: Option<i64> = sqlx::query_scalar(
+                "SELECT id FROM entries WHERE title = $1 COLLATE NOCASE ORDER BY id",
+            )
+            .bind(title.trim())
+            .fetch_optional(&mut *tx)
+            .await?;
+            if let Some(id) = existing {
+                return Ok(id);
+            }
+            None
+        }
+    };
+    let (entry_id, _) = find_or_create(&mut tx, title, category_id).await?;
+    tx.commit().await?;
+    Ok(entry_id)
+}
+
+pub async fn update(
+    pool: &SqlitePool,
+    id: i64,
+    title: &str,
+    category_id: Option<i64>,
+) -> Result<()> {
+    let title = non_empty("title", title)?;
+    let result = sqlx::query("UPDATE entries SET title = $1, category_id = $2 WHERE id = $3")
+        .bind(title)
+        .bind(category_id)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(Error::NotFound("entry"));
+    }
+    Ok(())
+}
+
+/// Deletes an entry; its time entries go with it through `ON DELETE CASCADE`.
+pub async fn delete(pool: &SqlitePool, id: i64) -> Result<()> {
+    sqlx::query("DELETE FROM entries WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
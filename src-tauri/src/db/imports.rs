@@ -0,0 +1,184 @@
+//! Logging time read from another tool or file, matching entries and
+//! categories by name.
+
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+use sqlx::SqlitePool;
+
+use super::{categories, entries, time_entries, TimeRange};
+use crate::error::{Error, Result};
+
+/// A time entry to import, naming its entry and category.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportRow {
+    /// `YYYY-MM-DD`.
+    pub date: String,
+    /// Minutes.
+    pub duration: i64,
+    /// Existing entry to log the row on, rather than the one titled
+    /// `entry_title`.
+    pub entry_id: Option<i64>,
+    pub entry_title: String,
+    pub category_name: Option<String>,
+    pub note: Option<String>,
+    pub range: Option<TimeRange>,
+}
+
+/// What an import did, or would do on a dry run.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub time_entries: usize,
+    /// Minutes.
+    pub total_duration: i64,
+    /// Titles of the entries that did not exist yet.
+    pub created_entries: Vec<String>,
+    /// Titles of the existing entries time was logged on.
+    pub matched_entries: Vec<String>,
+    /// Names of the categories that did not exist yet.
+    pub created_categories: Vec<String>,
+    /// Names of the existing categories rows were filed under.
+    pub matched_categories: Vec<String>,
+    /// Rows already logged by an earlier import.
+    pub skipped_duplicates: usize,
+}
+
+/// Logs `rows` in one transaction, creating missing entries and categories.
+/// `source` names the importer that read them, the `SOURCE` of its module in
+/// `exchange`, and is stored with the time entries.
+///
+/// Importing the same file twice logs it once. A row with a range is skipped
+/// when its entry already has a time entry over that exact range. Rows
+/// without a range are matched on their entry, date, duration and note
+/// against time entries logged by the same importer only, as time logged by
+/// hand can repeat legitimately: the file's third such row is skipped if
+/// there were at least three before the import.
+///
+/// A dry run does the same work and rolls it back, so the report previews
+/// the import exactly. A row that fails validation aborts the whole import.
+pub async fn import(
+    pool: &SqlitePool,
+    source: &str,
+    rows: &[ImportRow],
+    dry_run: bool,
+) -> Result<ImportReport> {
+    let mut report = ImportReport {
+        dry_run,
+        ..ImportReport::default()
+    };
+    // Each entry and category is reported once, under the name first seen.
+    let mut seen_categories = HashSet::new();
+    let mut seen_entries = HashSet::new();
+    // Rows without a range seen so far, and how many time entries like them
+    // the importer had logged before.
+    let mut repeats: HashMap<_, (usize, usize)> = HashMap::new();
+    let mut tx = pool.begin().await?;
+    for (index, row) in rows.iter().enumerate() {
+        let at_row = |err| Error::Row {
+            row: index + 1,
+            source: Box::new(err),
+        };
+        let (entry_id, entry_title, created) = match row.entry_id {
+            Some(id) => {
+                let (title,): (String,) = sqlx::query_as("SELECT title FROM entries WHERE id = $1")
+                    .bind(id)
+                    .fetch_optional(&mut *tx)
+                    .await?
+                    .ok_or_else(|| at_row(Error::NotFound("entry")))?;
+                (id, title, false)
+            }
+            None => {
+                let category_id = match row.category_name.as_deref() {
+                    Some(name) if !name.trim().is_empty() => {
+                        let (id, created) = categories::find_or_create(&mut tx, name)
+                            .await
+                            .map_err(at_row)?;
+                        if seen_categories.insert(id) {
+                            let names = if created {
+                                &mut report.created_categories
+                            } else {
+                                &mut report.matched_categories
+                            };
+                            names.push(name.trim().to_string());
+                        }
+                        Some(id)
+                    }
+                    _ => None,
+                };
+                let (entry_id, created) =
+                    entries::find_or_create(&mut tx, &row.entry_title, category_id)
+                        .await
+                        .map_err(at_row)?;
+                (entry_id, row.entry_title.trim().to_string(), created)
+            }
+        };
+        if seen_entries.insert(entry_id) {
+            let titles = if created {
+                &mut report.created_entries
+            } else {
+                &mut report.matched_entries
+            };
+            titles.push(entry_title);
+        }
+        let note = row.note.as_deref().filter(|note| !note.trim().is_empty());
+        let duplicate = match row.range {
+            Some(range) => {
+                let existing: Option<(i64,)> = sqlx::query_as(
+                    "SELECT id FROM time_entries WHERE entry_id = $1 AND started_at = $2 AND ended_at = $3",
+                )
+                .bind(entry_id)
+                .bind(range.started_at.to_utc())
+                .bind(range.ended_at.to_utc())
+                .fetch_optional(&mut *tx)
+                .await?;
+                existing.is_some()
+            }
+            None => {
+                let key = (entry_id, row.date.as_str(), row.duration, note);
+                let (seen, before) = match repeats.get(&key) {
+                    Some(&(seen, before)) => (seen + 1, before),
+                    None => {
+                        let before: i64 = sqlx::query_scalar(
+                            "
+                            SELECT COUNT(*) FROM time_entries
+                            WHERE entry_id = $1 AND date = $2 AND duration = $3 AND note IS $4
+                                AND started_at IS NULL AND source = $5
+                            ",
+                        )
+                        .bind(entry_id)
+                        .bind(&row.date)
+                        .bind(row.duration)
+                        .bind(note)
+                        .bind(source)
+                        .fetch_one(&mut *tx)
+                        .await?;
+                        (1, before as usize)
+                    }
+                };
+                repeats.insert(key, (seen, before));
+                seen <= before
+            }
+        };
+        if duplicate {
+            report.skipped_duplicates += 1;
+            continue;
+        }
+        let id = time_entries::insert(&mut *tx, entry_id, row.duration, &row.date, note, row.range)
+            .await
+            .map_err(at_row)?;
+        sqlx::query("UPDATE time_entries SET source = $1 WHERE id = $2")
+            .bind(source)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        report.time_entries += 1;
+        report.total_duration += row.duration;
+    }
+    if dry_run {
+        tx.rollback().await?;
+    } else {
+        tx.commit().await?;
+    }
+    Ok(report)
+}
@@ -0,0 +1,447 @@
+use std::future::Future;
+use std::pin::Pin;
+
+use sqlx::error::BoxDynError;
+use sqlx::migrate::{Migration as SqlxMigration, MigrationSource, Migrator};
+use sqlx::SqlitePool;
+use tauri_plugin_sql::{Migration, MigrationKind};
+
+use crate::error::Result;
+
+/// Schema history of `timeloop.db`.
+///
+/// Applied migrations are recorded in `_sqlx_migrations` together with a
+/// checksum of their SQL, so a shipped migration must never be edited, not
+/// even its whitespace: add a new version instead.
+///
+/// Each version has an `Up` migration followed by the `Down` one undoing it,
+/// which [`revert`] runs to hand the database back to an older build.
+pub fn migrations() -> Vec<Migration> {
+    vec![
+        Migration {
+            version: 1,
+            description: "create initial tables",
+            sql: r#"
+                CREATE TABLE IF NOT EXISTS categories (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    name TEXT NOT NULL,
+                    color TEXT DEFAULT '#6366f1',
+                    created_at TEXT DEFAULT CURRENT_TIMESTAMP
+                );
+
+                CREATE TABLE IF NOT EXISTS entries (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    title TEXT NOT NULL,
+                    category_id INTEGER,
+                    created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+                    FOREIGN KEY (category_id) REFERENCES categories(id)
+                );
+
+                CREATE TABLE IF NOT EXISTS time_entries (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    entry_id INTEGER NOT NULL,
+                    duration INTEGER NOT NULL,
+                    date TEXT NOT NULL,
+                    note TEXT,
+                    created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+                    FOREIGN KEY (entry_id) REFERENCES entries(id) ON DELETE CASCADE
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_time_entries_date ON time_entries(date);
+                CREATE INDEX IF NOT EXISTS idx_time_entries_entry_id ON time_entries(entry_id);
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 1,
+            description: "drop initial tables",
+            sql: r#"
+                DROP TABLE IF EXISTS time_entries;
+                DROP TABLE IF EXISTS entries;
+                DROP TABLE IF EXISTS categories;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 2,
+            description: "create active_timers",
+            sql: r#"
+                CREATE TABLE IF NOT EXISTS active_timers (
+                    id INTEGER PRIMARY KEY CHECK (id = 1),
+                    entry_id INTEGER NOT NULL,
+                    note TEXT,
+                    started_at TEXT NOT NULL,
+                    resumed_at TEXT,
+                    accumulated_seconds INTEGER NOT NULL DEFAULT 0,
+                    FOREIGN KEY (entry_id) REFERENCES entries(id) ON DELETE CASCADE
+                );
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 2,
+            description: "drop active_timers",
+            sql: r#"
+                DROP TABLE IF EXISTS active_timers;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 3,
+            description: "add time ranges to time_entries",
+            sql: r#"
+                ALTER TABLE time_entries ADD COLUMN started_at TEXT;
+                ALTER TABLE time_entries ADD COLUMN ended_at TEXT;
+                ALTER TABLE time_entries ADD COLUMN utc_offset INTEGER;
+
+                CREATE INDEX IF NOT EXISTS idx_time_entries_started_at ON time_entries(started_at);
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 3,
+            description: "remove time ranges from time_entries",
+            sql: r#"
+                DROP INDEX IF EXISTS idx_time_entries_started_at;
+
+                ALTER TABLE time_entries DROP COLUMN utc_offset;
+                ALTER TABLE time_entries DROP COLUMN ended_at;
+                ALTER TABLE time_entries DROP COLUMN started_at;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 4,
+            description: "create settings",
+            sql: r#"
+                CREATE TABLE IF NOT EXISTS settings (
+                    key TEXT PRIMARY KEY,
+                    value TEXT NOT NULL
+                );
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 4,
+            description: "drop settings",
+            sql: r#"
+                DROP TABLE IF EXISTS settings;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 5,
+            description: "add idle time to active_timers",
+            sql: r#"
+                ALTER TABLE active_timers ADD COLUMN idle_since TEXT;
+                ALTER TABLE active_timers ADD COLUMN idle_until TEXT;
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 5,
+            description: "remove idle time from active_timers",
+            sql: r#"
+                ALTER TABLE active_timers DROP COLUMN idle_until;
+                ALTER TABLE active_timers DROP COLUMN idle_since;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 6,
+            description: "add integrity constraints",
+            sql: r#"
+                -- Rows the new constraints would reject: references to deleted
+                -- rows, durations that are not positive and dates not written
+                -- YYYY-MM-DD. Such a date keeps its first ten characters when
+                -- they are one, as in a full timestamp, and else becomes the
+                -- day the row was created. The snapshot taken before migrating
+                -- keeps the rows as they were.
+                UPDATE entries SET category_id = NULL
+                WHERE category_id IS NOT NULL AND category_id NOT IN (SELECT id FROM categories);
+                DELETE FROM time_entries WHERE entry_id NOT IN (SELECT id FROM entries);
+                DELETE FROM active_timers WHERE entry_id NOT IN (SELECT id FROM entries);
+                UPDATE time_entries SET date = substr(date, 1, 10)
+                WHERE date NOT GLOB '[0-9][0-9][0-9][0-9]-[0-9][0-9]-[0-9][0-9]'
+                    AND substr(date, 1, 10) GLOB '[0-9][0-9][0-9][0-9]-[0-9][0-9]-[0-9][0-9]';
+                UPDATE time_entries SET date = substr(created_at, 1, 10)
+                WHERE date NOT GLOB '[0-9][0-9][0-9][0-9]-[0-9][0-9]-[0-9][0-9]'
+                    AND substr(created_at, 1, 10) GLOB '[0-9][0-9][0-9][0-9]-[0-9][0-9]-[0-9][0-9]';
+                DELETE FROM time_entries
+                WHERE duration <= 0 OR date NOT GLOB '[0-9][0-9][0-9][0-9]-[0-9][0-9]-[0-9][0-9]';
+
+                -- SQLite cannot add constraints to a table: copy the tables
+                -- into new ones that have them.
+                CREATE TABLE entries_new (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    title TEXT NOT NULL,
+                    category_id INTEGER,
+                    created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+                    FOREIGN KEY (category_id) REFERENCES categories(id) ON DELETE SET NULL
+                );
+                INSERT INTO entries_new (id, title, category_id, created_at)
+                    SELECT id, title, category_id, created_at FROM entries;
+
+                CREATE TABLE time_entries_new (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    entry_id INTEGER NOT NULL,
+                    duration INTEGER NOT NULL CHECK (duration > 0),
+                    date TEXT NOT NULL CHECK (date GLOB '[0-9][0-9][0-9][0-9]-[0-9][0-9]-[0-9][0-9]'),
+                    note TEXT,
+                    created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+                    started_at TEXT,
+                    ended_at TEXT,
+                    utc_offset INTEGER,
+                    FOREIGN KEY (entry_id) REFERENCES entries_new(id) ON DELETE CASCADE
+                );
+                INSERT INTO time_entries_new (
+                    id, entry_id, duration, date, note, created_at, started_at, ended_at, utc_offset
+                )
+                    SELECT id, entry_id, duration, date, note, created_at, started_at, ended_at,
+                        utc_offset
+                    FROM time_entries;
+
+                CREATE TABLE active_timers_new (
+                    id INTEGER PRIMARY KEY CHECK (id = 1),
+                    entry_id INTEGER NOT NULL,
+                    note TEXT,
+                    started_at TEXT NOT NULL,
+                    resumed_at TEXT,
+                    accumulated_seconds INTEGER NOT NULL DEFAULT 0,
+                    idle_since TEXT,
+                    idle_until TEXT,
+                    FOREIGN KEY (entry_id) REFERENCES entries_new(id) ON DELETE CASCADE
+                );
+                INSERT INTO active_timers_new
+                    SELECT id, entry_id, note, started_at, resumed_at, accumulated_seconds,
+                        idle_since, idle_until
+                    FROM active_timers;
+
+                -- Ids of deleted rows stay unused.
+                DELETE FROM sqlite_sequence WHERE name IN ('entries_new', 'time_entries_new');
+                INSERT INTO sqlite_sequence (name, seq)
+                    SELECT name || '_new', seq FROM sqlite_sequence
+                    WHERE name IN ('entries', 'time_entries');
+
+                -- Children first, so that dropping entries has nothing to
+                -- cascade to. Renaming entries_new updates the references to it.
+                DROP TABLE active_timers;
+                DROP TABLE time_entries;
+                DROP TABLE entries;
+                ALTER TABLE entries_new RENAME TO entries;
+                ALTER TABLE time_entries_new RENAME TO time_entries;
+                ALTER TABLE active_timers_new RENAME TO active_timers;
+
+                CREATE INDEX idx_time_entries_date ON time_entries(date);
+                CREATE INDEX idx_time_entries_entry_id ON time_entries(entry_id);
+                CREATE INDEX idx_time_entries_started_at ON time_entries(started_at);
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 6,
+            description: "remove integrity constraints",
+            sql: r#"
+                CREATE TABLE entries_new (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    title TEXT NOT NULL,
+                    category_id INTEGER,
+                    created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+                    FOREIGN KEY (category_id) REFERENCES categories(id)
+                );
+                INSERT INTO entries_new (id, title, category_id, created_at)
+                    SELECT id, title, category_id, created_at FROM entries;
+
+                CREATE TABLE time_entries_new (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    entry_id INTEGER NOT NULL,
+                    duration INTEGER NOT NULL,
+                    date TEXT NOT NULL,
+                    note TEXT,
+                    created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+                    started_at TEXT,
+                    ended_at TEXT,
+                    utc_offset INTEGER,
+                    FOREIGN KEY (entry_id) REFERENCES entries_new(id) ON DELETE CASCADE
+                );
+                INSERT INTO time_entries_new (
+                    id, entry_id, duration, date, note, created_at, started_at, ended_at, utc_offset
+                )
+                    SELECT id, entry_id, duration, date, note, created_at, started_at, ended_at,
+                        utc_offset
+                    FROM time_entries;
+
+                CREATE TABLE active_timers_new (
+                    id INTEGER PRIMARY KEY CHECK (id = 1),
+                    entry_id INTEGER NOT NULL,
+                    note TEXT,
+                    started_at TEXT NOT NULL,
+                    resumed_at TEXT,
+                    accumulated_seconds INTEGER NOT NULL DEFAULT 0,
+                    idle_since TEXT,
+                    idle_until TEXT,
+                    FOREIGN KEY (entry_id) REFERENCES entries_new(id) ON DELETE CASCADE
+                );
+                INSERT INTO active_timers_new
+                    SELECT id, entry_id, note, started_at, resumed_at, accumulated_seconds,
+                        idle_since, idle_until
+                    FROM active_timers;
+
+                -- Ids of deleted rows stay unused.
+                DELETE FROM sqlite_sequence WHERE name IN ('entries_new', 'time_entries_new');
+                INSERT INTO sqlite_sequence (name, seq)
+                    SELECT name || '_new', seq FROM sqlite_sequence
+                    WHERE name IN ('entries', 'time_entries');
+
+                -- Children first, so that dropping entries has nothing to
+                -- cascade to. Renaming entries_new updates the references to it.
+                DROP TABLE active_timers;
+                DROP TABLE time_entries;
+                DROP TABLE entries;
+                ALTER TABLE entries_new RENAME TO entries;
+                ALTER TABLE time_entries_new RENAME TO time_entries;
+                ALTER TABLE active_timers_new RENAME TO active_timers;
+
+                CREATE INDEX idx_time_entries_date ON time_entries(date);
+                CREATE INDEX idx_time_entries_entry_id ON time_entries(entry_id);
+                CREATE INDEX idx_time_entries_started_at ON time_entries(started_at);
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 7,
+            description: "record the source of imported time entries",
+            sql: r#"
+                ALTER TABLE time_entries ADD COLUMN source TEXT;
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 7,
+            description: "forget the source of imported time entries",
+            sql: r#"
+                ALTER TABLE time_entries DROP COLUMN source;
+            "#,
+            kind: MigrationKind::Down,
+        },
+        Migration {
+            version: 8,
+            description: "flag estimated time entry ranges",
+            sql: r#"
+                ALTER TABLE time_entries ADD COLUMN range_estimated INTEGER NOT NULL DEFAULT 0;
+            "#,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 8,
+            description: "drop estimated time entry ranges",
+            sql: r#"
+                -- Older versions would take them for the time actually worked.
+                UPDATE time_entries SET started_at = NULL, ended_at = NULL, utc_offset = NULL
+                    WHERE range_estimated = 1;
+                ALTER TABLE time_entries DROP COLUMN range_estimated;
+            "#,
+            kind: MigrationKind::Down,
+        },
+    ]
+}
+
+#[derive(Debug)]
+struct MigrationList(Vec<Migration>);
+
+impl MigrationList {
+    /// The `Up` migrations, the only ones tauri-plugin-sql ran.
+    fn up() -> Self {
+        Self(
+            migrations()
+                .into_iter()
+                .filter(|migration| matches!(migration.kind, MigrationKind::Up))
+                .collect(),
+        )
+    }
+
+    fn down() -> Self {
+        Self(
+            migrations()
+                .into_iter()
+                .filter(|migration| matches!(migration.kind, MigrationKind::Down))
+                .collect(),
+        )
+    }
+}
+
+// Mirrors the conversion done by tauri-plugin-sql so databases it migrated
+// keep matching versions and checksums.
+impl MigrationSource<'static> for MigrationList {
+    fn resolve(
+        self,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<SqlxMigration>, BoxDynError>> + Send>> {
+        Box::pin(async move {
+            Ok(self
+                .0
+                .into_iter()
+                .map(|migration| {
+                    SqlxMigration::new(
+                        migration.version,
+                        migration.description.into(),
+                        migration.kind.into(),
+                        migration.sql.into(),
+                        false,
+                    )
+                })
+                .collect())
+        })
+    }
+}
+
+/// Version of the newest migration, the schema this build expects.
+pub fn latest_version() -> i64 {
+    migrations()
+        .iter()
+        .map(|migration| migration.version)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Versions already applied to the database, none for a new one.
+pub async fn applied_versions(pool: &SqlitePool) -> Result<Vec<i64>> {
+    let tracked: Option<(String,)> = sqlx::query_as(
+        "SELECT name FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations'",
+    )
+    .fetch_optional(pool)
+    .await?;
+    if tracked.is_none() {
+        return Ok(Vec::new());
+    }
+    let versions =
+        sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success ORDER BY version")
+            .fetch_all(pool)
+            .await?;
+    Ok(versions)
+}
+
+/// Brings the schema up to the latest version.
+pub async fn run(pool: &SqlitePool) -> Result<()> {
+    let migrator = Migrator::new(MigrationList::up()).await?;
+    migrator.run(pool).await?;
+    Ok(())
+}
+
+/// Brings the schema up to `version` only, as the build that shipped it left
+/// it.
+pub async fn run_to(pool: &SqlitePool, version: i64) -> Result<()> {
+    let mut list = MigrationList::up();
+    list.0.retain(|migration| migration.version <= version);
+    let migrator = Migrator::new(list).await?;
+    migrator.run(pool).await?;
+    Ok(())
+}
+
+/// Takes the schema back to `version`, undoing the newer migrations from the
+/// latest one down. The columns and tables they added are dropped with their
+/// data.
+pub async fn revert(pool: &SqlitePool, version: i64) -> Result<()> {
+    let migrator = Migrator::new(MigrationList::down()).await?;
+    migrator.undo(pool, version).await?;
+    Ok(())
+}
@@ -0,0 +1,168 @@
+//! Typed access to `timeloop.db`.
+//!
+//! Query functions take a [`SqlitePool`] so they can run against the app
+//! database as well as a throwaway one.
+
+pub mod backup;
+pub mod categories;
+pub mod doctor;
+pub mod entries;
+pub mod imports;
+pub mod migrations;
+mod models;
+pub mod overlaps;
+pub mod reports;
+pub mod settings;
+pub mod snapshots;
+pub mod time_entries;
+pub mod timer;
+
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local, NaiveDate};
+use serde::Serialize;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::SqlitePool;
+
+pub use migrations::migrations;
+pub use models::{Category, Entry, EntryTotal, TimeEntry, TimeEntryWithDetails};
+pub use time_entries::TimeRange;
+
+use crate::error::{Error, Result};
+
+/// File name of the database inside the app config directory.
+pub const DATABASE_FILE: &str = "timeloop.db";
+
+/// Bundle identifier from `tauri.conf.json`, which names the app directories.
+pub const APP_IDENTIFIER: &str = "com.maxpertici.timeloop";
+
+/// Where the desktop app keeps its database, for tools running without it.
+pub fn default_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(APP_IDENTIFIER).join(DATABASE_FILE))
+}
+
+/// Connection pool managed as Tauri state.
+pub struct Database {
+    pool: SqlitePool,
+    path: PathBuf,
+}
+
+impl Database {
+    /// Opens (creating it if needed) the database at `path` and migrates it,
+    /// snapshotting it first if it has an older schema.
+    pub async fn open(path: &Path) -> Result<Self> {
+        let pool = connect(path).await?;
+        let applied = migrations::applied_versions(&pool).await?;
+        if let Some(&version) = applied.last() {
+            if version < migrations::latest_version() {
+                let dir = snapshots::directory(path);
+                snapshots::before_migration(&pool, &dir, version, Local::now()).await?;
+            }
+        }
+        migrations::run(&pool).await?;
+        Ok(Self {
+            pool,
+            path: path.to_path_buf(),
+        })
+    }
+
+    pub fn pool(&self) -> &SqlitePool {
+        &self.pool
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Waits for the queries under way and fails those to come, for the file
+    /// to be changed from another connection.
+    pub async fn close(&self) {
+        self.pool.close().await;
+    }
+}
+
+/// Result of [`downgrade`].
+#[derive(Debug, Clone, Serialize)]
+pub struct Downgrade {
+    /// Schema version the database was at.
+    pub from: i64,
+    pub to: i64,
+    /// Snapshot of the database taken beforehand, in the backups directory.
+    pub snapshot: String,
+}
+
+/// Takes the database at `path` back to schema `version`, so that an older
+/// build of the app can open it, after snapshotting it.
+///
+/// Data only the newer schema holds, such as time ranges from version 3, is
+/// dropped; the snapshot keeps it.
+pub async fn downgrade(path: &Path, version: i64, now: DateTime<Local>) -> Result<Downgrade> {
+    let pool = connect(path).await?;
+    let downgraded = async {
+        let from = check_downgrade(&pool, version).await?;
+        let snapshot =
+            snapshots::before_migration(&pool, &snapshots::directory(path), from, now).await?;
+        migrations::revert(&pool, version).await?;
+        Ok(Downgrade {
+            from,
+            to: version,
+            snapshot,
+        })
+    }
+    .await;
+    pool.close().await;
+    downgraded
+}
+
+/// Checks that the database can be taken back to schema `version`, returning
+/// the version it is at.
+pub(crate) async fn check_downgrade(pool: &SqlitePool, version: i64) -> Result<i64> {
+    let from = migrations::applied_versions(pool)
+        .await?
+        .last()
+        .copied()
+        .unwrap_or(0);
+    if !(1..from).contains(&version) {
+        return Err(Error::invalid(
+            "version",
+            format!("must be at least 1 and older than the current version, {from}"),
+        ));
+    }
+    Ok(from)
+}
+
+/// Connects to the database at `path`, creating it if needed, as is.
+///
+/// Foreign keys are enforced on every connection, which SQLite leaves to
+/// each connection to ask for: deleting a category uncategorizes its entries
+/// and deleting an entry deletes its time.
+pub(crate) async fn connect(path: &Path) -> Result<SqlitePool> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let options = SqliteConnectOptions::new()
+        .filename(path)
+        .create_if_missing(true)
+        .foreign_keys(true);
+    Ok(SqlitePoolOptions::new().connect_with(options).await?)
+}
+
+/// Checks that `date` is a calendar date written `YYYY-MM-DD`.
+pub(crate) fn validate_date(field: &'static str, date: &str) -> Result<()> {
+    if date.len() != 10 || NaiveDate::parse_from_str(date, "%Y-%m-%d").is_err() {
+        return Err(Error::invalid(
+            field,
+            format!("expected YYYY-MM-DD, got {date:?}"),
+        ));
+    }
+    Ok(())
+}
+
+/// Trims `value` and rejects it if nothing is left.
+pub(crate) fn non_empty(field: &'static str, value: &str) -> Result<String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err(Error::invalid(field, "must not be empty"));
+    }
+    Ok(value.to_string())
+}
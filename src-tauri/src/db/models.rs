@@ -0,0 +1,82 @@
+use chrono::{DateTime, FixedOffset, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use super::TimeRange;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Category {
+    pub id: i64,
+    pub name: String,
+    pub color: String,
+    pub created_at: String,
+}
+
+/// An entry joined with its category, if any.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Entry {
+    pub id: i64,
+    pub title: String,
+    pub category_id: Option<i64>,
+    pub created_at: String,
+    pub category_name: Option<String>,
+    pub category_color: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TimeEntry {
+    pub id: i64,
+    pub entry_id: i64,
+    /// Minutes.
+    pub duration: i64,
+    /// `YYYY-MM-DD`.
+    pub date: String,
+    pub note: Option<String>,
+    pub created_at: String,
+    /// When the time was worked, if known.
+    pub started_at: Option<DateTime<Utc>>,
+    pub ended_at: Option<DateTime<Utc>>,
+    /// Minutes east of UTC of the user's clock when `started_at` was logged.
+    pub utc_offset: Option<i32>,
+    /// Whether the range was estimated by [`backfill_ranges`] rather than
+    /// logged, which keeps it out of overlap checks.
+    ///
+    /// [`backfill_ranges`]: super::time_entries::backfill_ranges
+    pub range_estimated: bool,
+}
+
+impl TimeEntry {
+    /// The stored range, on the clock it was logged with.
+    pub fn range(&self) -> Option<TimeRange> {
+        let offset = FixedOffset::east_opt(self.utc_offset? * 60)?;
+        Some(TimeRange {
+            started_at: self.started_at?.with_timezone(&offset),
+            ended_at: self.ended_at?.with_timezone(&offset),
+        })
+    }
+}
+
+/// A time entry joined with the title and category of its entry.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TimeEntryWithDetails {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub time_entry: TimeEntry,
+    pub entry_title: String,
+    pub category_name: Option<String>,
+    pub category_color: Option<String>,
+}
+
+/// Time logged on an entry, over all time or a period.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct EntryTotal {
+    pub id: i64,
+    pub title: String,
+    pub category_id: Option<i64>,
+    pub category_name: Option<String>,
+    pub category_color: Option<String>,
+    pub total_duration: i64,
+    pub first_date: Option<String>,
+    pub last_date: Option<String>,
+    pub entry_count: i64,
+}
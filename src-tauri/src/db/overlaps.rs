@@ -0,0 +1,145 @@
+//! Double-booking detection between time entries that have a range.
+
+use chrono::{DateTime, NaiveDate, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{SqliteExecutor, SqlitePool};
+
+use super::{validate_date, TimeEntryWithDetails, TimeRange};
+use crate::error::{Error, Result};
+
+/// What to do when a time entry being saved overlaps others.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OverlapPolicy {
+    #[default]
+    Allow,
+    Refuse,
+}
+
+/// Two time entries whose ranges intersect.
+#[derive(Debug, Clone, Serialize)]
+pub struct Overlap {
+    pub first: TimeEntryWithDetails,
+    pub second: TimeEntryWithDetails,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    pub minutes: i64,
+}
+
+const SELECT_RANGED: &str = "
+    SELECT
+        te.*,
+        e.title AS entry_title,
+        c.name AS category_name,
+        c.color AS category_color
+    FROM time_entries te
+    JOIN entries e ON te.entry_id = e.id
+    LEFT JOIN categories c ON e.category_id = c.id
+    WHERE te.started_at IS NOT NULL AND te.ended_at IS NOT NULL AND NOT te.range_estimated
+";
+
+/// Overlaps involving a time entry dated between the two dates, inclusive.
+pub async fn find(pool: &SqlitePool, start_date: &str, end_date: &str) -> Result<Vec<Overlap>> {
+    validate_date("start_date", start_date)?;
+    validate_date("end_date", end_date)?;
+    // A range may run past midnight into the first day of the period, or
+    // from its last day into a time entry dated the next.
+    let shift = |date: &str, days| {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map(|date| (date + TimeDelta::days(days)).to_string())
+            .unwrap_or_else(|_| date.to_string())
+    };
+    let time_entries: Vec<TimeEntryWithDetails> = sqlx::query_as(&format!(
+        "{SELECT_RANGED} AND te.date >= $1 AND te.date <= $2 ORDER BY te.started_at"
+    ))
+    .bind(shift(start_date, -1))
+    .bind(shift(end_date, 1))
+    .fetch_all(pool)
+    .await?;
+
+    let in_period = |te: &TimeEntryWithDetails| {
+        te.time_entry.date.as_str() >= start_date && te.time_entry.date.as_str() <= end_date
+    };
+    Ok(pairs(time_entries)
+        .into_iter()
+        .filter(|overlap| in_period(&overlap.first) || in_period(&overlap.second))
+        .collect())
+}
+
+/// Time entries whose range intersects `range`, other than `exclude_id`.
+pub async fn conflicting(
+    executor: impl SqliteExecutor<'_>,
+    range: TimeRange,
+    exclude_id: Option<i64>,
+) -> Result<Vec<TimeEntryWithDetails>> {
+    let time_entries = sqlx::query_as(&format!(
+        "{SELECT_RANGED}
+            AND julianday(te.started_at) < julianday($2)
+            AND julianday($1) < julianday(te.ended_at)
+            AND te.id IS NOT $3
+        ORDER BY te.started_at"
+    ))
+    .bind(range.started_at.to_utc())
+    .bind(range.ended_at.to_utc())
+    .bind(exclude_id)
+    .fetch_all(executor)
+    .await?;
+    Ok(time_entries)
+}
+
+/// Fails with [`Error::Overlap`] when `policy` refuses overlaps and `range`
+/// has some.
+pub async fn enforce(
+    executor: impl SqliteExecutor<'_>,
+    policy: OverlapPolicy,
+    range: Option<TimeRange>,
+    exclude_id: Option<i64>,
+) -> Result<()> {
+    let (OverlapPolicy::Refuse, Some(range)) = (policy, range) else {
+        return Ok(());
+    };
+    let conflicts = conflicting(executor, range, exclude_id).await?;
+    if !conflicts.is_empty() {
+        return Err(Error::Overlap(
+            conflicts.iter().map(|te| te.time_entry.id).collect(),
+        ));
+    }
+    Ok(())
+}
+
+/// Every intersecting pair among time entries with a logged range.
+pub fn pairs(mut time_entries: Vec<TimeEntryWithDetails>) -> Vec<Overlap> {
+    time_entries.retain(|te| {
+        let te = &te.time_entry;
+        te.started_at.is_some() && te.ended_at.is_some() && !te.range_estimated
+    });
+    time_entries.sort_by_key(|te| te.time_entry.started_at);
+
+    let bounds = |te: &TimeEntryWithDetails| {
+        (
+            te.time_entry.started_at.unwrap_or_default(),
+            te.time_entry.ended_at.unwrap_or_default(),
+        )
+    };
+    let mut overlaps = Vec::new();
+    for (i, first) in time_entries.iter().enumerate() {
+        let (first_start, first_end) = bounds(first);
+        for second in &time_entries[i + 1..] {
+            let (second_start, second_end) = bounds(second);
+            // Sorted by start: nothing further along can begin before `first` ends.
+            if second_start >= first_end {
+                break;
+            }
+            let started_at = first_start.max(second_start);
+            let ended_at = first_end.min(second_end);
+            overlaps.push(Overlap {
+                first: first.clone(),
+                second: second.clone(),
+                started_at,
+                ended_at,
+                minutes: (ended_at - started_at).num_minutes(),
+            });
+        }
+    }
+    overlaps
+}
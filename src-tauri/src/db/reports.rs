@@ -0,0 +1,117 @@
+use sqlx::{QueryBuilder, Sqlite, SqlitePool};
+
+use super::{validate_date, EntryTotal};
+use crate::error::Result;
+
+const SELECT_TOTALS: &str = "
+    SELECT
+        e.id,
+        e.title,
+        e.category_id,
+        c.name AS category_name,
+        c.color AS category_color,
+        COALESCE(SUM(te.duration), 0) AS total_duration,
+        MIN(te.date) AS first_date,
+        MAX(te.date) AS last_date,
+        COUNT(te.id) AS entry_count
+    FROM entries e
+    LEFT JOIN categories c ON e.category_id = c.id
+";
+
+/// Totals for every entry that has logged time, most recently worked on first.
+pub async fn entry_totals(pool: &SqlitePool) -> Result<Vec<EntryTotal>> {
+    let totals = sqlx::query_as(&format!(
+        "{SELECT_TOTALS}
+        LEFT JOIN time_entries te ON e.id = te.entry_id
+        GROUP BY e.id, e.title, e.category_id, c.name, c.color
+        HAVING COUNT(te.id) > 0
+        ORDER BY MAX(te.date) DESC"
+    ))
+    .fetch_all(pool)
+    .await?;
+    Ok(totals)
+}
+
+/// Totals for every entry, those without logged time last.
+pub async fn entry_totals_including_empty(pool: &SqlitePool) -> Result<Vec<EntryTotal>> {
+    let totals = sqlx::query_as(&format!(
+        "{SELECT_TOTALS}
+        LEFT JOIN time_entries te ON e.id = te.entry_id
+        GROUP BY e.id, e.title, e.category_id, c.name, c.color
+        ORDER BY
+            CASE WHEN MAX(te.date) IS NULL THEN 1 ELSE 0 END,
+            MAX(te.date) DESC,
+            e.title ASC"
+    ))
+    .fetch_all(pool)
+    .await?;
+    Ok(totals)
+}
+
+/// Totals restricted to time logged between the two dates, inclusive.
+pub async fn entry_totals_for_period(
+    pool: &SqlitePool,
+    start_date: &str,
+    end_date: &str,
+) -> Result<Vec<EntryTotal>> {
+    validate_date("start_date", start_date)?;
+    validate_date("end_date", end_date)?;
+    let totals = sqlx::query_as(&format!(
+        "{SELECT_TOTALS}
+        LEFT JOIN time_entries te ON e.id = te.entry_id
+            AND te.date >= $1
+            AND te.date <= $2
+        GROUP BY e.id, e.title, e.category_id, c.name, c.color
+        HAVING COUNT(te.id) > 0
+        ORDER BY MAX(te.date) DESC"
+    ))
+    .bind(start_date)
+    .bind(end_date)
+    .fetch_all(pool)
+    .await?;
+    Ok(totals)
+}
+
+/// Minutes logged on all entries between the two dates, inclusive.
+pub async fn total_for_period(pool: &SqlitePool, start_date: &str, end_date: &str) -> Result<i64> {
+    validate_date("start_date", start_date)?;
+    validate_date("end_date", end_date)?;
+    let (total,) = sqlx::query_as(
+        "SELECT COALESCE(SUM(duration), 0) FROM time_entries WHERE date >= $1 AND date <= $2",
+    )
+    .bind(start_date)
+    .bind(end_date)
+    .fetch_one(pool)
+    .await?;
+    Ok(total)
+}
+
+/// Minutes logged on `entry_ids` between the two dates, inclusive.
+pub async fn time_for_period(
+    pool: &SqlitePool,
+    entry_ids: &[i64],
+    start_date: &str,
+    end_date: &str,
+) -> Result<i64> {
+    validate_date("start_date", start_date)?;
+    validate_date("end_date", end_date)?;
+    if entry_ids.is_empty() {
+        return Ok(0);
+    }
+
+    let mut query = QueryBuilder::<Sqlite>::new(
+        "SELECT COALESCE(SUM(duration), 0) FROM time_entries WHERE date >= ",
+    );
+    query.push_bind(start_date);
+    query.push(" AND date <= ");
+    query.push_bind(end_date);
+    query.push(" AND entry_id IN (");
+    let mut ids = query.separated(", ");
+    for id in entry_ids {
+        ids.push_bind(*id);
+    }
+    query.push(")");
+
+    let (total,) = query.build_query_as::<(i64,)>().fetch_one(pool).await?;
+    Ok(total)
+}
@@ -0,0 +1,245 @@
+//! Copies of the database kept in a `backups` directory next to it.
+//!
+//! Snapshots are taken daily and weekly while the app runs, before migrating
+//! the schema, before restoring another snapshot and before repairing the
+//! database, and the oldest of each
+//! kind are deleted. They are written with `VACUUM INTO`, which copies a
+//! consistent state of the database while it is in use.
+
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::{DateTime, Local, Utc};
+use serde::Serialize;
+use sqlx::SqlitePool;
+
+use super::backup::{self, RestoreSummary};
+use super::{connect, migrations, Database};
+use crate::error::{Error, Result};
+
+/// Name of the directory holding the snapshots, next to the database.
+pub const DIRECTORY: &str = "backups";
+
+/// Label format of snapshots taken on demand, precise enough that restoring
+/// a snapshot never overwrites it with the one taken beforehand.
+const STAMP: &str = "%Y%m%dT%H%M%S%3f";
+
+/// How often the running app checks whether a snapshot is due.
+const INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SnapshotKind {
+    Daily,
+    Weekly,
+    PreMigration,
+    PreRestore,
+    PreRepair,
+}
+
+impl SnapshotKind {
+    const ALL: [Self; 5] = [
+        Self::Daily,
+        Self::Weekly,
+        Self::PreMigration,
+        Self::PreRestore,
+        Self::PreRepair,
+    ];
+
+    fn prefix(self) -> &'static str {
+        match self {
+            Self::Daily => "daily",
+            Self::Weekly => "weekly",
+            Self::PreMigration => "pre-migration",
+            Self::PreRestore => "pre-restore",
+            Self::PreRepair => "pre-repair",
+        }
+    }
+
+    /// How many snapshots of the kind are kept.
+    fn keep(self) -> usize {
+        match self {
+            Self::Daily => 7,
+            Self::Weekly => 4,
+            Self::PreMigration | Self::PreRestore | Self::PreRepair => 5,
+        }
+    }
+
+    /// File name of a snapshot, labels sorting in the order they were taken.
+    fn file_name(self, label: &str) -> String {
+        format!("{}-{label}.db", self.prefix())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Snapshot {
+    pub name: String,
+    pub kind: SnapshotKind,
+    pub taken_at: DateTime<Utc>,
+    /// Bytes.
+    pub size: u64,
+}
+
+/// Where the snapshots of the database at `path` are kept.
+pub fn directory(path: &Path) -> PathBuf {
+    path.with_file_name(DIRECTORY)
+}
+
+/// Snapshots in `dir`, most recent first.
+pub fn list(dir: &Path) -> Result<Vec<Snapshot>> {
+    let read_dir = match std::fs::read_dir(dir) {
+        Ok(read_dir) => read_dir,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+    let mut snapshots = Vec::new();
+    for item in read_dir {
+        let item = item?;
+        let name = item.file_name().to_string_lossy().into_owned();
+        let Some(stem) = name.strip_suffix(".db") else {
+            continue;
+        };
+        let Some(kind) = SnapshotKind::ALL
+            .into_iter()
+            .find(|kind| stem.starts_with(&format!("{}-", kind.prefix())))
+        else {
+            continue;
+        };
+        let metadata = item.metadata()?;
+        snapshots.push(Snapshot {
+            name,
+            kind,
+            taken_at: metadata.modified()?.into(),
+            size: metadata.len(),
+        });
+    }
+    snapshots.sort_by(|a, b| b.taken_at.cmp(&a.taken_at).then(b.name.cmp(&a.name)));
+    Ok(snapshots)
+}
+
+/// Takes today's and this week's snapshots if they are missing, then deletes
+/// those no longer kept.
+pub async fn rotate(pool: &SqlitePool, dir: &Path, now: DateTime<Local>) -> Result<()> {
+    let existing = list(dir)?;
+    let due = [
+        SnapshotKind::Daily.file_name(&now.format("%Y-%m-%d").to_string()),
+        SnapshotKind::Weekly.file_name(&now.format("%G-W%V").to_string()),
+    ];
+    for name in due {
+        if !existing.iter().any(|snapshot| snapshot.name == name) {
+            take(pool, dir, &name).await?;
+        }
+    }
+    prune(dir)
+}
+
+/// Keeps snapshots rotating for as long as the app runs.
+pub async fn schedule(pool: SqlitePool, dir: PathBuf) {
+    loop {
+        if let Err(err) = rotate(&pool, &dir, Local::now()).await {
+            eprintln!("timeloop: could not snapshot the database: {err}");
+        }
+        tokio::time::sleep(INTERVAL).await;
+    }
+}
+
+/// Snapshots a database still at schema `version` before it is migrated,
+/// up or down, and returns the snapshot's name.
+pub(crate) async fn before_migration(
+    pool: &SqlitePool,
+    dir: &Path,
+    version: i64,
+    now: DateTime<Local>,
+) -> Result<String> {
+    let label = format!("v{version}-{}", now.format(STAMP));
+    let name = SnapshotKind::PreMigration.file_name(&label);
+    take(pool, dir, &name).await?;
+    prune(dir)?;
+    Ok(name)
+}
+
+/// Snapshots the database before [`doctor::repair`](super::doctor::repair)
+/// changes it, and returns the snapshot's name.
+pub(crate) async fn before_repair(
+    pool: &SqlitePool,
+    dir: &Path,
+    now: DateTime<Local>,
+) -> Result<String> {
+    let name = SnapshotKind::PreRepair.file_name(&now.format(STAMP).to_string());
+    take(pool, dir, &name).await?;
+    prune(dir)?;
+    Ok(name)
+}
+
+/// Replaces the data of `database` with that of the snapshot `name`, after
+/// snapshotting the current data.
+///
+/// The snapshot is read through a migrated copy, so snapshots taken before a
+/// migration restore into the current schema.
+pub async fn restore(
+    database: &Database,
+    name: &str,
+    now: DateTime<Local>,
+) -> Result<RestoreSummary> {
+    let dir = directory(database.path());
+    if !list(&dir)?.iter().any(|snapshot| snapshot.name == name) {
+        return Err(Error::NotFound("backup"));
+    }
+    let stamp = now.format(STAMP).to_string();
+    take(
+        database.pool(),
+        &dir,
+        &SnapshotKind::PreRestore.file_name(&stamp),
+    )
+    .await?;
+
+    let copy = dir.join(format!("{name}.restoring"));
+    std::fs::copy(dir.join(name), &copy)?;
+    let data = read_migrated(&copy, now.to_utc()).await;
+    std::fs::remove_file(&copy)?;
+    let summary = backup::restore(database.pool(), &data?).await?;
+    prune(&dir)?;
+    Ok(summary)
+}
+
+async fn read_migrated(path: &Path, now: DateTime<Utc>) -> Result<backup::Backup> {
+    let pool = connect(path).await?;
+    let data = async {
+        migrations::run(&pool).await?;
+        backup::create(&pool, now).await
+    }
+    .await;
+    pool.close().await;
+    data
+}
+
+async fn take(pool: &SqlitePool, dir: &Path, name: &str) -> Result<()> {
+    std::fs::create_dir_all(dir)?;
+    // Written under another name first so an interrupted copy is never listed.
+    let partial = dir.join(format!("{name}.partial"));
+    match std::fs::remove_file(&partial) {
+        Err(err) if err.kind() != ErrorKind::NotFound => return Err(err.into()),
+        _ => {}
+    }
+    sqlx::query("VACUUM INTO $1")
+        .bind(partial.to_string_lossy())
+        .execute(pool)
+        .await?;
+    std::fs::rename(&partial, dir.join(name))?;
+    Ok(())
+}
+
+fn prune(dir: &Path) -> Result<()> {
+    let snapshots = list(dir)?;
+    for kind in SnapshotKind::ALL {
+        for snapshot in snapshots
+            .iter()
+            .filter(|snapshot| snapshot.kind == kind)
+            .skip(kind.keep())
+        {
+            std::fs::remove_file(dir.join(&snapshot.name))?;
+        }
+    }
+    Ok(())
+}
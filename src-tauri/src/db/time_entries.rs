@@ -0,0 +1,308 @@
+use chrono::{DateTime, FixedOffset, NaiveDateTime, TimeDelta, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{SqliteExecutor, SqlitePool};
+
+use super::overlaps::{self, OverlapPolicy};
+use super::{validate_date, TimeEntry, TimeEntryWithDetails};
+use crate::error::{Error, Result};
+
+/// When a time entry was worked, as read on the user's clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeRange {
+    pub started_at: DateTime<FixedOffset>,
+    pub ended_at: DateTime<FixedOffset>,
+}
+
+impl TimeRange {
+    /// Pairs optional bounds, which must be given together and in order.
+    pub fn from_bounds(
+        started_at: Option<DateTime<FixedOffset>>,
+        ended_at: Option<DateTime<FixedOffset>>,
+    ) -> Result<Option<Self>> {
+        match (started_at, ended_at) {
+            (Some(started_at), Some(ended_at)) if ended_at <= started_at => {
+                Err(Error::invalid("ended_at", "must be after started_at"))
+            }
+            (Some(started_at), Some(ended_at)) => Ok(Some(Self {
+                started_at,
+                ended_at,
+            })),
+            (None, None) => Ok(None),
+            _ => Err(Error::invalid(
+                "started_at",
+                "started_at and ended_at go together",
+            )),
+        }
+    }
+
+    /// Length of the range, rounded to the nearest minute.
+    pub fn minutes(&self) -> i64 {
+        ((self.ended_at - self.started_at).num_seconds() + 30) / 60
+    }
+
+    /// Minutes between the bounds as read on a wall clock, which differs
+    /// from [`minutes`](Self::minutes) when the offset changed in between.
+    fn wall_clock_minutes(&self) -> i64 {
+        ((self.ended_at.naive_local() - self.started_at.naive_local()).num_seconds() + 30) / 60
+    }
+}
+
+/// Most recent time entries first, with their entry and category.
+pub async fn list(pool: &SqlitePool, limit: i64, offset: i64) -> Result<Vec<TimeEntryWithDetails>> {
+    let time_entries = sqlx::query_as(
+        "
+        SELECT
+            te.*,
+            e.title AS entry_title,
+            c.name AS category_name,
+            c.color AS category_color
+        FROM time_entries te
+        JOIN entries e ON te.entry_id = e.id
+        LEFT JOIN categories c ON e.category_id = c.id
+        ORDER BY te.date DESC, te.created_at DESC
+        LIMIT $1 OFFSET $2
+        ",
+    )
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+    Ok(time_entries)
+}
+
+/// Time entries dated between the two dates, inclusive, in the order they
+/// were worked.
+pub async fn list_for_period(
+    pool: &SqlitePool,
+    start_date: &str,
+    end_date: &str,
+) -> Result<Vec<TimeEntryWithDetails>> {
+    validate_date("start_date", start_date)?;
+    validate_date("end_date", end_date)?;
+    let time_entries = sqlx::query_as(
+        "
+        SELECT
+            te.*,
+            e.title AS entry_title,
+            c.name AS category_name,
+            c.color AS category_color
+        FROM time_entries te
+        JOIN entries e ON te.entry_id = e.id
+        LEFT JOIN categories c ON e.category_id = c.id
+        WHERE te.date >= $1 AND te.date <= $2
+        ORDER BY te.date, te.started_at, te.created_at, te.id
+        ",
+    )
+    .bind(start_date)
+    .bind(end_date)
+    .fetch_all(pool)
+    .await?;
+    Ok(time_entries)
+}
+
+pub async fn list_for_entry(pool: &SqlitePool, entry_id: i64) -> Result<Vec<TimeEntry>> {
+    let time_entries =
+        sqlx::query_as("SELECT * FROM time_entries WHERE entry_id = $1 ORDER BY date DESC")
+            .bind(entry_id)
+            .fetch_all(pool)
+            .await?;
+    Ok(time_entries)
+}
+
+pub async fn create(
+    pool: &SqlitePool,
+    entry_id: i64,
+    duration: i64,
+    date: &str,
+    note: Option<&str>,
+    range: Option<TimeRange>,
+    overlap: OverlapPolicy,
+) -> Result<i64> {
+    let duration = validate(duration, date, range)?;
+    let mut tx = pool.begin().await?;
+    overlaps::enforce(&mut *tx, overlap, range, None).await?;
+    let id = insert(&mut *tx, entry_id, duration, date, note, range).await?;
+    tx.commit().await?;
+    Ok(id)
+}
+
+/// Inserts a time entry without looking for overlaps, for callers that log
+/// time as part of a larger change.
+pub async fn insert(
+    executor: impl SqliteExecutor<'_>,
+    entry_id: i64,
+    duration: i64,
+    date: &str,
+    note: Option<&str>,
+    range: Option<TimeRange>,
+) -> Result<i64> {
+    let duration = validate(duration, date, range)?;
+    let (started_at, ended_at, utc_offset) = range_columns(range);
+    let result = sqlx::query(
+        "
+        INSERT INTO time_entries (entry_id, duration, date, note, started_at, ended_at, utc_offset)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        ",
+    )
+    .bind(entry_id)
+    .bind(duration)
+    .bind(date)
+    .bind(note)
+    .bind(started_at)
+    .bind(ended_at)
+    .bind(utc_offset)
+    .execute(executor)
+    .await?;
+    Ok(result.last_insert_rowid())
+}
+
+/// Replaces the fields of a time entry; a `None` range clears the stored one.
+/// An estimated range stays so only while it is left as it was.
+pub async fn update(
+    pool: &SqlitePool,
+    id: i64,
+    duration: i64,
+    date: &str,
+    note: Option<&str>,
+    range: Option<TimeRange>,
+    overlap: OverlapPolicy,
+) -> Result<()> {
+    let duration = validate(duration, date, range)?;
+    let mut tx = pool.begin().await?;
+    overlaps::enforce(&mut *tx, overlap, range, Some(id)).await?;
+    let (started_at, ended_at, utc_offset) = range_columns(range);
+    let result = sqlx::query(
+        "
+        UPDATE time_entries
+        SET
+            duration = $1, date = $2, note = $3, started_at = $4, ended_at = $5, utc_offset = $6,
+            range_estimated = range_estimated AND started_at IS $4 AND ended_at IS $5
+        WHERE id = $7
+        ",
+    )
+    .bind(duration)
+    .bind(date)
+    .bind(note)
+    .bind(started_at)
+    .bind(ended_at)
+    .bind(utc_offset)
+    .bind(id)
+    .execute(&mut *tx)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(Error::NotFound("time entry"));
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+pub async fn delete(pool: &SqlitePool, id: i64) -> Result<()> {
+    sqlx::query("DELETE FROM time_entries WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Gives a range to time entries logged before ranges were stored.
+///
+/// This is an estimate: the range is taken to end when the row was created,
+/// and is only set when it then falls within the entry's `date` on the clock
+/// of `timezone`, with the offset it had at the time. Estimated ranges are
+/// flagged as such and never count as overlapping. Returns the number of rows
+/// updated.
+pub async fn backfill_ranges<Tz: TimeZone>(pool: &SqlitePool, timezone: &Tz) -> Result<u64> {
+    let rows: Vec<(i64, i64, String, String)> = sqlx::query_as(
+        "
+        SELECT id, duration, date, created_at FROM time_entries
+        WHERE started_at IS NULL AND created_at IS NOT NULL
+        ",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut tx = pool.begin().await?;
+    let mut updated = 0;
+    for (id, duration, date, created_at) in rows {
+        let Ok(created_at) = NaiveDateTime::parse_from_str(&created_at, "%Y-%m-%d %H:%M:%S") else {
+            continue;
+        };
+        let ended_at = created_at.and_utc().with_timezone(timezone).fixed_offset();
+        let range = TimeRange {
+            started_at: ended_at - TimeDelta::minutes(duration),
+            ended_at,
+        };
+        if validate(duration, &date, Some(range)).is_err() {
+            continue;
+        }
+        let (started_at, ended_at, utc_offset) = range_columns(Some(range));
+        sqlx::query(
+            "
+            UPDATE time_entries
+            SET started_at = $1, ended_at = $2, utc_offset = $3, range_estimated = 1
+            WHERE id = $4
+            ",
+        )
+        .bind(started_at)
+        .bind(ended_at)
+        .bind(utc_offset)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        updated += 1;
+    }
+    tx.commit().await?;
+    Ok(updated)
+}
+
+/// Checks a time entry and returns the duration to store, which is the
+/// range's when there is one.
+///
+/// The duration given may be read on a wall clock, as when typed as two
+/// times of day: over a change to or from daylight saving time it is then an
+/// hour off from the time that went by.
+fn validate(duration: i64, date: &str, range: Option<TimeRange>) -> Result<i64> {
+    if duration <= 0 {
+        return Err(Error::invalid(
+            "duration",
+            "must be a positive number of minutes",
+        ));
+    }
+    validate_date("date", date)?;
+    let Some(range) = range else {
+        return Ok(duration);
+    };
+    if range.ended_at <= range.started_at {
+        return Err(Error::invalid("ended_at", "must be after started_at"));
+    }
+    if duration != range.minutes() && duration != range.wall_clock_minutes() {
+        return Err(Error::invalid(
+            "duration",
+            format!(
+                "{duration} minutes does not match a {} minute range",
+                range.minutes()
+            ),
+        ));
+    }
+    if range.started_at.date_naive().to_string() != date {
+        return Err(Error::invalid(
+            "date",
+            "must be the day started_at falls on",
+        ));
+    }
+    Ok(range.minutes())
+}
+
+/// Values of `started_at`, `ended_at` and `utc_offset` (minutes) for `range`.
+fn range_columns(
+    range: Option<TimeRange>,
+) -> (Option<DateTime<Utc>>, Option<DateTime<Utc>>, Option<i32>) {
+    match range {
+        Some(range) => (
+            Some(range.started_at.to_utc()),
+            Some(range.ended_at.to_utc()),
+            Some(range.started_at.offset().local_minus_utc() / 60),
+        ),
+        None => (None, None, None),
+    }
+}
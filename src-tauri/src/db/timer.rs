@@ -0,0 +1,287 @@
+//! The running timer, persisted in `active_timers` so it keeps counting while
+//! the app is closed.
+//!
+//! There is at most one timer. Its elapsed time is the sum of the finished
+//! running segments (`accumulated_seconds`) and of the current one, which
+//! started at `resumed_at`; a paused timer has no `resumed_at`.
+//!
+//! While it runs, the machine going idle is recorded as `idle_since`, and
+//! coming back as `idle_until`, until the user decides what to do with that
+//! span with [`resolve_idle`]. Pausing or stopping the timer drops it.
+
+use chrono::{DateTime, Local, SubsecRound, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, SqliteConnection, SqlitePool};
+
+use super::{time_entries, TimeEntry, TimeRange};
+use crate::error::{Error, Result};
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct ActiveTimer {
+    pub entry_id: i64,
+    pub entry_title: String,
+    pub note: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub resumed_at: Option<DateTime<Utc>>,
+    pub accumulated_seconds: i64,
+    /// Start of the idle span being recorded or waiting for an answer.
+    pub idle_since: Option<DateTime<Utc>>,
+    /// End of the idle span, set once the user is back.
+    pub idle_until: Option<DateTime<Utc>>,
+}
+
+impl ActiveTimer {
+    pub fn is_running(&self) -> bool {
+        self.resumed_at.is_some()
+    }
+
+    /// Whether the timer has run without a pause since it was started.
+    pub fn is_uninterrupted(&self) -> bool {
+        self.accumulated_seconds == 0 && self.resumed_at == Some(self.started_at)
+    }
+
+    /// Seconds counted so far, excluding pauses.
+    pub fn elapsed_seconds(&self, now: DateTime<Utc>) -> i64 {
+        let current = self
+            .resumed_at
+            .map_or(0, |resumed_at| (now - resumed_at).num_seconds().max(0));
+        self.accumulated_seconds + current
+    }
+
+    /// Seconds counted between `since` and `now`. Only the total of the
+    /// segments before the current one is kept, so they are taken to have
+    /// run from the start, as early as they can have.
+    pub fn elapsed_seconds_since(&self, since: DateTime<Utc>, now: DateTime<Utc>) -> i64 {
+        let before = (since - self.started_at).num_seconds().max(0);
+        let accumulated = (self.accumulated_seconds - before).max(0);
+        let current = self.resumed_at.map_or(0, |resumed_at| {
+            (now - resumed_at.max(since)).num_seconds().max(0)
+        });
+        accumulated + current
+    }
+}
+
+pub async fn get(pool: &SqlitePool) -> Result<Option<ActiveTimer>> {
+    let mut conn = pool.acquire().await?;
+    fetch(&mut conn).await
+}
+
+/// Starts a timer on `entry_id`, stopping the one already running if any.
+///
+/// Returns the time entry logged for the replaced timer.
+pub async fn start(
+    pool: &SqlitePool,
+    entry_id: i64,
+    note: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<Option<i64>> {
+    let mut tx = pool.begin().await?;
+    let logged = stop_in(&mut tx, now).await?.map(|logged| logged.id);
+    sqlx::query(
+        "INSERT INTO active_timers (id, entry_id, note, started_at, resumed_at)
+         VALUES (1, $1, $2, $3, $3)",
+    )
+    .bind(entry_id)
+    .bind(note)
+    .bind(now)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(logged)
+}
+
+/// Pauses the timer. Pausing a paused timer does nothing.
+pub async fn pause(pool: &SqlitePool, now: DateTime<Utc>) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    let timer = fetch(&mut tx).await?.ok_or(Error::NotFound("timer"))?;
+    if timer.is_running() {
+        sqlx::query(
+            "UPDATE active_timers
+             SET resumed_at = NULL, accumulated_seconds = $1, idle_since = NULL, idle_until = NULL
+             WHERE id = 1",
+        )
+        .bind(timer.elapsed_seconds(now))
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Resumes a paused timer. Resuming a running timer does nothing.
+pub async fn resume(pool: &SqlitePool, now: DateTime<Utc>) -> Result<()> {
+    let result =
+        sqlx::query("UPDATE active_timers SET resumed_at = COALESCE(resumed_at, $1) WHERE id = 1")
+            .bind(now)
+            .execute(pool)
+            .await?;
+    if result.rows_affected() == 0 {
+        return Err(Error::NotFound("timer"));
+    }
+    Ok(())
+}
+
+/// Stops the timer and logs its elapsed time, rounded to the minute, on the
+/// local day it was started. An uninterrupted timer also records its range.
+///
+/// Returns the logged time entry, read in the same transaction, or `None`
+/// when there was no timer or it ran for less than half a minute.
+pub async fn stop(pool: &SqlitePool, now: DateTime<Utc>) -> Result<Option<TimeEntry>> {
+    let mut tx = pool.begin().await?;
+    let logged = stop_in(&mut tx, now).await?;
+    tx.commit().await?;
+    Ok(logged)
+}
+
+/// What to do with the idle span of the timer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum IdleResolution {
+    /// Count it on the timer as if the user had been working.
+    Keep,
+    /// Take it off the timer.
+    Discard,
+    /// Take it off the timer and log it on another entry.
+    Reassign { entry_id: i64 },
+}
+
+/// Records that the running timer went idle at `since`, unless it already
+/// has an idle span. Returns whether it was recorded.
+pub async fn mark_idle(pool: &SqlitePool, since: DateTime<Utc>) -> Result<bool> {
+    let result = sqlx::query(
+        "UPDATE active_timers SET idle_since = $1
+         WHERE id = 1 AND resumed_at IS NOT NULL AND idle_since IS NULL",
+    )
+    .bind(since)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Closes the idle span at `until`, the user's last input, leaving it for
+/// [`resolve_idle`]. Returns whether there was an open span.
+pub async fn mark_returned(pool: &SqlitePool, until: DateTime<Utc>) -> Result<bool> {
+    let result = sqlx::query(
+        "UPDATE active_timers SET idle_until = $1
+         WHERE id = 1 AND idle_since IS NOT NULL AND idle_until IS NULL",
+    )
+    .bind(until)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Keeps, discards or reassigns the closed idle span of the timer.
+///
+/// Returns the time entry logged when reassigning, none when the span was
+/// shorter than half a minute.
+pub async fn resolve_idle(pool: &SqlitePool, resolution: IdleResolution) -> Result<Option<i64>> {
+    let mut tx = pool.begin().await?;
+    let timer = fetch(&mut tx).await?.ok_or(Error::NotFound("timer"))?;
+    let (Some(since), Some(until)) = (timer.idle_since, timer.idle_until) else {
+        return Err(Error::NotFound("idle time"));
+    };
+
+    let mut logged = None;
+    if resolution != IdleResolution::Keep {
+        // The timer runs, as pausing drops the span: split its current
+        // segment around the span.
+        let resumed_at = timer.resumed_at.unwrap_or(since).min(since);
+        sqlx::query(
+            "UPDATE active_timers SET accumulated_seconds = $1, resumed_at = $2 WHERE id = 1",
+        )
+        .bind(timer.accumulated_seconds + (since - resumed_at).num_seconds())
+        .bind(until)
+        .execute(&mut *tx)
+        .await?;
+    }
+    if let IdleResolution::Reassign { entry_id } = resolution {
+        let exists: Option<i64> = sqlx::query_scalar("SELECT id FROM entries WHERE id = $1")
+            .bind(entry_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+        exists.ok_or(Error::NotFound("entry"))?;
+        let range = TimeRange {
+            started_at: since.trunc_subsecs(0).with_timezone(&Local).fixed_offset(),
+            ended_at: until.trunc_subsecs(0).with_timezone(&Local).fixed_offset(),
+        };
+        if range.minutes() > 0 {
+            let date = range.started_at.date_naive().to_string();
+            let id = time_entries::insert(
+                &mut *tx,
+                entry_id,
+                range.minutes(),
+                &date,
+                None,
+                Some(range),
+            )
+            .await?;
+            logged = Some(id);
+        }
+    }
+    sqlx::query("UPDATE active_timers SET idle_since = NULL, idle_until = NULL WHERE id = 1")
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(logged)
+}
+
+async fn fetch(conn: &mut SqliteConnection) -> Result<Option<ActiveTimer>> {
+    let timer = sqlx::query_as(
+        "
+        SELECT t.entry_id, e.title AS entry_title, t.note, t.started_at, t.resumed_at,
+            t.accumulated_seconds, t.idle_since, t.idle_until
+        FROM active_timers t
+        JOIN entries e ON t.entry_id = e.id
+        WHERE t.id = 1
+        ",
+    )
+    .fetch_optional(conn)
+    .await?;
+    Ok(timer)
+}
+
+async fn stop_in(conn: &mut SqliteConnection, now: DateTime<Utc>) -> Result<Option<TimeEntry>> {
+    let Some(timer) = fetch(conn).await? else {
+        return Ok(None);
+    };
+    sqlx::query("DELETE FROM active_timers WHERE id = 1")
+        .execute(&mut *conn)
+        .await?;
+
+    let (minutes, range) = if timer.is_uninterrupted() {
+        let range = TimeRange {
+            started_at: timer
+                .started_at
+                .trunc_subsecs(0)
+                .with_timezone(&Local)
+                .fixed_offset(),
+            ended_at: now.trunc_subsecs(0).with_timezone(&Local).fixed_offset(),
+        };
+        (range.minutes(), Some(range))
+    } else {
+        ((timer.elapsed_seconds(now) + 30) / 60, None)
+    };
+    if minutes <= 0 {
+        return Ok(None);
+    }
+    let date = timer
+        .started_at
+        .with_timezone(&Local)
+        .date_naive()
+        .to_string();
+    let id = time_entries::insert(
+        &mut *conn,
+        timer.entry_id,
+        minutes,
+        &date,
+        timer.note.as_deref(),
+        range,
+    )
+    .await?;
+    let logged = sqlx::query_as("SELECT * FROM time_entries WHERE id = $1")
+        .bind(id)
+        .fetch_one(conn)
+        .await?;
+    Ok(Some(logged))
+}
@@ -0,0 +1,111 @@
+//! The `org.timeloop.Timeloop` service on the D-Bus session bus, for Linux
+//! desktops to drive the timer and show today's total.
+//!
+//! The object at `/org/timeloop/Timeloop` has the methods `StartTimer(s
+//! entry)`, `PauseTimer()`, `ResumeTimer()`, `StopTimer() → x minutes` and
+//! `TodayTotal() → (x minutes, x timer_seconds)`, answered like the control
+//! socket's, and emits `TimerChanged(s entry, b running)` whenever the timer
+//! changes, `entry` being empty once it is stopped.
+
+use std::sync::Arc;
+
+use chrono::Utc;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use sqlx::SqlitePool;
+use zbus::object_server::SignalEmitter;
+use zbus::{fdo, interface, Connection};
+
+use crate::db::timer;
+use crate::rpc::{self, Started, Stopped, TodayTotal};
+
+pub const NAME: &str = "org.timeloop.Timeloop";
+pub const PATH: &str = "/org/timeloop/Timeloop";
+
+struct Timeloop {
+    pool: SqlitePool,
+    on_change: Arc<dyn Fn() + Send + Sync>,
+}
+
+impl Timeloop {
+    async fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> fdo::Result<T> {
+        let result = rpc::dispatch(&self.pool, method, params, Utc::now())
+            .await
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+        if rpc::changes_timer(method) {
+            (self.on_change)();
+        }
+        serde_json::from_value(result).map_err(|err| fdo::Error::Failed(err.to_string()))
+    }
+}
+
+#[interface(name = "org.timeloop.Timeloop")]
+impl Timeloop {
+    /// Starts a timer on the entry titled `entry`, created if needed, and
+    /// returns its title.
+    async fn start_timer(&self, entry: &str) -> fdo::Result<String> {
+        let started: Started = self.call("timer.start", json!({ "title": entry })).await?;
+        Ok(started.entry_title)
+    }
+
+    async fn pause_timer(&self) -> fdo::Result<()> {
+        self.call::<Value>("timer.pause", Value::Null).await?;
+        Ok(())
+    }
+
+    async fn resume_timer(&self) -> fdo::Result<()> {
+        self.call::<Value>("timer.resume", Value::Null).await?;
+        Ok(())
+    }
+
+    /// Stops the timer and returns the minutes logged, 0 when it ran for
+    /// less than half a minute.
+    async fn stop_timer(&self) -> fdo::Result<i64> {
+        let stopped: Stopped = self.call("timer.stop", Value::Null).await?;
+        Ok(stopped.logged.unwrap_or(0))
+    }
+
+    #[zbus(out_args("minutes", "timer_seconds"))]
+    async fn today_total(&self) -> fdo::Result<(i64, i64)> {
+        let total: TodayTotal = self.call("today.total", Value::Null).await?;
+        Ok((total.minutes, total.timer_seconds))
+    }
+
+    #[zbus(signal)]
+    async fn timer_changed(
+        emitter: &SignalEmitter<'_>,
+        entry: &str,
+        running: bool,
+    ) -> zbus::Result<()>;
+}
+
+/// Serves the object on `connection` and takes the service name, calling
+/// `on_change` after each method that may have changed the timer.
+///
+/// The app passes its session bus connection; tests can pass one to a
+/// private `dbus-daemon`.
+pub async fn register(
+    connection: &Connection,
+    pool: SqlitePool,
+    on_change: Arc<dyn Fn() + Send + Sync>,
+) -> zbus::Result<()> {
+    connection
+        .object_server()
+        .at(PATH, Timeloop { pool, on_change })
+        .await?;
+    connection.request_name(NAME).await?;
+    Ok(())
+}
+
+/// Emits `TimerChanged` with the current state of the timer.
+pub async fn timer_changed(connection: &Connection, pool: &SqlitePool) -> zbus::Result<()> {
+    let timer = timer::get(pool)
+        .await
+        .map_err(|err| zbus::Error::Failure(err.to_string()))?;
+    let (entry, running) = timer.map_or((String::new(), false), |timer| {
+        let running = timer.is_running();
+        (timer.entry_title, running)
+    });
+    let emitter = SignalEmitter::new(connection, PATH)?;
+    Timeloop::timer_changed(&emitter, &entry, running).await
+}
@@ -0,0 +1,46 @@
+use serde::{Serialize, Serializer};
+
+/// Errors returned by the data layer and surfaced to the webview by commands.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Sql(#[from] sqlx::Error),
+    #[error(transparent)]
+    Migrate(#[from] sqlx::migrate::MigrateError),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("{0} not found")]
+    NotFound(&'static str),
+    #[error("overlaps time entries {0:?}")]
+    Overlap(Vec<i64>),
+    #[error("invalid {field}: {reason}")]
+    Invalid { field: &'static str, reason: String },
+    #[error(transparent)]
+    Csv(#[from] csv::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    /// A failure on one row of an imported file, numbered from 1.
+    #[error("row {row}: {source}")]
+    Row { row: usize, source: Box<Error> },
+    /// A failure reported by the running app to a client of its socket.
+    #[error("{0}")]
+    Remote(String),
+}
+
+impl Error {
+    pub(crate) fn invalid(field: &'static str, reason: impl Into<String>) -> Self {
+        Self::Invalid {
+            field,
+            reason: reason.into(),
+        }
+    }
+}
+
+// Commands reject with the error message as a plain string on the JS side.
+impl Serialize for Error {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -0,0 +1,50 @@
+//! Clockify detailed report exports, as CSV.
+//!
+//! The project, or the client when there is none, becomes the category and
+//! the description, or the task, the entry title. Tags are kept in the note.
+//! Times are read on the user's clock, with the date and time formats
+//! Clockify offers in its settings.
+
+use csv::ReaderBuilder;
+
+use super::{field, on_row, Header, RangeColumns, UNTITLED};
+use crate::db::imports::ImportRow;
+use crate::error::Result;
+
+pub const SOURCE: &str = "clockify";
+
+pub fn parse(contents: &str) -> Result<Vec<ImportRow>> {
+    let mut reader = ReaderBuilder::new().from_reader(contents.as_bytes());
+    let header = Header::new(reader.headers()?);
+    let project = header.find(&["Project"]);
+    let client = header.find(&["Client"]);
+    let description = header.find(&["Description"]);
+    let task = header.find(&["Task"]);
+    let tags = header.find(&["Tags"]);
+    let range = RangeColumns::require(&header)?;
+
+    let mut rows = Vec::new();
+    for (index, record) in reader.records().enumerate() {
+        let record = record?;
+        let range = range.read(&record).map_err(on_row(index))?;
+        let duration = range.minutes();
+        if duration <= 0 {
+            continue;
+        }
+        let category = field(&record, project).or_else(|| field(&record, client));
+        let title = field(&record, description)
+            .or_else(|| field(&record, task))
+            .or(category)
+            .unwrap_or(UNTITLED);
+        rows.push(ImportRow {
+            date: range.started_at.date_naive().to_string(),
+            duration,
+            entry_id: None,
+            entry_title: title.to_string(),
+            category_name: category.map(String::from),
+            note: field(&record, tags).map(|tags| format!("Tags: {tags}")),
+            range: Some(range),
+        });
+    }
+    Ok(rows)
+}
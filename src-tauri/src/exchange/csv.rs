@@ -0,0 +1,162 @@
+//! Time entries as CSV, one row per time entry naming its entry and category.
+//!
+//! Exports have the columns `date,start,end,duration,entry,category,note`,
+//! with `duration` in minutes and `start`/`end` in RFC 3339 when the range is
+//! known. Imports read the same columns by default, or any others named in
+//! [`CsvColumns`].
+
+use chrono::DateTime;
+use csv::{ReaderBuilder, Writer};
+use serde::Deserialize;
+
+use super::{field, on_row, parse_minutes, Header};
+use crate::db::imports::ImportRow;
+use crate::db::{TimeEntryWithDetails, TimeRange};
+use crate::error::{Error, Result};
+
+pub const SOURCE: &str = "csv";
+
+const HEADER: [&str; 7] = [
+    "date", "start", "end", "duration", "entry", "category", "note",
+];
+
+/// Header of the column holding each field of an imported row.
+///
+/// `date` may be missing when `start` is given, and `duration` when both
+/// `start` and `end` are.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CsvColumns {
+    pub date: String,
+    pub start: String,
+    pub end: String,
+    pub duration: String,
+    pub entry: String,
+    pub category: String,
+    pub note: String,
+}
+
+impl Default for CsvColumns {
+    fn default() -> Self {
+        let [date, start, end, duration, entry, category, note] = HEADER.map(String::from);
+        Self {
+            date,
+            start,
+            end,
+            duration,
+            entry,
+            category,
+            note,
+        }
+    }
+}
+
+pub fn export(time_entries: &[TimeEntryWithDetails]) -> Result<String> {
+    let mut writer = Writer::from_writer(Vec::new());
+    writer.write_record(HEADER)?;
+    for te in time_entries {
+        let range = te.time_entry.range();
+        writer.write_record([
+            te.time_entry.date.clone(),
+            range.map_or_else(String::new, |range| range.started_at.to_rfc3339()),
+            range.map_or_else(String::new, |range| range.ended_at.to_rfc3339()),
+            te.time_entry.duration.to_string(),
+            te.entry_title.clone(),
+            te.category_name.clone().unwrap_or_default(),
+            te.time_entry.note.clone().unwrap_or_default(),
+        ])?;
+    }
+    let bytes = writer.into_inner().map_err(|err| err.into_error())?;
+    Ok(String::from_utf8(bytes).expect("CSV written from strings is UTF-8"))
+}
+
+/// Reads the rows of `contents`, whose first line is a header.
+pub fn parse(contents: &str, columns: &CsvColumns) -> Result<Vec<ImportRow>> {
+    let mut reader = ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(contents.as_bytes());
+    let header = Header::new(reader.headers()?);
+    let find = |name: &str| header.find(&[name.trim()]);
+    let required = |name: &str| header.require(&[name.trim()]);
+    let entry = required(&columns.entry)?;
+    let date = find(&columns.date);
+    let start = find(&columns.start);
+    let end = find(&columns.end);
+    let duration = find(&columns.duration);
+    let category = find(&columns.category);
+    let note = find(&columns.note);
+    if date.is_none() && start.is_none() {
+        required(&columns.date)?;
+    }
+    if duration.is_none() && (start.is_none() || end.is_none()) {
+        required(&columns.duration)?;
+    }
+
+    let mut rows = Vec::new();
+    for (index, record) in reader.records().enumerate() {
+        let record = record?;
+        let field = |column| field(&record, column);
+        let row = read_row(
+            field(Some(entry)),
+            field(date),
+            (field(start), field(end)),
+            field(duration),
+        )
+        .map(|row| ImportRow {
+            category_name: field(category).map(String::from),
+            note: field(note).map(String::from),
+            ..row
+        })
+        .map_err(on_row(index))?;
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+fn read_row(
+    entry: Option<&str>,
+    date: Option<&str>,
+    (start, end): (Option<&str>, Option<&str>),
+    duration: Option<&str>,
+) -> Result<ImportRow> {
+    let timestamp = |field: &'static str, value: Option<&str>| {
+        value
+            .map(|value| {
+                DateTime::parse_from_rfc3339(value).map_err(|_| {
+                    Error::invalid(
+                        field,
+                        format!("expected an RFC 3339 timestamp, got {value:?}"),
+                    )
+                })
+            })
+            .transpose()
+    };
+    let range = TimeRange::from_bounds(timestamp("start", start)?, timestamp("end", end)?)?;
+    let date = match (date, range) {
+        (Some(date), _) => date.to_string(),
+        (None, Some(range)) => range.started_at.date_naive().to_string(),
+        (None, None) => return Err(Error::invalid("date", "must not be empty")),
+    };
+    let duration = match (duration, range) {
+        (Some(duration), _) => parse_minutes(duration).ok_or_else(|| {
+            Error::invalid(
+                "duration",
+                format!("expected minutes or H:MM, got {duration:?}"),
+            )
+        })?,
+        (None, Some(range)) => range.minutes(),
+        (None, None) => return Err(Error::invalid("duration", "must not be empty")),
+    };
+    let entry_title = entry
+        .ok_or_else(|| Error::invalid("entry", "must not be empty"))?
+        .to_string();
+    Ok(ImportRow {
+        date,
+        duration,
+        entry_id: None,
+        entry_title,
+        category_name: None,
+        note: None,
+        range,
+    })
+}
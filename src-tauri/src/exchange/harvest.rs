@@ -0,0 +1,54 @@
+//! Harvest detailed time report exports, as CSV.
+//!
+//! Harvest logs hours per day without start and end times. The project, or
+//! the client when there is none, becomes the category, the task the entry
+//! title and the notes the note.
+
+use csv::ReaderBuilder;
+
+use super::{field, on_row, parse_date, parse_hours, Header};
+use crate::db::imports::ImportRow;
+use crate::error::{Error, Result};
+
+pub const SOURCE: &str = "harvest";
+
+pub fn parse(contents: &str) -> Result<Vec<ImportRow>> {
+    let mut reader = ReaderBuilder::new().from_reader(contents.as_bytes());
+    let header = Header::new(reader.headers()?);
+    let date = header.require(&["Date", "Spent Date"])?;
+    let hours = header.require(&["Hours"])?;
+    let client = header.find(&["Client"]);
+    let project = header.find(&["Project"]);
+    let task = header.find(&["Task"]);
+    let notes = header.find(&["Notes"]);
+
+    let mut rows = Vec::new();
+    for (index, record) in reader.records().enumerate() {
+        let record = record?;
+        let at_row = on_row(index);
+        let date =
+            parse_date("date", field(&record, Some(date)).unwrap_or_default()).map_err(&at_row)?;
+        let text = field(&record, Some(hours)).unwrap_or_default();
+        let duration = parse_hours(text)
+            .ok_or_else(|| Error::invalid("hours", format!("expected hours, got {text:?}")))
+            .map_err(&at_row)?;
+        if duration <= 0 {
+            continue;
+        }
+        let category = field(&record, project).or_else(|| field(&record, client));
+        let title = field(&record, task)
+            .or(category)
+            .ok_or_else(|| Error::invalid("task", "must not be empty"))
+            .map_err(&at_row)?;
+        rows.push(ImportRow {
+            date: date.to_string(),
+            duration,
+            entry_id: None,
+            entry_title: title.to_string(),
+            category_name: category.map(String::from),
+            note: field(&record, notes).map(String::from),
+            range: None,
+        });
+    }
+    Ok(rows)
+}
@@ -0,0 +1,657 @@
+//! iCalendar (RFC 5545) files: time entries exported to overlay logged time
+//! on a calendar, and calendar events read to log the time they took.
+//!
+//! Each time entry is a `VEVENT` summarized by its entry title, with the
+//! category in `CATEGORIES` and the note as the description. Time entries
+//! without a range become all-day events on their date, the logged duration
+//! heading the description.
+//!
+//! Reading, events keep the times of their `TZID`, read on the user's clock
+//! when the zone is unknown or not given. Daily and weekly recurrences are
+//! expanded, with their exceptions; other recurrence rules only yield the
+//! first occurrence. All-day and cancelled events are left out.
+
+use std::collections::HashSet;
+
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, Local, NaiveDate, NaiveDateTime, TimeZone, Utc,
+    Weekday,
+};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+use super::on_row;
+use crate::db::imports::ImportRow;
+use crate::db::{TimeEntryWithDetails, TimeRange};
+use crate::error::{Error, Result};
+
+pub const SOURCE: &str = "ical";
+
+const UNTITLED: &str = "(no title)";
+const PRODUCT: &str = "-//Timeloop//Timeloop//EN";
+const TIMESTAMP: &str = "%Y%m%dT%H%M%SZ";
+
+/// Content lines longer than this many bytes are folded.
+const LINE_LENGTH: usize = 75;
+
+/// A calendar with one event per time entry, stamped with `now`.
+pub fn export(time_entries: &[TimeEntryWithDetails], now: DateTime<Utc>) -> Result<String> {
+    let mut calendar = String::new();
+    let mut line = |property: &str, value: &str| {
+        fold(&format!("{property}:{value}"), &mut calendar);
+    };
+    line("BEGIN", "VCALENDAR");
+    line("VERSION", "2.0");
+    line("PRODID", PRODUCT);
+    line("CALSCALE", "GREGORIAN");
+    for te in time_entries {
+        let time_entry = &te.time_entry;
+        line("BEGIN", "VEVENT");
+        line("UID", &format!("time-entry-{}@timeloop", time_entry.id));
+        line("DTSTAMP", &now.format(TIMESTAMP).to_string());
+        let mut description = Vec::new();
+        match (time_entry.started_at, time_entry.ended_at) {
+            (Some(started_at), Some(ended_at)) => {
+                line("DTSTART", &started_at.format(TIMESTAMP).to_string());
+                line("DTEND", &ended_at.format(TIMESTAMP).to_string());
+            }
+            _ => {
+                let date = NaiveDate::parse_from_str(&time_entry.date, "%Y-%m-%d")
+                    .map_err(|_| Error::invalid("date", "expected YYYY-MM-DD"))?;
+                let next = date + Duration::days(1);
+                line("DTSTART;VALUE=DATE", &date.format("%Y%m%d").to_string());
+                line("DTEND;VALUE=DATE", &next.format("%Y%m%d").to_string());
+                // All-day events are shown as free rather than blocking the day.
+                line("TRANSP", "TRANSPARENT");
+                description.push(format!("Duration: {}", duration(time_entry.duration)));
+            }
+        }
+        line("SUMMARY", &escape(&te.entry_title));
+        if let Some(category) = &te.category_name {
+            line("CATEGORIES", &escape(category));
+        }
+        description.extend(time_entry.note.clone().filter(|note| !note.is_empty()));
+        if !description.is_empty() {
+            line("DESCRIPTION", &escape(&description.join("\n\n")));
+        }
+        line("END", "VEVENT");
+    }
+    line("END", "VCALENDAR");
+    Ok(calendar)
+}
+
+/// `1h 30m`, `45m` or `2h`.
+fn duration(minutes: i64) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, minutes) => format!("{minutes}m"),
+        (hours, 0) => format!("{hours}h"),
+        (hours, minutes) => format!("{hours}h {minutes}m"),
+    }
+}
+
+/// A TEXT value, with its backslashes, separators and line breaks escaped.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Appends `line` ending in CRLF, continued on lines starting with a space
+/// wherever it would exceed [`LINE_LENGTH`], never within a character.
+fn fold(line: &str, out: &mut String) {
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > LINE_LENGTH {
+            out.push_str("\r\n ");
+            length = 1;
+        }
+        out.push(c);
+        length += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+/// An occurrence of a calendar event, on the user's clock.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalendarEvent {
+    pub uid: Option<String>,
+    pub summary: String,
+    pub description: Option<String>,
+    pub location: Option<String>,
+    pub started_at: DateTime<FixedOffset>,
+    pub ended_at: DateTime<FixedOffset>,
+    /// Minutes.
+    pub duration: i64,
+}
+
+/// A calendar event to log, and the entry to log it on.
+#[derive(Debug, Clone, Deserialize)]
+pub struct EventSelection {
+    #[serde(flatten)]
+    pub event: CalendarEvent,
+    /// Existing entry to log the event on.
+    pub entry_id: Option<i64>,
+    /// Otherwise the title of the entry to log it on, found or created, the
+    /// event summary by default.
+    pub entry_title: Option<String>,
+    /// Category of the entry when it is created.
+    pub category_name: Option<String>,
+}
+
+impl EventSelection {
+    /// The time entry to import. Logged on another entry than its summary,
+    /// the event keeps its summary as the note.
+    pub fn row(&self) -> ImportRow {
+        let event = &self.event;
+        let entry_title = self
+            .entry_title
+            .as_deref()
+            .map(str::trim)
+            .filter(|title| !title.is_empty())
+            .unwrap_or(&event.summary)
+            .to_string();
+        let on_summary =
+            self.entry_id.is_none() && entry_title.eq_ignore_ascii_case(&event.summary);
+        ImportRow {
+            date: event.started_at.date_naive().to_string(),
+            duration: event.duration,
+            entry_id: self.entry_id,
+            entry_title,
+            category_name: self.category_name.clone(),
+            note: (!on_summary).then(|| event.summary.clone()),
+            range: Some(TimeRange {
+                started_at: event.started_at,
+                ended_at: event.ended_at,
+            }),
+        }
+    }
+}
+
+/// Occurrences of the events of a calendar starting between the two dates,
+/// inclusive, in the order they start.
+pub fn events(contents: &str, start_date: &str, end_date: &str) -> Result<Vec<CalendarEvent>> {
+    let date = |field, date: &str| {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| Error::invalid(field, format!("expected YYYY-MM-DD, got {date:?}")))
+    };
+    let (start_date, end_date) = (date("start_date", start_date)?, date("end_date", end_date)?);
+    let components = read_events(contents)?;
+    // Occurrences moved or changed individually, replacing the generated ones.
+    let overrides: HashSet<(Option<&str>, DateTime<Utc>)> = components
+        .iter()
+        .filter_map(|component| Some((component.uid.as_deref(), component.recurrence_id?)))
+        .collect();
+
+    let mut events = Vec::new();
+    for component in &components {
+        if component.cancelled || component.all_day {
+            continue;
+        }
+        let Some(start) = component.start else {
+            continue;
+        };
+        let length = match (component.end, component.length) {
+            (Some(end), _) => end
+                .instant()
+                .zip(start.instant())
+                .map(|(end, start)| end - start),
+            (None, length) => length,
+        }
+        .unwrap_or_default();
+        if length <= Duration::zero() {
+            continue;
+        }
+        let occurrences = match &component.rule {
+            Some(rule) if component.recurrence_id.is_none() => {
+                rule.occurrences(start.naive, end_date)
+            }
+            _ => vec![start.naive],
+        };
+        for naive in occurrences {
+            let Some(started_at) = start.at(naive) else {
+                continue;
+            };
+            let date = started_at.with_timezone(&Local).date_naive();
+            if date > end_date {
+                break;
+            }
+            let instant = started_at.to_utc();
+            if date < start_date
+                || component.excluded.contains(&instant)
+                || (component.recurrence_id.is_none()
+                    && overrides.contains(&(component.uid.as_deref(), instant)))
+            {
+                continue;
+            }
+            let started_at = started_at.with_timezone(&Local).fixed_offset();
+            let ended_at = (started_at + length).with_timezone(&Local).fixed_offset();
+            events.push(CalendarEvent {
+                uid: component.uid.clone(),
+                summary: component.summary.clone(),
+                description: component.description.clone(),
+                location: component.location.clone(),
+                started_at,
+                ended_at,
+                duration: TimeRange {
+                    started_at,
+                    ended_at,
+                }
+                .minutes(),
+            });
+        }
+    }
+    events.sort_by_key(|event| event.started_at);
+    Ok(events)
+}
+
+/// The properties of a `VEVENT` needed to list its occurrences.
+#[derive(Default)]
+struct Component {
+    uid: Option<String>,
+    summary: String,
+    description: Option<String>,
+    location: Option<String>,
+    start: Option<Time>,
+    end: Option<Time>,
+    length: Option<Duration>,
+    all_day: bool,
+    cancelled: bool,
+    rule: Option<Rule>,
+    excluded: HashSet<DateTime<Utc>>,
+    recurrence_id: Option<DateTime<Utc>>,
+}
+
+fn read_events(contents: &str) -> Result<Vec<Component>> {
+    let mut components = Vec::new();
+    // Components being read, innermost last; properties only count in a
+    // `VEVENT` itself, not in its alarms.
+    let mut open: Vec<String> = Vec::new();
+    let mut event = Component::default();
+    for (index, line) in unfold(contents).into_iter().enumerate() {
+        let at_line = on_row(index);
+        let Some(property) = Property::parse(&line) else {
+            continue;
+        };
+        match property.name.as_str() {
+            "BEGIN" => {
+                if property.value.eq_ignore_ascii_case("VEVENT") {
+                    event = Component::default();
+                }
+                open.push(property.value.to_ascii_uppercase());
+                continue;
+            }
+            "END" => {
+                if open.pop().as_deref() == Some("VEVENT") {
+                    components.push(std::mem::take(&mut event));
+                }
+                continue;
+            }
+            _ if open.last().map(String::as_str) != Some("VEVENT") => continue,
+            _ => {}
+        }
+        match property.name.as_str() {
+            "UID" => event.uid = Some(property.value),
+            "SUMMARY" => event.summary = unescape(&property.value),
+            "DESCRIPTION" => event.description = Some(unescape(&property.value)),
+            "LOCATION" => event.location = Some(unescape(&property.value)),
+            "STATUS" => event.cancelled = property.value.eq_ignore_ascii_case("CANCELLED"),
+            "DTSTART" => {
+                let start = Time::parse(&property).map_err(&at_line)?;
+                event.all_day = start.all_day;
+                event.start = Some(start);
+            }
+            "DTEND" => event.end = Some(Time::parse(&property).map_err(&at_line)?),
+            "DURATION" => event.length = Some(parse_duration(&property.value).map_err(&at_line)?),
+            "RRULE" => event.rule = Some(Rule::parse(&property.value).map_err(&at_line)?),
+            "EXDATE" => {
+                for value in property.value.split(',') {
+                    let time = Time::parse(&Property {
+                        value: value.to_string(),
+                        ..property.clone()
+                    })
+                    .map_err(&at_line)?;
+                    event.excluded.extend(time.instant());
+                }
+            }
+            "RECURRENCE-ID" => {
+                event.recurrence_id = Time::parse(&property).map_err(&at_line)?.instant();
+            }
+            _ => {}
+        }
+    }
+    Ok(components
+        .into_iter()
+        .map(|mut event| {
+            if event.summary.trim().is_empty() {
+                event.summary = UNTITLED.to_string();
+            }
+            event
+        })
+        .collect())
+}
+
+/// Content lines, joining those folded over several lines.
+fn unfold(contents: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in contents.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continued), Some(last)) => last.push_str(continued),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// A content line: `NAME;PARAM=VALUE;...:VALUE`.
+#[derive(Clone)]
+struct Property {
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn parse(line: &str) -> Option<Self> {
+        // The value starts at the first colon outside a quoted parameter.
+        let mut quoted = false;
+        let colon = line.char_indices().find_map(|(index, c)| match c {
+            '"' => {
+                quoted = !quoted;
+                None
+            }
+            ':' if !quoted => Some(index),
+            _ => None,
+        })?;
+        let mut parts = line[..colon].split(';');
+        let name = parts.next()?.trim().to_ascii_uppercase();
+        let params = parts
+            .filter_map(|param| {
+                let (key, value) = param.split_once('=')?;
+                Some((
+                    key.to_ascii_uppercase(),
+                    value.trim_matches('"').to_string(),
+                ))
+            })
+            .collect();
+        Some(Self {
+            name,
+            params,
+            value: line[colon + 1..].trim().to_string(),
+        })
+    }
+
+    fn param(&self, key: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// A TEXT value with its escapes undone.
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n' | 'N') => unescaped.push('\n'),
+                Some(c) => unescaped.push(c),
+                None => {}
+            },
+            c => unescaped.push(c),
+        }
+    }
+    unescaped.trim().to_string()
+}
+
+#[derive(Clone, Copy)]
+enum Zone {
+    Utc,
+    Named(Tz),
+    /// Floating times, and zones not in the time zone database.
+    Local,
+}
+
+/// A `DATE` or `DATE-TIME` value with the zone it is read in.
+#[derive(Clone, Copy)]
+struct Time {
+    naive: NaiveDateTime,
+    zone: Zone,
+    all_day: bool,
+}
+
+impl Time {
+    fn parse(property: &Property) -> Result<Self> {
+        let value = property.value.as_str();
+        let invalid = || Error::invalid("date", format!("unrecognized date {value:?}"));
+        if property.param("VALUE") == Some("DATE") || value.len() == 8 {
+            let date = NaiveDate::parse_from_str(value, "%Y%m%d").map_err(|_| invalid())?;
+            return Ok(Self {
+                naive: date.and_time(Default::default()),
+                zone: Zone::Local,
+                all_day: true,
+            });
+        }
+        let (value, zone) = match value.strip_suffix('Z') {
+            Some(value) => (value, Zone::Utc),
+            None => (
+                value,
+                property
+                    .param("TZID")
+                    .and_then(|tzid| tzid.trim_start_matches('/').parse().ok())
+                    .map_or(Zone::Local, Zone::Named),
+            ),
+        };
+        let naive = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").map_err(|_| invalid())?;
+        Ok(Self {
+            naive,
+            zone,
+            all_day: false,
+        })
+    }
+
+    /// `naive` read in the zone of this time, the earlier of two readings
+    /// when clocks are turned back.
+    fn at(&self, naive: NaiveDateTime) -> Option<DateTime<FixedOffset>> {
+        match self.zone {
+            Zone::Utc => Some(naive.and_utc().fixed_offset()),
+            Zone::Named(tz) => tz
+                .from_local_datetime(&naive)
+                .earliest()
+                .map(|time| time.fixed_offset()),
+            Zone::Local => Local
+                .from_local_datetime(&naive)
+                .earliest()
+                .map(|time| time.fixed_offset()),
+        }
+    }
+
+    fn instant(&self) -> Option<DateTime<Utc>> {
+        self.at(self.naive).map(|time| time.to_utc())
+    }
+}
+
+/// A `DURATION` value such as `PT1H30M`, `P1D` or `P1W`.
+fn parse_duration(value: &str) -> Result<Duration> {
+    let invalid = || Error::invalid("duration", format!("unrecognized duration {value:?}"));
+    let (sign, rest) = match value.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let rest = rest.strip_prefix('P').ok_or_else(invalid)?;
+    let mut seconds = 0;
+    let mut number = String::new();
+    for c in rest.chars() {
+        let unit = match c {
+            '0'..='9' => {
+                number.push(c);
+                continue;
+            }
+            'T' => continue,
+            'W' => 7 * 24 * 60 * 60,
+            'D' => 24 * 60 * 60,
+            'H' => 60 * 60,
+            'M' => 60,
+            'S' => 1,
+            _ => return Err(invalid()),
+        };
+        seconds += number.parse::<i64>().map_err(|_| invalid())? * unit;
+        number.clear();
+    }
+    Ok(Duration::seconds(sign * seconds))
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Frequency {
+    Daily,
+    Weekly,
+    /// Rules not expanded, yielding only the first occurrence.
+    Other,
+}
+
+/// The parts of an `RRULE` understood when expanding it.
+struct Rule {
+    frequency: Frequency,
+    interval: u32,
+    count: Option<usize>,
+    until: Option<NaiveDateTime>,
+    weekdays: Vec<Weekday>,
+}
+
+impl Rule {
+    fn parse(value: &str) -> Result<Self> {
+        let invalid = |part: &str| Error::invalid("rrule", format!("unrecognized part {part:?}"));
+        let mut rule = Self {
+            frequency: Frequency::Other,
+            interval: 1,
+            count: None,
+            until: None,
+            weekdays: Vec::new(),
+        };
+        for part in value.split(';').filter(|part| !part.is_empty()) {
+            let (key, value) = part.split_once('=').ok_or_else(|| invalid(part))?;
+            match key.to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    rule.frequency = match value.to_ascii_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        _ => Frequency::Other,
+                    }
+                }
+                "INTERVAL" => rule.interval = value.parse().map_err(|_| invalid(part))?,
+                "COUNT" => rule.count = Some(value.parse().map_err(|_| invalid(part))?),
+                "UNTIL" => {
+                    // Compared with the start of occurrences in their own zone,
+                    // which is exact to the day.
+                    let value = value.trim_end_matches('Z');
+                    rule.until = Some(
+                        NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+                            .or_else(|_| {
+                                NaiveDate::parse_from_str(value, "%Y%m%d")
+                                    .map(|date| date.and_hms_opt(23, 59, 59).unwrap_or_default())
+                            })
+                            .map_err(|_| invalid(part))?,
+                    );
+                }
+                "BYDAY" => {
+                    for day in value.split(',') {
+                        match weekday(day) {
+                            Some(weekday) => rule.weekdays.push(weekday),
+                            // `1MO` and the like, only meaningful in monthly
+                            // and yearly rules.
+                            None => rule.frequency = Frequency::Other,
+                        }
+                    }
+                }
+                "BYMONTH" | "BYMONTHDAY" | "BYYEARDAY" | "BYWEEKNO" | "BYSETPOS" | "BYHOUR"
+                | "BYMINUTE" | "BYSECOND" => rule.frequency = Frequency::Other,
+                _ => {}
+            }
+        }
+        rule.interval = rule.interval.max(1);
+        Ok(rule)
+    }
+
+    /// Starts of the occurrences from `start` until the end of `last`, on the
+    /// clock of the event.
+    fn occurrences(&self, start: NaiveDateTime, last: NaiveDate) -> Vec<NaiveDateTime> {
+        // A day of margin for events in zones ahead of the user's.
+        let last = last + Duration::days(1);
+        let mut occurrences = Vec::new();
+        let mut push = |time: NaiveDateTime| {
+            let done = time.date() > last
+                || self.until.is_some_and(|until| time > until)
+                || self.count.is_some_and(|count| occurrences.len() >= count);
+            if !done {
+                occurrences.push(time);
+            }
+            !done
+        };
+        match self.frequency {
+            Frequency::Other => {
+                push(start);
+            }
+            Frequency::Daily => {
+                let step = Duration::days(self.interval.into());
+                let mut time = start;
+                while push(time) {
+                    time += step;
+                }
+            }
+            Frequency::Weekly => {
+                let mut weekdays = self.weekdays.clone();
+                if weekdays.is_empty() {
+                    weekdays.push(start.weekday());
+                }
+                weekdays.sort_by_key(|weekday| weekday.num_days_from_monday());
+                let step = Duration::weeks(self.interval.into());
+                let mut monday =
+                    start - Duration::days(start.weekday().num_days_from_monday().into());
+                // The start is the first occurrence, counted by `COUNT`, even
+                // on a day the rule does not list.
+                if !push(start) {
+                    return occurrences;
+                }
+                'weeks: loop {
+                    for weekday in &weekdays {
+                        let time = monday + Duration::days(weekday.num_days_from_monday().into());
+                        if time <= start {
+                            continue;
+                        }
+                        if !push(time) {
+                            break 'weeks;
+                        }
+                    }
+                    monday += step;
+                }
+            }
+        }
+        occurrences
+    }
+}
+
+/// A weekday written as in `BYDAY`, `MO` to `SU`.
+fn weekday(day: &str) -> Option<Weekday> {
+    let weekday = match day.trim().to_ascii_uppercase().as_str() {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    };
+    Some(weekday)
+}
@@ -0,0 +1,160 @@
+//! Time entries read from and written to files, for moving data in and out of
+//! `timeloop.db`.
+
+pub mod clockify;
+pub mod csv;
+pub mod harvest;
+pub mod ical;
+pub mod timewarrior;
+pub mod toggl;
+
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+
+use crate::db::TimeRange;
+use crate::error::{Error, Result};
+
+/// Entry title for time logged without a description.
+pub(crate) const UNTITLED: &str = "(no description)";
+
+/// Minutes in a duration written as whole minutes, `H:MM` or `H:MM:SS`,
+/// seconds rounded to the nearest minute.
+pub(crate) fn parse_minutes(text: &str) -> Option<i64> {
+    let text = text.trim();
+    if !text.contains(':') {
+        return text.parse().ok();
+    }
+    let parts = text
+        .split(':')
+        .map(|part| part.parse::<i64>().ok().filter(|n| *n >= 0))
+        .collect::<Option<Vec<_>>>()?;
+    let (hours, minutes) = match parts[..] {
+        [hours, minutes] if minutes < 60 => (hours, minutes),
+        [hours, minutes, seconds] if minutes < 60 && seconds < 60 => {
+            (hours, minutes + (seconds + 30) / 60)
+        }
+        _ => return None,
+    };
+    hours.checked_mul(60)?.checked_add(minutes)
+}
+
+/// Minutes in a number of hours written as a decimal (`1.5`) or `H:MM`.
+pub(crate) fn parse_hours(text: &str) -> Option<i64> {
+    let text = text.trim();
+    if text.contains(':') {
+        return parse_minutes(text);
+    }
+    let hours: f64 = text.replace(',', ".").parse().ok()?;
+    let minutes = (hours * 60.0).round();
+    // `as` would saturate rather than fail.
+    (minutes.is_finite() && (0.0..i64::MAX as f64).contains(&minutes)).then_some(minutes as i64)
+}
+
+/// A date as other tools write it: ISO, `MM/DD/YYYY` or `DD.MM.YYYY`.
+pub(crate) fn parse_date(field: &'static str, text: &str) -> Result<NaiveDate> {
+    ["%Y-%m-%d", "%m/%d/%Y", "%d.%m.%Y"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(text.trim(), format).ok())
+        .ok_or_else(|| Error::invalid(field, format!("unrecognized date {text:?}")))
+}
+
+/// A date and a time of day, on 24 or 12 hour clocks, seconds optional.
+pub(crate) fn parse_date_time(
+    field: &'static str,
+    date: &str,
+    time: &str,
+) -> Result<NaiveDateTime> {
+    let time = ["%H:%M:%S", "%H:%M", "%I:%M:%S %p", "%I:%M %p"]
+        .iter()
+        .find_map(|format| NaiveTime::parse_from_str(time.trim(), format).ok())
+        .ok_or_else(|| Error::invalid(field, format!("unrecognized time {time:?}")))?;
+    Ok(parse_date(field, date)?.and_time(time))
+}
+
+/// A range written without a UTC offset, read on the user's clock.
+pub(crate) fn local_range(started_at: NaiveDateTime, ended_at: NaiveDateTime) -> Result<TimeRange> {
+    let local = |field: &'static str, time: NaiveDateTime| {
+        Local
+            .from_local_datetime(&time)
+            .earliest()
+            .map(|time| time.fixed_offset())
+            .ok_or_else(|| Error::invalid(field, format!("{time} does not exist on this clock")))
+    };
+    Ok(TimeRange {
+        started_at: local("started_at", started_at)?,
+        ended_at: local("ended_at", ended_at)?,
+    })
+}
+
+/// Column positions in the header of a CSV export from another tool.
+pub(crate) struct Header(::csv::StringRecord);
+
+impl Header {
+    pub(crate) fn new(record: &::csv::StringRecord) -> Self {
+        Self(record.clone())
+    }
+
+    /// The first column named one of `names`, ignoring case.
+    pub(crate) fn find(&self, names: &[&str]) -> Option<usize> {
+        names.iter().find_map(|name| {
+            self.0
+                .iter()
+                .position(|column| column.trim().eq_ignore_ascii_case(name))
+        })
+    }
+
+    pub(crate) fn require(&self, names: &[&str]) -> Result<usize> {
+        self.find(names)
+            .ok_or_else(|| Error::invalid("columns", format!("no {:?} column", names[0])))
+    }
+}
+
+/// Columns of the date and time of day a range starts and ends at, as the
+/// detailed reports of Toggl Track and Clockify have them.
+pub(crate) struct RangeColumns {
+    start_date: usize,
+    start_time: usize,
+    end_date: usize,
+    end_time: usize,
+}
+
+impl RangeColumns {
+    pub(crate) fn require(header: &Header) -> Result<Self> {
+        Ok(Self {
+            start_date: header.require(&["Start date"])?,
+            start_time: header.require(&["Start time"])?,
+            end_date: header.require(&["End date"])?,
+            end_time: header.require(&["End time"])?,
+        })
+    }
+
+    /// The range of `record`, read on the user's clock.
+    pub(crate) fn read(&self, record: &::csv::StringRecord) -> Result<TimeRange> {
+        let at = |field_name, date, time| {
+            parse_date_time(
+                field_name,
+                field(record, Some(date)).unwrap_or_default(),
+                field(record, Some(time)).unwrap_or_default(),
+            )
+        };
+        let started_at = at("start", self.start_date, self.start_time)?;
+        let ended_at = at("end", self.end_date, self.end_time)?;
+        local_range(started_at, ended_at)
+    }
+}
+
+/// Turns an error on the row at `index` among the records of a file into an
+/// [`Error::Row`].
+pub(crate) fn on_row(index: usize) -> impl Fn(Error) -> Error {
+    move |source| Error::Row {
+        row: index + 1,
+        source: Box::new(source),
+    }
+}
+
+/// The non-empty, trimmed value of `column` in `record`.
+pub(crate) fn field(record: &::csv::StringRecord, column: Option<usize>) -> Option<&str> {
+    column
+        .and_then(|column| record.get(column))
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
@@ -0,0 +1,312 @@
+//! Timewarrior interval files, the `data/YYYY-MM.data` files of its database.
+//!
+//! Each line is an interval such as
+//! `inc 20261017T070000Z - 20261017T083000Z # "Client call" category:Acme # "notes"`.
+//! The entry title is written as a tag and the category as a `category:` tag,
+//! the note as the annotation. Reading, the other tags together make up the
+//! title. Timewarrior only knows intervals, so time entries without a range
+//! are not exported, and open intervals are not imported.
+
+use chrono::{DateTime, Local, NaiveDateTime, Utc};
+use serde::Serialize;
+
+use super::on_row;
+use crate::db::imports::ImportRow;
+use crate::db::{TimeEntryWithDetails, TimeRange};
+use crate::error::{Error, Result};
+
+pub const SOURCE: &str = "timewarrior";
+
+/// Prefix of the tag naming the category.
+const CATEGORY_TAG: &str = "category:";
+const TIMESTAMP: &str = "%Y%m%dT%H%M%SZ";
+const UNTAGGED: &str = "(untagged)";
+
+/// Reads intervals from the contents of one or more data files.
+pub fn parse(contents: &str) -> Result<Vec<ImportRow>> {
+    let mut rows = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let row = parse_line(line).map_err(on_row(index))?;
+        rows.extend(row);
+    }
+    Ok(rows)
+}
+
+fn parse_line(line: &str) -> Result<Option<ImportRow>> {
+    let mut tokens = tokenize(
+        line.strip_prefix("inc ")
+            .ok_or_else(|| Error::invalid("interval", format!("expected `inc`, got {line:?}")))?,
+    )
+    .into_iter()
+    .peekable();
+    let is_separator = |token: &Token, text: &str| !token.quoted && token.text == text;
+
+    let started_at = timestamp(tokens.next())?;
+    if !tokens.peek().is_some_and(|token| is_separator(token, "-")) {
+        return Ok(None);
+    }
+    tokens.next();
+    let ended_at = timestamp(tokens.next())?;
+
+    let mut tags = Vec::new();
+    let mut annotation = Vec::new();
+    if tokens.next_if(|token| is_separator(token, "#")).is_some() {
+        for token in tokens.by_ref() {
+            if is_separator(&token, "#") {
+                break;
+            }
+            tags.push(token.text);
+        }
+        annotation.extend(tokens.map(|token| token.text));
+    }
+
+    let range = TimeRange {
+        started_at: started_at.with_timezone(&Local).fixed_offset(),
+        ended_at: ended_at.with_timezone(&Local).fixed_offset(),
+    };
+    let duration = range.minutes();
+    if duration <= 0 {
+        return Ok(None);
+    }
+    let (categories, titles): (Vec<_>, Vec<_>) =
+        tags.iter().partition(|tag| tag.starts_with(CATEGORY_TAG));
+    let title = titles
+        .iter()
+        .map(|tag| tag.as_str())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let annotation = annotation.join(" ");
+    Ok(Some(ImportRow {
+        date: range.started_at.date_naive().to_string(),
+        duration,
+        entry_id: None,
+        entry_title: if title.is_empty() {
+            UNTAGGED.to_string()
+        } else {
+            title
+        },
+        category_name: categories
+            .first()
+            .map(|tag| tag[CATEGORY_TAG.len()..].to_string())
+            .filter(|name| !name.is_empty()),
+        note: Some(annotation).filter(|note| !note.is_empty()),
+        range: Some(range),
+    }))
+}
+
+fn timestamp(token: Option<Token>) -> Result<DateTime<Utc>> {
+    let text = token.map(|token| token.text).unwrap_or_default();
+    NaiveDateTime::parse_from_str(&text, TIMESTAMP)
+        .map(|time| time.and_utc())
+        .map_err(|_| {
+            Error::invalid(
+                "interval",
+                format!("expected a UTC timestamp, got {text:?}"),
+            )
+        })
+}
+
+struct Token {
+    text: String,
+    quoted: bool,
+}
+
+/// Splits on spaces, keeping double-quoted text with `\"` escapes together.
+fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let mut token = String::new();
+        if c == '"' {
+            chars.next();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => token.extend(chars.next()),
+                    '"' => break,
+                    c => token.push(c),
+                }
+            }
+            tokens.push(Token {
+                text: token,
+                quoted: true,
+            });
+        } else {
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                token.push(c);
+            }
+            tokens.push(Token {
+                text: token,
+                quoted: false,
+            });
+        }
+    }
+    tokens
+}
+
+/// A data file, named after the month its intervals started in.
+#[derive(Debug, Clone, Serialize)]
+pub struct DataFile {
+    pub name: String,
+    pub contents: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Export {
+    pub files: Vec<DataFile>,
+    /// Time entries left out for lacking a range.
+    pub skipped: usize,
+}
+
+pub fn export(time_entries: &[TimeEntryWithDetails]) -> Export {
+    let mut files: Vec<DataFile> = Vec::new();
+    let mut skipped = 0;
+    for te in time_entries {
+        let (Some(started_at), Some(ended_at)) = (te.time_entry.started_at, te.time_entry.ended_at)
+        else {
+            skipped += 1;
+            continue;
+        };
+        let name = format!("{}.data", started_at.format("%Y-%m"));
+        let line = interval(te, started_at, ended_at);
+        match files.iter_mut().find(|file| file.name == name) {
+            Some(file) => file.contents.push_str(&line),
+            None => files.push(DataFile {
+                name,
+                contents: line,
+            }),
+        }
+    }
+    for file in &mut files {
+        file.contents = merge("", &file.contents).contents;
+    }
+    Export { files, skipped }
+}
+
+/// A data file with intervals merged in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Merge {
+    pub contents: String,
+    /// Added intervals left out for overlapping existing ones.
+    pub overlapping: usize,
+}
+
+/// The intervals of both data files in the order they started. An added
+/// interval with the same bounds as an existing one is dropped, keeping the
+/// existing line, and one overlapping an existing interval is left out, as
+/// Timewarrior does not allow tracking the same time twice.
+pub fn merge(existing: &str, added: &str) -> Merge {
+    let lines = |contents: &str| -> Vec<String> {
+        contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(String::from)
+            .collect()
+    };
+    let mut merged = lines(existing);
+    let taken: Vec<_> = merged.iter().filter_map(|line| span(line)).collect();
+    let mut overlapping = 0;
+    for line in lines(added) {
+        if merged.iter().any(|old| bounds(old) == bounds(&line)) {
+            continue;
+        }
+        if let Some((start, end)) = span(&line) {
+            if taken
+                .iter()
+                .any(|&(taken_start, taken_end)| start < taken_end && taken_start < end)
+            {
+                overlapping += 1;
+                continue;
+            }
+        }
+        merged.push(line);
+    }
+    merged.sort_by(|a, b| bounds(a).cmp(bounds(b)));
+    merged.dedup_by(|a, b| bounds(a) == bounds(b));
+    Merge {
+        contents: merged.iter().map(|line| format!("{line}\n")).collect(),
+        overlapping,
+    }
+}
+
+/// `inc START - END`, the part of a line before its tags. Timestamps having a
+/// fixed width, these sort in the order the intervals started.
+fn bounds(line: &str) -> &str {
+    line.split('#').next().unwrap_or(line).trim_end()
+}
+
+/// The time an interval covers, until forever while it is open.
+fn span(line: &str) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let parse = |text: &str| {
+        NaiveDateTime::parse_from_str(text, TIMESTAMP)
+            .ok()
+            .map(|time| time.and_utc())
+    };
+    let mut words = bounds(line).split_whitespace().skip(1);
+    let start = parse(words.next()?)?;
+    let end = match words.next() {
+        Some("-") => parse(words.next()?)?,
+        _ => DateTime::<Utc>::MAX_UTC,
+    };
+    Some((start, end))
+}
+
+fn interval(
+    te: &TimeEntryWithDetails,
+    started_at: DateTime<Utc>,
+    ended_at: DateTime<Utc>,
+) -> String {
+    let mut tags = Vec::new();
+    if te.entry_title != UNTAGGED {
+        tags.push(te.entry_title.clone());
+    }
+    if let Some(category) = &te.category_name {
+        tags.push(format!("{CATEGORY_TAG}{category}"));
+    }
+    // Timewarrior keeps tags sorted.
+    tags.sort();
+    let note = te
+        .time_entry
+        .note
+        .as_deref()
+        .filter(|note| !note.is_empty());
+
+    let mut line = format!(
+        "inc {} - {}",
+        started_at.format(TIMESTAMP),
+        ended_at.format(TIMESTAMP)
+    );
+    if !tags.is_empty() || note.is_some() {
+        line.push_str(" #");
+    }
+    for tag in &tags {
+        line.push(' ');
+        line.push_str(&quote_if_needed(tag));
+    }
+    if let Some(note) = note {
+        line.push_str(" # ");
+        line.push_str(&quote(&note.replace(['\n', '\r'], " ")));
+    }
+    line.push('\n');
+    line
+}
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn quote_if_needed(tag: &str) -> String {
+    if tag.is_empty() || tag.contains(|c: char| c.is_whitespace() || c == '"' || c == '#') {
+        quote(tag)
+    } else {
+        tag.to_string()
+    }
+}
@@ -0,0 +1,118 @@
+//! Toggl Track exports: the detailed report as CSV, and time entries as JSON
+//! from the reports or the API.
+//!
+//! The project becomes the category and the description the entry title,
+//! falling back to the task or project for time logged without one. Tags are
+//! kept in the note. Times are read on the user's clock, and entries shorter
+//! than half a minute or still running are left out.
+
+use chrono::{DateTime, FixedOffset, Local};
+use csv::ReaderBuilder;
+use serde::Deserialize;
+
+use super::{field, on_row, Header, RangeColumns, UNTITLED};
+use crate::db::imports::ImportRow;
+use crate::db::TimeRange;
+use crate::error::Result;
+
+pub const SOURCE: &str = "toggl";
+
+/// Reads a CSV or JSON export, told apart by its first character.
+pub fn parse(contents: &str) -> Result<Vec<ImportRow>> {
+    match contents.trim_start().chars().next() {
+        Some('[' | '{') => parse_json(contents),
+        _ => parse_csv(contents),
+    }
+}
+
+pub fn parse_csv(contents: &str) -> Result<Vec<ImportRow>> {
+    let mut reader = ReaderBuilder::new().from_reader(contents.as_bytes());
+    let header = Header::new(reader.headers()?);
+    let project = header.find(&["Project"]);
+    let task = header.find(&["Task"]);
+    let description = header.find(&["Description"]);
+    let tags = header.find(&["Tags"]);
+    let range = RangeColumns::require(&header)?;
+
+    let mut rows = Vec::new();
+    for (index, record) in reader.records().enumerate() {
+        let record = record?;
+        let range = range.read(&record).map_err(on_row(index))?;
+        let project = field(&record, project);
+        let title = field(&record, description)
+            .or_else(|| field(&record, task))
+            .or(project)
+            .unwrap_or(UNTITLED);
+        rows.extend(row(title, project, field(&record, tags), range));
+    }
+    Ok(rows)
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonExport {
+    List(Vec<JsonEntry>),
+    Report { data: Vec<JsonEntry> },
+}
+
+#[derive(Deserialize)]
+struct JsonEntry {
+    description: Option<String>,
+    #[serde(alias = "project_name")]
+    project: Option<String>,
+    start: DateTime<FixedOffset>,
+    #[serde(alias = "stop")]
+    end: Option<DateTime<FixedOffset>>,
+    #[serde(default)]
+    tags: Option<Vec<String>>,
+}
+
+pub fn parse_json(contents: &str) -> Result<Vec<ImportRow>> {
+    let (JsonExport::List(entries) | JsonExport::Report { data: entries }) =
+        serde_json::from_str(contents)?;
+    let on_local_clock = |time: DateTime<FixedOffset>| time.with_timezone(&Local).fixed_offset();
+    Ok(entries
+        .into_iter()
+        .filter_map(|entry| {
+            let range = TimeRange {
+                started_at: on_local_clock(entry.start),
+                ended_at: on_local_clock(entry.end?),
+            };
+            let project = entry.project.as_deref().filter(|p| !p.trim().is_empty());
+            let title = entry
+                .description
+                .as_deref()
+                .filter(|d| !d.trim().is_empty())
+                .or(project)
+                .unwrap_or(UNTITLED);
+            let tags = entry.tags.unwrap_or_default().join(", ");
+            row(
+                title,
+                project,
+                Some(tags.as_str()).filter(|t| !t.is_empty()),
+                range,
+            )
+        })
+        .collect())
+}
+
+fn row(
+    title: &str,
+    project: Option<&str>,
+    tags: Option<&str>,
+    range: TimeRange,
+) -> Option<ImportRow> {
+    let duration = range.minutes();
+    if duration <= 0 {
+        return None;
+    }
+    Some(ImportRow {
+        date: range.started_at.date_naive().to_string(),
+        duration,
+        entry_id: None,
+        entry_title: title.trim().to_string(),
+        category_name: project.map(|project| project.trim().to_string()),
+        note: tags.map(|tags| format!("Tags: {tags}")),
+        range: Some(range),
+    })
+}
@@ -0,0 +1,141 @@
+//! Idle detection while the timer runs.
+//!
+//! Every few seconds the watcher asks an [`IdleSource`] how long there has
+//! been no keyboard or mouse input. Past the threshold it records the start
+//! of the idle span on the timer, and once input comes back its end, for the
+//! webview to ask whether to keep, discard or reassign that time (see
+//! [`timer::resolve_idle`]). The source and the clock are traits so that the
+//! logic runs in tests without a desktop.
+
+mod system;
+
+use std::future::Future;
+use std::time::Duration;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::Serialize;
+use sqlx::SqlitePool;
+
+use crate::db::{settings, timer};
+use crate::error::{Error, Result};
+
+pub use system::SystemIdle;
+
+const THRESHOLD_KEY: &str = "idle.threshold_minutes";
+
+pub const DEFAULT_THRESHOLD_MINUTES: i64 = 5;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Tells how long the user has been away.
+pub trait IdleSource {
+    /// Seconds since the last keyboard or mouse input, none when the
+    /// platform does not say.
+    fn idle_seconds(&self) -> impl Future<Output = Option<i64>> + Send;
+}
+
+pub trait Clock {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum IdleChange {
+    /// The user went idle at `since`, while the timer was running.
+    Started { since: DateTime<Utc> },
+    /// The user is back, the last input before that being at `until`.
+    Returned {
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+    },
+}
+
+pub struct IdleWatcher<S, C> {
+    source: S,
+    clock: C,
+}
+
+impl<S: IdleSource, C: Clock> IdleWatcher<S, C> {
+    pub fn new(source: S, clock: C) -> Self {
+        Self { source, clock }
+    }
+
+    /// Records the timer going idle past `threshold_minutes`, or the user
+    /// coming back, and returns that change.
+    pub async fn check(
+        &self,
+        pool: &SqlitePool,
+        threshold_minutes: i64,
+    ) -> Result<Option<IdleChange>> {
+        let Some(timer) = timer::get(pool).await? else {
+            return Ok(None);
+        };
+        let Some(resumed_at) = timer.resumed_at else {
+            return Ok(None);
+        };
+        let Some(idle_seconds) = self.source.idle_seconds().await else {
+            return Ok(None);
+        };
+        let last_input = self.clock.now() - TimeDelta::seconds(idle_seconds);
+        let away = threshold_minutes > 0 && idle_seconds >= threshold_minutes * 60;
+
+        match (timer.idle_since, timer.idle_until) {
+            (None, _) if away => {
+                // Time before the timer was started or resumed is not on it.
+                let since = last_input.max(resumed_at);
+                timer::mark_idle(pool, since).await?;
+                Ok(Some(IdleChange::Started { since }))
+            }
+            (Some(since), None) if !away => {
+                let until = last_input.max(since);
+                timer::mark_returned(pool, until).await?;
+                Ok(Some(IdleChange::Returned { since, until }))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Checks every few seconds for as long as the app runs, calling
+    /// `on_change` after each change recorded on the timer.
+    pub async fn watch(self, pool: SqlitePool, on_change: impl Fn(IdleChange)) {
+        loop {
+            let checked = match threshold_minutes(&pool).await {
+                Ok(threshold) => self.check(&pool, threshold).await,
+                Err(err) => Err(err),
+            };
+            match checked {
+                Ok(Some(change)) => on_change(change),
+                Ok(None) => {}
+                Err(err) => eprintln!("timeloop: idle detection failed: {err}"),
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+}
+
+/// Minutes without input after which the timer is idle, 0 when idle
+/// detection is off.
+pub async fn threshold_minutes(pool: &SqlitePool) -> Result<i64> {
+    Ok(settings::get(pool, THRESHOLD_KEY)
+        .await?
+        .and_then(|minutes| minutes.parse().ok())
+        .unwrap_or(DEFAULT_THRESHOLD_MINUTES))
+}
+
+pub async fn set_threshold_minutes(pool: &SqlitePool, minutes: i64) -> Result<()> {
+    if !(0..=24 * 60).contains(&minutes) {
+        return Err(Error::invalid(
+            "minutes",
+            "must be between 0 (off) and 1440",
+        ));
+    }
+    settings::set(pool, THRESHOLD_KEY, &minutes.to_string()).await
+}
@@ -0,0 +1,100 @@
+//! The time since the last input, as each desktop reports it.
+
+use super::IdleSource;
+
+/// Asks GNOME's idle monitor, then the freedesktop screensaver, on Linux;
+/// CoreGraphics on macOS; and `GetLastInputInfo` on Windows.
+#[derive(Default)]
+pub struct SystemIdle {
+    /// The session bus, connected on first use; none without one.
+    #[cfg(target_os = "linux")]
+    connection: tokio::sync::OnceCell<Option<zbus::Connection>>,
+}
+
+#[cfg(target_os = "linux")]
+impl IdleSource for SystemIdle {
+    async fn idle_seconds(&self) -> Option<i64> {
+        let connection = self
+            .connection
+            .get_or_init(|| async { zbus::Connection::session().await.ok() })
+            .await
+            .as_ref()?;
+        let gnome = connection
+            .call_method(
+                Some("org.gnome.Mutter.IdleMonitor"),
+                "/org/gnome/Mutter/IdleMonitor/Core",
+                Some("org.gnome.Mutter.IdleMonitor"),
+                "GetIdletime",
+                &(),
+            )
+            .await;
+        if let Ok(milliseconds) = gnome.and_then(|reply| reply.body().deserialize::<u64>()) {
+            return i64::try_from(milliseconds / 1000).ok();
+        }
+        let reply = connection
+            .call_method(
+                Some("org.freedesktop.ScreenSaver"),
+                "/org/freedesktop/ScreenSaver",
+                Some("org.freedesktop.ScreenSaver"),
+                "GetSessionIdleTime",
+                &(),
+            )
+            .await
+            .ok()?;
+        reply.body().deserialize::<u32>().ok().map(i64::from)
+    }
+}
+
+#[cfg(target_os = "macos")]
+impl IdleSource for SystemIdle {
+    async fn idle_seconds(&self) -> Option<i64> {
+        #[link(name = "CoreGraphics", kind = "framework")]
+        extern "C" {
+            fn CGEventSourceSecondsSinceLastEventType(state: i32, event_type: u32) -> f64;
+        }
+        const COMBINED_SESSION_STATE: i32 = 0;
+        const ANY_INPUT_EVENT: u32 = u32::MAX;
+        // SAFETY: a plain query taking and returning numbers.
+        let seconds = unsafe {
+            CGEventSourceSecondsSinceLastEventType(COMBINED_SESSION_STATE, ANY_INPUT_EVENT)
+        };
+        Some(seconds as i64)
+    }
+}
+
+#[cfg(windows)]
+impl IdleSource for SystemIdle {
+    async fn idle_seconds(&self) -> Option<i64> {
+        #[repr(C)]
+        struct LastInputInfo {
+            size: u32,
+            time: u32,
+        }
+        #[link(name = "user32")]
+        extern "system" {
+            fn GetLastInputInfo(info: *mut LastInputInfo) -> i32;
+        }
+        #[link(name = "kernel32")]
+        extern "system" {
+            fn GetTickCount() -> u32;
+        }
+        let mut info = LastInputInfo {
+            size: std::mem::size_of::<LastInputInfo>() as u32,
+            time: 0,
+        };
+        // SAFETY: `info` is a valid LASTINPUTINFO with its size set.
+        if unsafe { GetLastInputInfo(&mut info) } == 0 {
+            return None;
+        }
+        // Both are milliseconds since boot, wrapping every 49 days.
+        let milliseconds = unsafe { GetTickCount() }.wrapping_sub(info.time);
+        Some(i64::from(milliseconds / 1000))
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+impl IdleSource for SystemIdle {
+    async fn idle_seconds(&self) -> Option<i64> {
+        None
+    }
+}
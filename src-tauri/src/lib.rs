@@ -1,51 +1,188 @@
-use tauri_plugin_sql::{Migration, MigrationKind};
+pub mod api;
+pub mod cli;
+mod commands;
+pub mod db;
+#[cfg(target_os = "linux")]
+pub mod dbus;
+mod error;
+pub mod exchange;
+pub mod idle;
+pub mod rpc;
+pub mod shortcuts;
+#[cfg(desktop)]
+pub mod tray;
+
+use std::sync::Arc;
+
+use tauri::{Emitter, Manager};
+
+pub use error::{Error, Result};
+
+/// Event telling the webview and the D-Bus service that the timer changed.
+const TIMER_CHANGED: &str = "timer-changed";
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    let migrations = vec![
-        Migration {
-            version: 1,
-            description: "create initial tables",
-            sql: r#"
-                CREATE TABLE IF NOT EXISTS categories (
-                    id INTEGER PRIMARY KEY AUTOINCREMENT,
-                    name TEXT NOT NULL,
-                    color TEXT DEFAULT '#6366f1',
-                    created_at TEXT DEFAULT CURRENT_TIMESTAMP
-                );
-
-                CREATE TABLE IF NOT EXISTS entries (
-                    id INTEGER PRIMARY KEY AUTOINCREMENT,
-                    title TEXT NOT NULL,
-                    category_id INTEGER,
-                    created_at TEXT DEFAULT CURRENT_TIMESTAMP,
-                    FOREIGN KEY (category_id) REFERENCES categories(id)
-                );
-
-                CREATE TABLE IF NOT EXISTS time_entries (
-                    id INTEGER PRIMARY KEY AUTOINCREMENT,
-                    entry_id INTEGER NOT NULL,
-                    duration INTEGER NOT NULL,
-                    date TEXT NOT NULL,
-                    note TEXT,
-                    created_at TEXT DEFAULT CURRENT_TIMESTAMP,
-                    FOREIGN KEY (entry_id) REFERENCES entries(id) ON DELETE CASCADE
-                );
-
-                CREATE INDEX IF NOT EXISTS idx_time_entries_date ON time_entries(date);
-                CREATE INDEX IF NOT EXISTS idx_time_entries_entry_id ON time_entries(entry_id);
-            "#,
-            kind: MigrationKind::Up,
-        },
-    ];
-
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
-        .plugin(
-            tauri_plugin_sql::Builder::default()
-                .add_migrations("sqlite:timeloop.db", migrations)
-                .build(),
-        )
+        .setup(|app| {
+            // Where tauri-plugin-sql used to create `sqlite:timeloop.db`.
+            let path = app.path().app_config_dir()?;
+            let database =
+                tauri::async_runtime::block_on(db::Database::open(&path.join(db::DATABASE_FILE)))?;
+            tauri::async_runtime::spawn(db::snapshots::schedule(
+                database.pool().clone(),
+                db::snapshots::directory(database.path()),
+            ));
+            let on_change: Arc<dyn Fn() + Send + Sync> = {
+                let handle = app.handle().clone();
+                Arc::new(move || {
+                    let _ = handle.emit(TIMER_CHANGED, ());
+                })
+            };
+            let api = api::ApiServer::new(
+                app.path().app_data_dir()?,
+                database.pool().clone(),
+                on_change.clone(),
+            );
+            // A taken port should not keep the app from starting.
+            if let Err(err) = tauri::async_runtime::block_on(api.start_if_enabled()) {
+                eprintln!("timeloop: could not start the API server: {err}");
+            }
+            #[cfg(unix)]
+            {
+                let path = rpc::socket_path();
+                match tauri::async_runtime::block_on(rpc::listen(&path)) {
+                    Ok(listener) => {
+                        tauri::async_runtime::spawn(rpc::serve(
+                            listener,
+                            database.pool().clone(),
+                            on_change.clone(),
+                        ));
+                    }
+                    Err(err) => {
+                        eprintln!("timeloop: could not listen on {}: {err}", path.display())
+                    }
+                }
+            }
+            #[cfg(target_os = "linux")]
+            {
+                let handle = app.handle().clone();
+                let pool = database.pool().clone();
+                tauri::async_runtime::spawn(async move {
+                    // Not every desktop runs a session bus.
+                    if let Err(err) = serve_dbus(&handle, pool, on_change).await {
+                        eprintln!("timeloop: could not register on D-Bus: {err}");
+                    }
+                });
+            }
+            let watcher = idle::IdleWatcher::new(idle::SystemIdle::default(), idle::SystemClock);
+            let on_idle = on_change.clone();
+            tauri::async_runtime::spawn(watcher.watch(database.pool().clone(), move |_| on_idle()));
+            #[cfg(desktop)]
+            tray::create(app.handle(), database.pool().clone())?;
+            #[cfg(desktop)]
+            {
+                app.handle()
+                    .plugin(tauri_plugin_global_shortcut::Builder::new().build())?;
+                let bindings = tauri::async_runtime::block_on(shortcuts::load(database.pool()))?;
+                // Keys taken by another application should not keep the app from starting.
+                if let Err(err) = shortcuts::register(app.handle(), &bindings) {
+                    eprintln!("timeloop: {err}");
+                }
+            }
+            app.manage(database);
+            app.manage(api);
+            Ok(())
+        })
+        .invoke_handler(tauri::generate_handler![
+            commands::get_categories,
+            commands::create_category,
+            commands::update_category,
+            commands::delete_category,
+            commands::get_entries,
+            commands::get_entries_for_period,
+            commands::search_entries,
+            commands::get_entry_by_id,
+            commands::create_entry,
+            commands::update_entry,
+            commands::delete_entry,
+            commands::get_time_entries,
+            commands::get_time_entries_for_entry,
+            commands::create_time_entry,
+            commands::update_time_entry,
+            commands::delete_time_entry,
+            commands::find_overlaps,
+            commands::get_conflicting_time_entries,
+            commands::backfill_time_ranges,
+            commands::get_entries_with_total_time,
+            commands::get_entries_with_total_time_including_empty,
+            commands::get_entries_with_total_time_for_period,
+            commands::calculate_time_for_period,
+            commands::get_active_timer,
+            commands::start_timer,
+            commands::pause_timer,
+            commands::resume_timer,
+            commands::stop_timer,
+            commands::resolve_idle,
+            commands::get_idle_threshold,
+            commands::set_idle_threshold,
+            commands::export_csv,
+            commands::import_csv,
+            commands::import_toggl,
+            commands::import_clockify,
+            commands::import_harvest,
+            commands::import_timewarrior,
+            commands::export_timewarrior,
+            commands::export_ical,
+            commands::list_calendar_events,
+            commands::import_calendar_events,
+            commands::export_backup,
+            commands::import_backup,
+            commands::list_backups,
+            commands::restore_backup,
+            commands::downgrade_database,
+            commands::check_database,
+            commands::get_api_config,
+            commands::set_api_enabled,
+            commands::reset_api_token,
+            commands::get_shortcuts,
+            commands::set_shortcut,
+            commands::set_tray_labels,
+        ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+/// Shows, unminimizes and focuses the main window.
+#[cfg(desktop)]
+fn show_main_window(app: &tauri::AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+/// Registers the D-Bus service on the session bus, relaying `TIMER_CHANGED`
+/// as its `TimerChanged` signal.
+#[cfg(target_os = "linux")]
+async fn serve_dbus(
+    handle: &tauri::AppHandle,
+    pool: sqlx::SqlitePool,
+    on_change: Arc<dyn Fn() + Send + Sync>,
+) -> zbus::Result<()> {
+    use tauri::Listener;
+
+    let connection = zbus::Connection::session().await?;
+    dbus::register(&connection, pool.clone(), on_change).await?;
+    handle.listen(TIMER_CHANGED, move |_| {
+        let (connection, pool) = (connection.clone(), pool.clone());
+        tauri::async_runtime::spawn(async move {
+            if let Err(err) = dbus::timer_changed(&connection, &pool).await {
+                eprintln!("timeloop: could not signal the timer change on D-Bus: {err}");
+            }
+        });
+    });
+    Ok(())
+}
@@ -0,0 +1,146 @@
+//! JSON-RPC 2.0 control of the running app over a Unix socket, for
+//! `timeloop-cli` and status bar scripts.
+//!
+//! Requests and responses are JSON objects, one per line:
+//!
+//! ```text
+//! → {"jsonrpc": "2.0", "id": 1, "method": "timer.start", "params": {"title": "Client call"}}
+//! ← {"jsonrpc": "2.0", "id": 1, "result": {"entry_title": "Client call", "stopped": null}}
+//! ```
+//!
+//! | Method | Params | Result |
+//! |---|---|---|
+//! | `timer.get` | | the timer, `null` if none |
+//! | `timer.start` | `entry_id` or `title` (and `category`), `note` | [`Started`] |
+//! | `timer.pause`, `timer.resume` | | `null` |
+//! | `timer.stop` | | [`Stopped`] |
+//! | `today.total` | | [`TodayTotal`] |
+//!
+//! Requests are answered by [`dispatch`], which the CLI also calls directly
+//! when the app is not running.
+
+#[cfg(unix)]
+mod socket;
+
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::SqlitePool;
+
+use crate::db::{entries, reports, timer};
+use crate::error::{Error, Result};
+
+#[cfg(unix)]
+pub use socket::{listen, serve, socket_path, Client};
+
+/// Methods answered, the others failing with "method not found".
+pub const METHODS: [&str; 6] = [
+    "timer.get",
+    "timer.start",
+    "timer.pause",
+    "timer.resume",
+    "timer.stop",
+    "today.total",
+];
+
+#[derive(Deserialize)]
+struct StartParams {
+    entry_id: Option<i64>,
+    title: Option<String>,
+    category: Option<String>,
+    note: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Started {
+    pub entry_title: String,
+    /// Entry of the timer stopped to start this one, if it logged time.
+    pub stopped: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Stopped {
+    pub entry_title: String,
+    /// Minutes logged, none when the timer ran for less than half a minute.
+    pub logged: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TodayTotal {
+    pub date: NaiveDate,
+    /// Minutes logged today.
+    pub minutes: i64,
+    /// Time on the timer not logged yet, running or paused.
+    pub timer_seconds: i64,
+}
+
+/// Whether `method` may change the timer, for the app to refresh it.
+pub fn changes_timer(method: &str) -> bool {
+    method.starts_with("timer.") && method != "timer.get"
+}
+
+/// Answers a request on `pool`, `params` being `null` when omitted.
+pub async fn dispatch(
+    pool: &SqlitePool,
+    method: &str,
+    params: Value,
+    now: DateTime<Utc>,
+) -> Result<Value> {
+    let result = match method {
+        "timer.get" => serde_json::to_value(timer::get(pool).await?)?,
+        "timer.start" => {
+            let params: StartParams = serde_json::from_value(params)?;
+            let entry_id = match (params.entry_id, params.title.as_deref()) {
+                (Some(id), _) => id,
+                (None, Some(title)) => {
+                    entries::find_or_create_named(pool, title, params.category.as_deref()).await?
+                }
+                (None, None) => {
+                    return Err(Error::invalid("title", "entry_id or title is required"))
+                }
+            };
+            let entry = entries::get(pool, entry_id)
+                .await?
+                .ok_or(Error::NotFound("entry"))?;
+            let previous = timer::get(pool).await?;
+            let logged = timer::start(pool, entry_id, params.note.as_deref(), now).await?;
+            serde_json::to_value(Started {
+                entry_title: entry.title,
+                stopped: previous
+                    .filter(|_| logged.is_some())
+                    .map(|previous| previous.entry_title),
+            })?
+        }
+        "timer.pause" => {
+            timer::pause(pool, now).await?;
+            Value::Null
+        }
+        "timer.resume" => {
+            timer::resume(pool, now).await?;
+            Value::Null
+        }
+        "timer.stop" => {
+            let active = timer::get(pool).await?.ok_or(Error::NotFound("timer"))?;
+            let logged = timer::stop(pool, now).await?.map(|logged| logged.duration);
+            serde_json::to_value(Stopped {
+                entry_title: active.entry_title,
+                logged,
+            })?
+        }
+        "today.total" => {
+            let date = now.with_timezone(&Local).date_naive();
+            let today = date.to_string();
+            let minutes = reports::total_for_period(pool, &today, &today).await?;
+            let timer_seconds = timer::get(pool)
+                .await?
+                .map_or(0, |timer| timer.elapsed_seconds(now));
+            serde_json::to_value(TodayTotal {
+                date,
+                minutes,
+                timer_seconds,
+            })?
+        }
+        _ => return Err(Error::NotFound("method")),
+    };
+    Ok(result)
+}
@@ -0,0 +1,226 @@
+//! The socket the running app listens on, and the client connecting to it.
+
+use std::io::ErrorKind;
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use chrono::Utc;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::SqlitePool;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+
+use super::{changes_timer, dispatch, METHODS};
+use crate::error::{Error, Result};
+
+const SOCKET_FILE: &str = "timeloop.sock";
+
+// JSON-RPC error codes.
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const SERVER_ERROR: i64 = -32000;
+
+#[derive(Serialize, Deserialize)]
+struct Request {
+    jsonrpc: String,
+    /// Absent in notifications, which get no response.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Deserialize)]
+struct Response {
+    result: Option<Value>,
+    error: Option<ResponseError>,
+}
+
+#[derive(Deserialize)]
+struct ResponseError {
+    message: String,
+}
+
+/// In the user's runtime directory, or where there is none in a directory of
+/// theirs in the temporary one, `timeloop-<uid>`.
+pub fn socket_path() -> PathBuf {
+    dirs::runtime_dir()
+        .unwrap_or_else(|| std::env::temp_dir().join(format!("timeloop-{}", uid())))
+        .join(SOCKET_FILE)
+}
+
+fn uid() -> u32 {
+    extern "C" {
+        fn getuid() -> u32;
+    }
+    // SAFETY: a plain query that cannot fail.
+    unsafe { getuid() }
+}
+
+/// Listens on `path`, replacing the socket a crashed app may have left behind
+/// but refusing to take over one still answered.
+pub async fn listen(path: &Path) -> Result<UnixListener> {
+    if let Some(dir) = path.parent() {
+        private_dir(dir)?;
+    }
+    if UnixStream::connect(path).await.is_ok() {
+        return Err(Error::invalid(
+            "socket",
+            format!("{} is in use by another instance", path.display()),
+        ));
+    }
+    match std::fs::remove_file(path) {
+        Err(err) if err.kind() != ErrorKind::NotFound => return Err(err.into()),
+        _ => {}
+    }
+    let listener = UnixListener::bind(path)?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
+/// Creates `dir` for the user only if it is missing, and refuses one another
+/// user owns or may enter, such as a directory made in its place in `/tmp`.
+fn private_dir(dir: &Path) -> Result<()> {
+    match std::fs::DirBuilder::new().mode(0o700).create(dir) {
+        Err(err) if err.kind() != ErrorKind::AlreadyExists => return Err(err.into()),
+        _ => {}
+    }
+    let metadata = std::fs::symlink_metadata(dir)?;
+    if !metadata.is_dir() || metadata.uid() != uid() || metadata.mode() & 0o077 != 0 {
+        return Err(Error::invalid(
+            "socket",
+            format!("{} is not a private directory", dir.display()),
+        ));
+    }
+    Ok(())
+}
+
+/// Answers requests on `listener` for as long as the app runs, calling
+/// `on_change` after each one that may have changed the timer.
+pub async fn serve(
+    listener: UnixListener,
+    pool: SqlitePool,
+    on_change: Arc<dyn Fn() + Send + Sync>,
+) {
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(err) => {
+                eprintln!("timeloop: control socket connection failed: {err}");
+                continue;
+            }
+        };
+        let (pool, on_change) = (pool.clone(), on_change.clone());
+        tauri::async_runtime::spawn(async move {
+            if let Err(err) = answer(stream, &pool, &*on_change).await {
+                eprintln!("timeloop: control socket connection failed: {err}");
+            }
+        });
+    }
+}
+
+async fn answer(
+    stream: UnixStream,
+    pool: &SqlitePool,
+    on_change: &(dyn Fn() + Sync),
+) -> Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let Some(response) = respond(&line, pool, on_change).await else {
+            continue;
+        };
+        let mut response = response.to_string();
+        response.push('\n');
+        writer.write_all(response.as_bytes()).await?;
+    }
+    Ok(())
+}
+
+/// The response to one line, none for notifications.
+async fn respond(line: &str, pool: &SqlitePool, on_change: &(dyn Fn() + Sync)) -> Option<Value> {
+    let error = |id: Value, code: i64, message: String| {
+        let error = json!({ "code": code, "message": message });
+        json!({ "jsonrpc": "2.0", "id": id, "error": error })
+    };
+    let request: Value = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(err) => return Some(error(Value::Null, PARSE_ERROR, err.to_string())),
+    };
+    let id = request.get("id").cloned();
+    let request = match serde_json::from_value::<Request>(request) {
+        Ok(request) if request.jsonrpc == "2.0" => request,
+        Ok(_) => {
+            let message = "jsonrpc must be \"2.0\"".to_string();
+            return Some(error(id.unwrap_or_default(), INVALID_REQUEST, message));
+        }
+        Err(err) => {
+            return Some(error(
+                id.unwrap_or_default(),
+                INVALID_REQUEST,
+                err.to_string(),
+            ))
+        }
+    };
+    if !METHODS.contains(&request.method.as_str()) {
+        let message = format!("unknown method {:?}", request.method);
+        return request.id.map(|id| error(id, METHOD_NOT_FOUND, message));
+    }
+    let result = dispatch(pool, &request.method, request.params, Utc::now()).await;
+    if result.is_ok() && changes_timer(&request.method) {
+        on_change();
+    }
+    let id = request.id?;
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(err) => error(id, SERVER_ERROR, err.to_string()),
+    })
+}
+
+/// A connection to the running app.
+pub struct Client {
+    lines: tokio::io::Lines<BufReader<tokio::net::unix::OwnedReadHalf>>,
+    writer: tokio::net::unix::OwnedWriteHalf,
+    next_id: u64,
+}
+
+impl Client {
+    /// Connects to the app listening on `path`, if it runs.
+    pub async fn connect(path: &Path) -> Option<Self> {
+        let (reader, writer) = UnixStream::connect(path).await.ok()?.into_split();
+        Some(Self {
+            lines: BufReader::new(reader).lines(),
+            writer,
+            next_id: 1,
+        })
+    }
+
+    pub async fn call<T: DeserializeOwned>(&mut self, method: &str, params: Value) -> Result<T> {
+        let request = Request {
+            jsonrpc: "2.0".to_string(),
+            id: Some(self.next_id.into()),
+            method: method.to_string(),
+            params,
+        };
+        self.next_id += 1;
+        let mut line = serde_json::to_string(&request)?;
+        line.push('\n');
+        self.writer.write_all(line.as_bytes()).await?;
+        let line = self.lines.next_line().await?.ok_or_else(|| {
+            std::io::Error::new(ErrorKind::UnexpectedEof, "the app closed the connection")
+        })?;
+        let response: Response = serde_json::from_str(&line)?;
+        match (response.result, response.error) {
+            (_, Some(error)) => Err(Error::Remote(error.message)),
+            (result, None) => Ok(serde_json::from_value(result.unwrap_or_default())?),
+        }
+    }
+}
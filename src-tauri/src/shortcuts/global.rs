@@ -0,0 +1,86 @@
+//! Registration of the shortcuts with the desktop, and what they do.
+
+use chrono::Utc;
+use serde_json::{json, Value};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
+
+use super::{ShortcutAction, ShortcutBinding, QUICK_LOG};
+use crate::db::{entries, timer, Database};
+use crate::error::{Error, Result};
+use crate::{rpc, TIMER_CHANGED};
+
+/// Replaces the registered shortcuts with `bindings`, registering all
+/// those it can before reporting the ones taken by another application.
+pub fn register(app: &AppHandle, bindings: &[ShortcutBinding]) -> Result<()> {
+    let global = app.global_shortcut();
+    global
+        .unregister_all()
+        .map_err(|err| Error::invalid("accelerator", err.to_string()))?;
+    let mut failed = Vec::new();
+    for binding in bindings {
+        let Some(accelerator) = &binding.accelerator else {
+            continue;
+        };
+        let action = binding.action;
+        let registered = global.on_shortcut(accelerator.as_str(), move |app, _, event| {
+            if event.state == ShortcutState::Pressed {
+                trigger(app, action);
+            }
+        });
+        if let Err(err) = registered {
+            failed.push(format!("{accelerator} ({err})"));
+        }
+    }
+    match failed.is_empty() {
+        true => Ok(()),
+        false => Err(Error::invalid(
+            "accelerator",
+            format!("could not register {}", failed.join(", ")),
+        )),
+    }
+}
+
+fn trigger(app: &AppHandle, action: ShortcutAction) {
+    if action == ShortcutAction::QuickLog {
+        quick_log(app);
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let pool = app.state::<Database>().pool().clone();
+        match run(&pool, action).await {
+            Ok(true) => {
+                let _ = app.emit(TIMER_CHANGED, ());
+            }
+            // Nothing to toggle: ask which entry to start.
+            Ok(false) if action == ShortcutAction::ToggleTimer => quick_log(&app),
+            Ok(false) => {}
+            Err(err) => eprintln!("timeloop: {err}"),
+        }
+    });
+}
+
+fn quick_log(app: &AppHandle) {
+    crate::show_main_window(app);
+    let _ = app.emit(QUICK_LOG, ());
+}
+
+/// Runs a timer action, returning whether there was a timer to act on.
+async fn run(pool: &sqlx::SqlitePool, action: ShortcutAction) -> Result<bool> {
+    let timer = timer::get(pool).await?;
+    let (method, params) = match (action, timer) {
+        (ShortcutAction::ToggleTimer, Some(timer)) if timer.is_running() => {
+            ("timer.pause", Value::Null)
+        }
+        (ShortcutAction::ToggleTimer, Some(_)) => ("timer.resume", Value::Null),
+        (ShortcutAction::ToggleTimer, None) => match entries::recent(pool, 1).await?.first() {
+            Some(entry) => ("timer.start", json!({ "entry_id": entry.id })),
+            None => return Ok(false),
+        },
+        (ShortcutAction::StopTimer, Some(_)) => ("timer.stop", Value::Null),
+        (ShortcutAction::StopTimer, None) | (ShortcutAction::QuickLog, _) => return Ok(false),
+    };
+    rpc::dispatch(pool, method, params, Utc::now()).await?;
+    Ok(true)
+}
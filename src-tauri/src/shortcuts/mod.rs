@@ -0,0 +1,265 @@
+//! Global keyboard shortcuts, working while another application has focus.
+//!
+//! Each action is bound to an accelerator such as `Ctrl+Alt+Shift+T`, kept
+//! under `shortcut.<action>` in the settings table: a missing row stands for
+//! the default, an empty value for no shortcut. Accelerators are normalized
+//! before being saved, so that two spellings of the same keys are caught as
+//! a conflict.
+
+#[cfg(desktop)]
+mod global;
+
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::AppHandle;
+
+use crate::db::settings;
+use crate::error::{Error, Result};
+
+#[cfg(desktop)]
+pub use global::register;
+
+/// Event telling the webview to open the quick log search.
+pub const QUICK_LOG: &str = "quick-log";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShortcutAction {
+    /// Pauses the running timer, resumes the paused one, or starts one on the
+    /// entry time was last logged on.
+    ToggleTimer,
+    StopTimer,
+    /// Brings up the main window with the entry search focused.
+    QuickLog,
+}
+
+impl ShortcutAction {
+    pub const ALL: [Self; 3] = [Self::ToggleTimer, Self::StopTimer, Self::QuickLog];
+
+    fn key(self) -> &'static str {
+        match self {
+            Self::ToggleTimer => "shortcut.toggle_timer",
+            Self::StopTimer => "shortcut.stop_timer",
+            Self::QuickLog => "shortcut.quick_log",
+        }
+    }
+
+    pub fn default_accelerator(self) -> &'static str {
+        match self {
+            Self::ToggleTimer => "CmdOrCtrl+Alt+Shift+T",
+            Self::StopTimer => "CmdOrCtrl+Alt+Shift+S",
+            Self::QuickLog => "CmdOrCtrl+Alt+Shift+L",
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            Self::ToggleTimer => "toggle the timer",
+            Self::StopTimer => "stop the timer",
+            Self::QuickLog => "open quick log",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ShortcutBinding {
+    pub action: ShortcutAction,
+    /// Normalized accelerator, none when the action has no shortcut.
+    pub accelerator: Option<String>,
+    pub default_accelerator: String,
+}
+
+/// The shortcut of every action.
+///
+/// A saved accelerator that no longer parses leaves its action without one.
+pub async fn load(pool: &SqlitePool) -> Result<Vec<ShortcutBinding>> {
+    let mut bindings = Vec::new();
+    for action in ShortcutAction::ALL {
+        let default_accelerator = normalize(action.default_accelerator())?;
+        let accelerator = match settings::get(pool, action.key()).await? {
+            None => Some(default_accelerator.clone()),
+            Some(saved) if saved.is_empty() => None,
+            Some(saved) => normalize(&saved).ok(),
+        };
+        bindings.push(ShortcutBinding {
+            action,
+            accelerator,
+            default_accelerator,
+        });
+    }
+    Ok(bindings)
+}
+
+/// Binds `action` to `accelerator`, or unbinds it, refusing keys another
+/// action has. The new shortcuts are registered before being saved, and the
+/// previous ones stay if that fails.
+pub async fn set(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    action: ShortcutAction,
+    accelerator: Option<&str>,
+) -> Result<Vec<ShortcutBinding>> {
+    let previous = load(pool).await?;
+    let bindings = rebind(&previous, action, accelerator)?;
+
+    #[cfg(desktop)]
+    if let Err(err) = register(app, &bindings) {
+        let _ = register(app, &previous);
+        return Err(err);
+    }
+    #[cfg(not(desktop))]
+    let _ = app;
+    let accelerator = bindings
+        .iter()
+        .find(|binding| binding.action == action)
+        .and_then(|binding| binding.accelerator.as_deref());
+    settings::set(pool, action.key(), accelerator.unwrap_or_default()).await?;
+    Ok(bindings)
+}
+
+/// `bindings` with `action` bound to `accelerator`, or unbound when it is
+/// none or blank, refusing keys another action has.
+pub fn rebind(
+    bindings: &[ShortcutBinding],
+    action: ShortcutAction,
+    accelerator: Option<&str>,
+) -> Result<Vec<ShortcutBinding>> {
+    let accelerator = accelerator
+        .filter(|accelerator| !accelerator.trim().is_empty())
+        .map(normalize)
+        .transpose()?;
+    if let Some(accelerator) = &accelerator {
+        if let Some(other) = bindings
+            .iter()
+            .find(|other| other.action != action && other.accelerator.as_ref() == Some(accelerator))
+        {
+            return Err(Error::invalid(
+                "accelerator",
+                format!(
+                    "{accelerator} is already used to {}",
+                    other.action.describe()
+                ),
+            ));
+        }
+    }
+    let mut bindings = bindings.to_vec();
+    for binding in &mut bindings {
+        if binding.action == action {
+            binding.accelerator = accelerator.clone();
+        }
+    }
+    Ok(bindings)
+}
+
+/// Modifiers in the order they are written back, `CmdOrCtrl` being Super on
+/// macOS and Ctrl elsewhere.
+const MODIFIERS: [(&str, &[&str]); 4] = [
+    ("Ctrl", &["CTRL", "CONTROL"]),
+    ("Alt", &["ALT", "OPTION"]),
+    ("Shift", &["SHIFT"]),
+    ("Super", &["SUPER", "CMD", "COMMAND", "META"]),
+];
+
+const CMD_OR_CTRL: [&str; 4] = [
+    "CMDORCTRL",
+    "CMDORCONTROL",
+    "COMMANDORCTRL",
+    "COMMANDORCONTROL",
+];
+
+/// Keys other than letters, digits and function keys.
+const NAMED_KEYS: [(&str, &[&str]); 26] = [
+    ("Space", &["SPACE"]),
+    ("Enter", &["ENTER"]),
+    ("Tab", &["TAB"]),
+    ("Escape", &["ESCAPE", "ESC"]),
+    ("Backspace", &["BACKSPACE"]),
+    ("Delete", &["DELETE"]),
+    ("Insert", &["INSERT"]),
+    ("Home", &["HOME"]),
+    ("End", &["END"]),
+    ("PageUp", &["PAGEUP"]),
+    ("PageDown", &["PAGEDOWN"]),
+    ("ArrowUp", &["ARROWUP", "UP"]),
+    ("ArrowDown", &["ARROWDOWN", "DOWN"]),
+    ("ArrowLeft", &["ARROWLEFT", "LEFT"]),
+    ("ArrowRight", &["ARROWRIGHT", "RIGHT"]),
+    ("Backquote", &["BACKQUOTE", "`"]),
+    ("Minus", &["MINUS", "-"]),
+    ("Equal", &["EQUAL", "="]),
+    ("BracketLeft", &["BRACKETLEFT", "["]),
+    ("BracketRight", &["BRACKETRIGHT", "]"]),
+    ("Backslash", &["BACKSLASH", "\\"]),
+    ("Semicolon", &["SEMICOLON", ";"]),
+    ("Quote", &["QUOTE", "'"]),
+    ("Comma", &["COMMA", ","]),
+    ("Period", &["PERIOD", "."]),
+    ("Slash", &["SLASH", "/"]),
+];
+
+/// Writes `accelerator` as e.g. `Ctrl+Alt+Shift+T`, checking it has a single
+/// key, and a modifier unless that key is a function key.
+pub fn normalize(accelerator: &str) -> Result<String> {
+    let invalid = |reason: String| Error::invalid("accelerator", reason);
+    let mut modifiers = [false; 4];
+    let mut key = None;
+    for token in accelerator.split('+').map(str::trim) {
+        if token.is_empty() {
+            return Err(invalid(format!("{accelerator:?} has an empty key")));
+        }
+        let upper = token.to_uppercase();
+        let modifier = if CMD_OR_CTRL.contains(&upper.as_str()) {
+            Some(if cfg!(target_os = "macos") { 3 } else { 0 })
+        } else {
+            MODIFIERS
+                .iter()
+                .position(|(_, names)| names.contains(&upper.as_str()))
+        };
+        match (modifier, &key) {
+            (Some(modifier), _) => modifiers[modifier] = true,
+            (None, None) => {
+                key =
+                    Some(normalize_key(&upper).ok_or_else(|| {
+                        invalid(format!("unknown key {token:?} in {accelerator:?}"))
+                    })?)
+            }
+            (None, Some(_)) => {
+                return Err(invalid(format!("{accelerator:?} has more than one key")))
+            }
+        }
+    }
+    let key = key.ok_or_else(|| invalid(format!("{accelerator:?} has no key")))?;
+    let function_key = key.len() > 1 && key.starts_with('F') && key[1..].parse::<u8>().is_ok();
+    if !modifiers.contains(&true) && !function_key {
+        return Err(invalid(format!(
+            "{accelerator:?} needs a modifier such as Ctrl or Alt"
+        )));
+    }
+    let mut parts: Vec<&str> = MODIFIERS
+        .iter()
+        .zip(modifiers)
+        .filter(|(_, used)| *used)
+        .map(|((name, _), _)| *name)
+        .collect();
+    parts.push(&key);
+    Ok(parts.join("+"))
+}
+
+fn normalize_key(upper: &str) -> Option<String> {
+    // `KeyT` and `Digit1` are the names of `T` and `1` in key codes.
+    let single = upper
+        .strip_prefix("KEY")
+        .or_else(|| upper.strip_prefix("DIGIT"))
+        .filter(|rest| rest.len() == 1)
+        .unwrap_or(upper);
+    if single.len() == 1 && single.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Some(single.to_string());
+    }
+    if let Some(number) = upper.strip_prefix('F').and_then(|n| n.parse::<u8>().ok()) {
+        return (1..=24).contains(&number).then(|| format!("F{number}"));
+    }
+    NAMED_KEYS
+        .iter()
+        .find(|(_, names)| names.contains(&upper))
+        .map(|(name, _)| name.to_string())
+}
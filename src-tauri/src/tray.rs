@@ -0,0 +1,312 @@
+//! The tray icon, to switch tasks without raising the main window.
+//!
+//! Its menu lists the entries time was last logged on, the one with the
+//! timer checked, then "Stop timer"; the tooltip shows the timer and today's
+//! total. [`menu`] decides what it shows without touching Tauri, and
+//! [`refresh`] applies that to the icon whenever the timer changes and every
+//! half minute for the times to keep up.
+//!
+//! The webview sends the [`Labels`] of its own language through
+//! [`set_labels`]; the menu is in English until it does.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use sqlx::SqlitePool;
+use tauri::menu::{CheckMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Emitter, Listener, Manager};
+
+use crate::cli::status::{Status, StatusFormat};
+use crate::db::timer::{self, ActiveTimer};
+use crate::db::{entries, Database, Entry};
+use crate::error::Result;
+use crate::{rpc, TIMER_CHANGED};
+
+const TRAY_ID: &str = "main";
+
+/// Entries listed in the menu.
+const RECENT_ENTRIES: i64 = 8;
+
+const REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// What a menu item does when clicked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Starts a timer on the entry, or resumes it if it has the paused one.
+    Start(i64),
+    Stop,
+    /// Shows and focuses the main window.
+    Show,
+    Quit,
+}
+
+impl Action {
+    /// The menu item id the action is read back from.
+    pub fn id(self) -> String {
+        match self {
+            Self::Start(entry_id) => format!("entry:{entry_id}"),
+            Self::Stop => "stop".to_string(),
+            Self::Show => "show".to_string(),
+            Self::Quit => "quit".to_string(),
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        match id {
+            "stop" => Some(Self::Stop),
+            "show" => Some(Self::Show),
+            "quit" => Some(Self::Quit),
+            _ => id.strip_prefix("entry:")?.parse().ok().map(Self::Start),
+        }
+    }
+}
+
+/// The menu's own items, as worded by the webview's translations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Labels {
+    pub stop_timer: String,
+    pub show: String,
+    pub quit: String,
+}
+
+impl Default for Labels {
+    fn default() -> Self {
+        Self {
+            stop_timer: "Stop timer".to_string(),
+            show: "Show Timeloop".to_string(),
+            quit: "Quit".to_string(),
+        }
+    }
+}
+
+/// The labels the menu is built with, kept in the app's state.
+struct CurrentLabels(Mutex<Labels>);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Item {
+    Entry {
+        entry_id: i64,
+        label: String,
+        /// Whether the timer is on it.
+        checked: bool,
+    },
+    Stop {
+        label: String,
+        enabled: bool,
+    },
+    Separator,
+    Show {
+        label: String,
+    },
+    Quit {
+        label: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrayMenu {
+    pub items: Vec<Item>,
+    pub tooltip: String,
+}
+
+/// The menu for `recent` entries, most recent first, and `status`, its own
+/// items worded by `labels`.
+///
+/// The timer's entry is listed first when it is not among the recent ones,
+/// as happens when no time was logged on it yet.
+pub fn menu(recent: &[Entry], status: &Status, labels: &Labels) -> TrayMenu {
+    let timer = status.timer();
+    let mut items = Vec::new();
+    if let Some(timer) = timer.filter(|timer| recent.iter().all(|e| e.id != timer.entry_id)) {
+        items.push(Item::Entry {
+            entry_id: timer.entry_id,
+            label: label(&timer.entry_title),
+            checked: true,
+        });
+    }
+    items.extend(recent.iter().map(|entry| Item::Entry {
+        entry_id: entry.id,
+        label: label(&entry.title),
+        checked: timer.is_some_and(|timer| timer.entry_id == entry.id),
+    }));
+    if !items.is_empty() {
+        items.push(Item::Separator);
+    }
+    items.extend([
+        Item::Stop {
+            label: label(&labels.stop_timer),
+            enabled: timer.is_some(),
+        },
+        Item::Separator,
+        Item::Show {
+            label: label(&labels.show),
+        },
+        Item::Quit {
+            label: label(&labels.quit),
+        },
+    ]);
+    TrayMenu {
+        items,
+        tooltip: status.render(StatusFormat::Text),
+    }
+}
+
+/// Escapes the `&` menus take as a mnemonic marker.
+fn label(title: &str) -> String {
+    title.replace('&', "&&")
+}
+
+/// Adds the tray icon, refreshed on `TIMER_CHANGED` and every half minute.
+pub fn create(app: &AppHandle, pool: SqlitePool) -> tauri::Result<()> {
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("Timeloop")
+        .show_menu_on_left_click(true)
+        .on_menu_event(on_menu_event);
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+    app.manage(CurrentLabels(Mutex::new(Labels::default())));
+
+    let (handle, listened) = (app.clone(), pool.clone());
+    app.listen(TIMER_CHANGED, move |_| {
+        let (handle, pool) = (handle.clone(), listened.clone());
+        tauri::async_runtime::spawn(async move { refresh(&handle, &pool).await });
+    });
+    tauri::async_runtime::spawn(keep_fresh(app.clone(), pool));
+    Ok(())
+}
+
+/// Words the menu's own items with `labels` from now on.
+pub fn set_labels(app: &AppHandle, labels: Labels) {
+    *app.state::<CurrentLabels>().0.lock().unwrap() = labels;
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let pool = app.state::<Database>().pool().clone();
+        refresh(&app, &pool).await;
+    });
+}
+
+async fn keep_fresh(app: AppHandle, pool: SqlitePool) {
+    loop {
+        refresh(&app, &pool).await;
+        tokio::time::sleep(REFRESH_INTERVAL).await;
+    }
+}
+
+/// Shows the current timer, recent entries and today's total.
+async fn refresh(app: &AppHandle, pool: &SqlitePool) {
+    let labels = app.state::<CurrentLabels>().0.lock().unwrap().clone();
+    match read(pool, Utc::now(), &labels).await {
+        Ok(menu) => {
+            if let Err(err) = apply(app, &menu) {
+                eprintln!("timeloop: could not update the tray menu: {err}");
+            }
+        }
+        Err(err) => eprintln!("timeloop: could not update the tray menu: {err}"),
+    }
+}
+
+async fn read(pool: &SqlitePool, now: DateTime<Utc>, labels: &Labels) -> Result<TrayMenu> {
+    let recent = entries::recent(pool, RECENT_ENTRIES).await?;
+    let status = Status::read(pool, now).await?;
+    Ok(menu(&recent, &status, labels))
+}
+
+fn apply(app: &AppHandle, tray_menu: &TrayMenu) -> tauri::Result<()> {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return Ok(());
+    };
+    let menu = Menu::new(app)?;
+    for item in &tray_menu.items {
+        match item {
+            Item::Entry {
+                entry_id,
+                label,
+                checked,
+            } => {
+                let id = Action::Start(*entry_id).id();
+                menu.append(&CheckMenuItem::with_id(
+                    app,
+                    id,
+                    label,
+                    true,
+                    *checked,
+                    None::<&str>,
+                )?)?;
+            }
+            Item::Stop { label, enabled } => menu.append(&MenuItem::with_id(
+                app,
+                Action::Stop.id(),
+                label,
+                *enabled,
+                None::<&str>,
+            )?)?,
+            Item::Separator => menu.append(&PredefinedMenuItem::separator(app)?)?,
+            Item::Show { label } => menu.append(&MenuItem::with_id(
+                app,
+                Action::Show.id(),
+                label,
+                true,
+                None::<&str>,
+            )?)?,
+            Item::Quit { label } => menu.append(&MenuItem::with_id(
+                app,
+                Action::Quit.id(),
+                label,
+                true,
+                None::<&str>,
+            )?)?,
+        }
+    }
+    tray.set_menu(Some(menu))?;
+    tray.set_tooltip(Some(&tray_menu.tooltip))?;
+    Ok(())
+}
+
+fn on_menu_event(app: &AppHandle, event: MenuEvent) {
+    let Some(action) = Action::from_id(event.id().as_ref()) else {
+        return;
+    };
+    match action {
+        Action::Show => crate::show_main_window(app),
+        Action::Quit => app.exit(0),
+        Action::Start(_) | Action::Stop => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                let pool = app.state::<Database>().pool().clone();
+                match run(&pool, action).await {
+                    Ok(()) => {
+                        let _ = app.emit(TIMER_CHANGED, ());
+                    }
+                    Err(err) => eprintln!("timeloop: {err}"),
+                }
+                // Undoes the check a click toggled when the timer did not move.
+                refresh(&app, &pool).await;
+            });
+        }
+    }
+}
+
+async fn run(pool: &SqlitePool, action: Action) -> Result<()> {
+    let (method, params) = match action {
+        Action::Start(entry_id) => match timer::get(pool).await? {
+            Some(ActiveTimer {
+                entry_id: current,
+                resumed_at,
+                ..
+            }) if current == entry_id => match resumed_at {
+                Some(_) => return Ok(()),
+                None => ("timer.resume", Value::Null),
+            },
+            _ => ("timer.start", json!({ "entry_id": entry_id })),
+        },
+        Action::Stop => ("timer.stop", Value::Null),
+        Action::Show | Action::Quit => return Ok(()),
+    };
+    rpc::dispatch(pool, method, params, Utc::now()).await?;
+    Ok(())
+}
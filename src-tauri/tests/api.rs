@@ -0,0 +1,186 @@
+//! The HTTP API: its routes on a database in memory, and the token checked
+//! by a running server.
+
+mod common;
+
+use std::sync::Arc;
+
+use hyper::{Method, StatusCode};
+use serde_json::{json, Value};
+use timeloop_lib::api::routes::route;
+use timeloop_lib::api::{ApiConfig, ApiServer, CONFIG_FILE};
+use timeloop_lib::db::{entries, Database};
+use timeloop_lib::Error;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use common::{database_path, memory};
+
+async fn send(
+    pool: &sqlx::SqlitePool,
+    method: Method,
+    uri: &str,
+    body: Value,
+) -> (StatusCode, Value) {
+    let (path, query) = uri.split_once('?').unwrap_or((uri, ""));
+    let body = match body {
+        Value::Null => Vec::new(),
+        body => body.to_string().into_bytes(),
+    };
+    route(pool, &method, path, query, &body).await.unwrap()
+}
+
+#[tokio::test]
+async fn categories_keep_their_color_when_updated_without_one() {
+    let pool = memory().await;
+    let (status, created) = send(
+        &pool,
+        Method::POST,
+        "/v1/categories",
+        json!({ "name": "Clients", "color": "#ef4444" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let id = created["id"].as_i64().unwrap();
+
+    let uri = format!("/v1/categories/{id}");
+    let (status, _) = send(&pool, Method::PUT, &uri, json!({ "name": "Customers" })).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (_, categories) = send(&pool, Method::GET, "/v1/categories", Value::Null).await;
+    assert_eq!(categories[0]["name"], "Customers");
+    assert_eq!(categories[0]["color"], "#ef4444");
+
+    send(
+        &pool,
+        Method::PUT,
+        &uri,
+        json!({ "name": "Customers", "color": "#22c55e" }),
+    )
+    .await;
+    let (_, categories) = send(&pool, Method::GET, "/v1/categories", Value::Null).await;
+    assert_eq!(categories[0]["color"], "#22c55e");
+
+    assert!(matches!(
+        route(
+            &pool,
+            &Method::PUT,
+            "/v1/categories/99",
+            "",
+            br#"{"name":"Customers"}"#
+        )
+        .await,
+        Err(Error::NotFound("category"))
+    ));
+    let (status, _) = send(&pool, Method::DELETE, &uri, Value::Null).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+async fn query_strings_are_decoded() {
+    let pool = memory().await;
+    entries::create(&pool, "Client call", None).await.unwrap();
+    entries::create(&pool, "Clientele", None).await.unwrap();
+
+    for uri in ["/v1/entries?q=Client%20call", "/v1/entries?q=Client+call"] {
+        let (status, found) = send(&pool, Method::GET, uri, Value::Null).await;
+        assert_eq!(status, StatusCode::OK);
+        let titles: Vec<&str> = found
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| entry["title"].as_str().unwrap())
+            .collect();
+        assert_eq!(titles, ["Client call"], "{uri}");
+    }
+    let (_, found) = send(&pool, Method::GET, "/v1/entries?q=%43lient", Value::Null).await;
+    assert_eq!(found.as_array().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn bad_requests_are_refused() {
+    let pool = memory().await;
+    let refused = |method: Method, path: &'static str, query: &'static str, body: &'static str| {
+        let pool = pool.clone();
+        async move {
+            route(&pool, &method, path, query, body.as_bytes())
+                .await
+                .unwrap_err()
+        }
+    };
+
+    assert!(matches!(
+        refused(Method::GET, "/v1/nothing", "", "").await,
+        Error::NotFound("route")
+    ));
+    assert!(matches!(
+        refused(Method::PATCH, "/v1/entries", "", "").await,
+        Error::NotFound("route")
+    ));
+    assert!(matches!(
+        refused(Method::GET, "/v1/entries/one", "", "").await,
+        Error::Invalid { field: "id", .. }
+    ));
+    assert!(matches!(
+        refused(Method::GET, "/v1/entries/99", "", "").await,
+        Error::NotFound("entry")
+    ));
+    assert!(matches!(
+        refused(Method::GET, "/v1/entries", "from=2026-01-01", "").await,
+        Error::Invalid { field: "from", .. }
+    ));
+    assert!(matches!(
+        refused(Method::GET, "/v1/time-entries", "limit=ten", "").await,
+        Error::Invalid { field: "limit", .. }
+    ));
+    assert!(matches!(
+        refused(Method::POST, "/v1/categories", "", "{").await,
+        Error::Json(_)
+    ));
+}
+
+#[test]
+fn the_first_token_is_saved() {
+    let dir = database_path("api-config").parent().unwrap().to_path_buf();
+    let config = ApiConfig::load(&dir).unwrap();
+    assert!(!config.enabled);
+    assert!(dir.join(CONFIG_FILE).exists());
+    assert_eq!(ApiConfig::load(&dir).unwrap().token, config.token);
+}
+
+/// Sends a `GET /v1/timer` on `stream` and returns the response's status.
+async fn status(stream: &mut TcpStream, token: &str) -> u16 {
+    let request = format!(
+        "GET /v1/timer HTTP/1.1\r\nHost: localhost\r\nAuthorization: Bearer {token}\r\n\r\n"
+    );
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut buffer = vec![0; 4096];
+    let read = stream.read(&mut buffer).await.unwrap();
+    let response = String::from_utf8_lossy(&buffer[..read]);
+    response.split(' ').nth(1).unwrap().parse().unwrap()
+}
+
+#[tokio::test]
+async fn requests_must_send_the_current_token() {
+    let path = database_path("api-server");
+    let dir = path.parent().unwrap().to_path_buf();
+    std::fs::write(
+        dir.join(CONFIG_FILE),
+        r#"{"enabled":true,"port":0,"token":"first"}"#,
+    )
+    .unwrap();
+    let database = Database::open(&path).await.unwrap();
+    let server = ApiServer::new(dir, database.pool().clone(), Arc::new(|| {}));
+    let address = server.start_if_enabled().await.unwrap().unwrap();
+
+    let mut stream = TcpStream::connect(address).await.unwrap();
+    assert_eq!(status(&mut stream, "first").await, 200);
+    assert_eq!(status(&mut stream, "second").await, 401);
+
+    // A new token applies to the connection already open too.
+    let config = server.reset_token().await.unwrap();
+    assert_eq!(status(&mut stream, "first").await, 401);
+    assert_eq!(status(&mut stream, &config.token).await, 200);
+
+    server.set_enabled(false).await.unwrap();
+    assert!(TcpStream::connect(address).await.is_err());
+}
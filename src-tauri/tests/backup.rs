@@ -0,0 +1,147 @@
+//! Backups restored unchanged, and refused when made by a newer app.
+
+mod common;
+
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use timeloop_lib::db::backup::{self, Backup};
+use timeloop_lib::db::overlaps::OverlapPolicy;
+use timeloop_lib::db::{categories, entries, migrations, time_entries, timer, TimeRange};
+use timeloop_lib::Error;
+
+use common::{count, memory};
+
+fn at(time: &str) -> DateTime<Utc> {
+    time.parse().unwrap()
+}
+
+/// The rows of a backup, without the time it was made.
+fn rows(backup: &Backup) -> Value {
+    let mut value = serde_json::to_value(backup).unwrap();
+    value.as_object_mut().unwrap().remove("exported_at");
+    value
+}
+
+#[tokio::test]
+async fn backups_round_trip_unchanged() {
+    let pool = memory().await;
+    let clients = categories::create(&pool, "Clients", "#ef4444")
+        .await
+        .unwrap();
+    let call = entries::create(&pool, "Client call", Some(clients))
+        .await
+        .unwrap();
+    let admin = entries::create(&pool, "Admin", None).await.unwrap();
+    // Across midnight, an hour ahead of UTC.
+    let range = TimeRange::from_bounds(
+        Some("2026-10-05T23:30:00+01:00".parse().unwrap()),
+        Some("2026-10-06T00:45:00+01:00".parse().unwrap()),
+    )
+    .unwrap();
+    time_entries::create(
+        &pool,
+        call,
+        75,
+        "2026-10-05",
+        Some("Kickoff"),
+        range,
+        OverlapPolicy::Refuse,
+    )
+    .await
+    .unwrap();
+    time_entries::create(
+        &pool,
+        admin,
+        30,
+        "2026-10-06",
+        None,
+        None,
+        OverlapPolicy::Refuse,
+    )
+    .await
+    .unwrap();
+    // Restored rows keep when they were made, not when they were restored.
+    for table in ["categories", "entries", "time_entries"] {
+        sqlx::query(&format!(
+            "UPDATE {table} SET created_at = '2026-01-02 03:04:05'"
+        ))
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+
+    let made = backup::create(&pool, at("2026-10-07T08:00:00Z"))
+        .await
+        .unwrap();
+    assert_eq!(made.schema_version, migrations::latest_version());
+    let json = serde_json::to_string(&made).unwrap();
+    let parsed = backup::parse(&json).unwrap();
+    assert_eq!(rows(&parsed), rows(&made));
+
+    // Restoring replaces what the database holds, the timer included.
+    let other = memory().await;
+    let stray = entries::create(&other, "Stray", None).await.unwrap();
+    timer::start(&other, stray, None, at("2026-10-07T09:00:00Z"))
+        .await
+        .unwrap();
+    let summary = backup::restore(&other, &parsed).await.unwrap();
+    assert_eq!(
+        (summary.categories, summary.entries, summary.time_entries),
+        (1, 2, 2)
+    );
+    assert_eq!(count(&other, "active_timers").await, 0);
+
+    let restored = backup::create(&other, at("2026-10-07T10:00:00Z"))
+        .await
+        .unwrap();
+    assert_eq!(rows(&restored), rows(&made));
+    let logged = time_entries::list_for_entry(&other, call).await.unwrap();
+    assert_eq!(logged[0].range(), range);
+    assert_eq!(
+        made.time_entries[0].created_at.as_deref(),
+        Some("2026-01-02 03:04:05")
+    );
+}
+
+#[tokio::test]
+async fn backups_from_a_newer_app_are_refused() {
+    let pool = memory().await;
+    let mut made = backup::create(&pool, Utc::now()).await.unwrap();
+    made.schema_version = migrations::latest_version() + 1;
+    let json = serde_json::to_string(&made).unwrap();
+    assert!(matches!(
+        backup::parse(&json),
+        Err(Error::Invalid {
+            field: "schema_version",
+            ..
+        })
+    ));
+    assert!(matches!(
+        backup::restore(&pool, &made).await,
+        Err(Error::Invalid {
+            field: "schema_version",
+            ..
+        })
+    ));
+
+    // Only the header is read before refusing, so rows of an unknown shape
+    // do not hide the reason.
+    let newer = format!(
+        r#"{{"format":"timeloop","schema_version":{},"categories":"unknown"}}"#,
+        migrations::latest_version() + 1
+    );
+    assert!(matches!(
+        backup::parse(&newer),
+        Err(Error::Invalid {
+            field: "schema_version",
+            ..
+        })
+    ));
+    assert!(matches!(
+        backup::parse(r#"{"format":"other","schema_version":1}"#),
+        Err(Error::Invalid {
+            field: "format",
+            ..
+        })
+    ));
+}
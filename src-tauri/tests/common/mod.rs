@@ -0,0 +1,71 @@
+//! Helpers shared by the database tests.
+
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+
+use chrono::Local;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+
+/// A database path no other test uses.
+pub fn database_path(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "timeloop-{test}-{}-{}",
+        std::process::id(),
+        Local::now().timestamp_nanos_opt().unwrap_or_default()
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.join(timeloop_lib::db::DATABASE_FILE)
+}
+
+/// Connects to the database at `path`, creating it if needed, without
+/// migrating it.
+pub async fn connect(path: &Path) -> SqlitePool {
+    let options = SqliteConnectOptions::new()
+        .filename(path)
+        .create_if_missing(true);
+    SqlitePool::connect_with(options).await.unwrap()
+}
+
+/// A migrated database living in memory, on a single connection as each
+/// connection to `:memory:` opens a database of its own.
+pub async fn memory() -> SqlitePool {
+    let options = SqliteConnectOptions::new()
+        .in_memory(true)
+        .foreign_keys(true);
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(options)
+        .await
+        .unwrap();
+    timeloop_lib::db::migrations::run(&pool).await.unwrap();
+    pool
+}
+
+pub async fn count(pool: &SqlitePool, table: &str) -> i64 {
+    sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {table}"))
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+pub async fn has_table(pool: &SqlitePool, table: &str) -> bool {
+    sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = $1",
+    )
+    .bind(table)
+    .fetch_one(pool)
+    .await
+    .unwrap()
+        > 0
+}
+
+pub async fn has_column(pool: &SqlitePool, table: &str, column: &str) -> bool {
+    sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM pragma_table_info($1) WHERE name = $2")
+        .bind(table)
+        .bind(column)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+        > 0
+}
@@ -0,0 +1,146 @@
+//! The D-Bus service on a private bus, run when `dbus-daemon` is installed.
+
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use std::io::{BufRead, BufReader, ErrorKind};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use futures_util::StreamExt;
+use timeloop_lib::db::Database;
+use timeloop_lib::dbus;
+use zbus::proxy::SignalStream;
+use zbus::{Connection, Proxy};
+
+use common::database_path;
+
+/// A `dbus-daemon` of the test's own, killed when dropped.
+struct Bus {
+    daemon: Child,
+    address: String,
+}
+
+impl Bus {
+    /// Starts a bus listening in `dir`, none if `dbus-daemon` is missing.
+    fn start(dir: &Path) -> Option<Self> {
+        let config = dir.join("bus.conf");
+        std::fs::write(
+            &config,
+            format!(
+                r#"<!DOCTYPE busconfig PUBLIC "-//freedesktop//DTD D-Bus Bus Configuration 1.0//EN"
+ "http://www.freedesktop.org/standards/dbus/1.0/busconfig.dtd">
+<busconfig>
+  <type>session</type>
+  <listen>unix:dir={}</listen>
+  <auth>EXTERNAL</auth>
+  <policy context="default">
+    <allow send_destination="*" eavesdrop="true"/>
+    <allow eavesdrop="true"/>
+    <allow own="*"/>
+  </policy>
+</busconfig>"#,
+                dir.display()
+            ),
+        )
+        .unwrap();
+        let mut daemon = match Command::new("dbus-daemon")
+            .arg(format!("--config-file={}", config.display()))
+            .args(["--nofork", "--print-address"])
+            .stdout(Stdio::piped())
+            .spawn()
+        {
+            Ok(daemon) => daemon,
+            Err(err) if err.kind() == ErrorKind::NotFound => return None,
+            Err(err) => panic!("could not start dbus-daemon: {err}"),
+        };
+        let mut address = String::new();
+        BufReader::new(daemon.stdout.take().unwrap())
+            .read_line(&mut address)
+            .unwrap();
+        Some(Self {
+            daemon,
+            address: address.trim().to_string(),
+        })
+    }
+
+    async fn connect(&self) -> Connection {
+        zbus::connection::Builder::address(self.address.as_str())
+            .unwrap()
+            .build()
+            .await
+            .unwrap()
+    }
+}
+
+impl Drop for Bus {
+    fn drop(&mut self) {
+        let _ = self.daemon.kill();
+        let _ = self.daemon.wait();
+    }
+}
+
+/// The entry and whether the timer runs, from the next `TimerChanged`.
+async fn next_signal(signals: &mut SignalStream<'_>) -> (String, bool) {
+    let signal = signals.next().await.unwrap();
+    signal.body().deserialize().unwrap()
+}
+
+#[tokio::test]
+async fn methods_drive_the_timer_and_signal_its_changes() {
+    let path = database_path("dbus");
+    let Some(bus) = Bus::start(path.parent().unwrap()) else {
+        eprintln!("dbus-daemon is not installed, skipping");
+        return;
+    };
+    let database = Database::open(&path).await.unwrap();
+    let pool = database.pool().clone();
+
+    // As in the app, each change is followed by `TimerChanged`.
+    let service = bus.connect().await;
+    let changes = Arc::new(AtomicUsize::new(0));
+    let on_change = {
+        let (changes, service, pool) = (changes.clone(), service.clone(), pool.clone());
+        Arc::new(move || {
+            changes.fetch_add(1, Ordering::SeqCst);
+            let (service, pool) = (service.clone(), pool.clone());
+            tokio::spawn(async move { dbus::timer_changed(&service, &pool).await.unwrap() });
+        })
+    };
+    dbus::register(&service, pool, on_change).await.unwrap();
+
+    let client = bus.connect().await;
+    let proxy = Proxy::new(&client, dbus::NAME, dbus::PATH, dbus::NAME)
+        .await
+        .unwrap();
+    let mut signals = proxy.receive_signal("TimerChanged").await.unwrap();
+
+    let title: String = proxy.call("StartTimer", &("Client call",)).await.unwrap();
+    assert_eq!(title, "Client call");
+    assert_eq!(
+        next_signal(&mut signals).await,
+        ("Client call".to_string(), true)
+    );
+
+    proxy.call::<_, _, ()>("PauseTimer", &()).await.unwrap();
+    assert_eq!(
+        next_signal(&mut signals).await,
+        ("Client call".to_string(), false)
+    );
+
+    let (minutes, timer_seconds): (i64, i64) = proxy.call("TodayTotal", &()).await.unwrap();
+    assert_eq!(minutes, 0);
+    assert!(timer_seconds < 5);
+
+    // Stopped within half a minute, the timer logs nothing.
+    let logged: i64 = proxy.call("StopTimer", &()).await.unwrap();
+    assert_eq!(logged, 0);
+    assert_eq!(next_signal(&mut signals).await, (String::new(), false));
+
+    let err = proxy.call::<_, _, i64>("StopTimer", &()).await.unwrap_err();
+    assert!(err.to_string().contains("timer"), "{err}");
+    assert_eq!(changes.load(Ordering::SeqCst), 3);
+}
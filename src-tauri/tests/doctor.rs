@@ -0,0 +1,214 @@
+//! `timeloop-cli doctor`: problems found in a damaged database, and what
+//! fixing them changes.
+
+mod common;
+
+use chrono::Local;
+use sqlx::SqlitePool;
+use timeloop_lib::db::doctor::{self, Fix, Problem};
+use timeloop_lib::db::{snapshots, Database};
+
+use common::{connect, count, database_path};
+
+/// Writes rows the constraints would reject, through a connection that
+/// does not enforce them.
+async fn damage(database: &Database) {
+    let pool = connect(database.path()).await;
+    sqlx::raw_sql(
+        "
+        PRAGMA foreign_keys = OFF;
+        PRAGMA ignore_check_constraints = ON;
+        INSERT INTO categories (id, name) VALUES (1, 'Clients');
+        INSERT INTO entries (id, title, category_id) VALUES
+            (1, 'Support', 1), (2, 'Deleted category', 9), (3, 'Website', 1), (4, ' website ', 1),
+            (5, 'support', NULL);
+        INSERT INTO time_entries
+            (id, entry_id, duration, date, created_at, started_at, ended_at, utc_offset)
+        VALUES
+            (1, 1, 30, '2024-03-01', '2024-03-01 09:00:00', NULL, NULL, NULL),
+            (2, 99, 30, '2024-03-01', '2024-03-01 09:00:00', NULL, NULL, NULL),
+            (3, 1, 30, '2024-02-30', '2024-03-01 09:00:00', NULL, NULL, NULL),
+            (4, 1, 60, 'monday', '2024-03-04 10:00:00',
+                '2024-03-04T08:00:00+00:00', '2024-03-04T09:00:00+00:00', 60),
+            (5, 1, 0, '2024-03-05', '2024-03-05 09:00:00', NULL, NULL, NULL),
+            (6, 1, 3000, '2024-03-06', '2024-03-06 09:00:00', NULL, NULL, NULL),
+            (7, 1, 2000, '2024-03-07', '2024-03-07 10:00:00',
+                '2024-03-07T08:00:00+00:00', '2024-03-07T09:00:00+00:00', 60),
+            (8, 4, 45, '2024-03-08', '2024-03-08 09:00:00', NULL, NULL, NULL);
+        INSERT INTO active_timers (id, entry_id, started_at) VALUES (1, 4, '2024-03-09T08:00:00Z');
+        ",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    pool.close().await;
+}
+
+async fn rows(pool: &SqlitePool) -> Vec<(i64, i64, i64, String)> {
+    sqlx::query_as("SELECT id, entry_id, duration, date FROM time_entries ORDER BY id")
+        .fetch_all(pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn finds_every_problem_without_changing_anything() {
+    let database = Database::open(&database_path("doctor-check"))
+        .await
+        .unwrap();
+    damage(&database).await;
+    let pool = database.pool();
+    let before = rows(pool).await;
+
+    let issues = doctor::check(pool).await.unwrap();
+    let found: Vec<(Problem, Option<Fix>)> = issues
+        .into_iter()
+        .map(|issue| (issue.problem, issue.fix))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            (
+                Problem::MissingReference {
+                    table: "entries".to_string(),
+                    id: 2,
+                    parent: "categories".to_string(),
+                },
+                Some(Fix::Uncategorize { entry_id: 2 }),
+            ),
+            (
+                Problem::OrphanedTimeEntry {
+                    id: 2,
+                    entry_id: 99
+                },
+                Some(Fix::DeleteTimeEntry { id: 2 }),
+            ),
+            (
+                Problem::InvalidDate {
+                    id: 3,
+                    date: "2024-02-30".to_string(),
+                },
+                Some(Fix::SetDate {
+                    id: 3,
+                    date: "2024-03-01".to_string(),
+                }),
+            ),
+            (
+                Problem::InvalidDate {
+                    id: 4,
+                    date: "monday".to_string(),
+                },
+                Some(Fix::SetDate {
+                    id: 4,
+                    date: "2024-03-04".to_string(),
+                }),
+            ),
+            (
+                Problem::InvalidDuration { id: 5, duration: 0 },
+                Some(Fix::DeleteTimeEntry { id: 5 }),
+            ),
+            (
+                Problem::InvalidDuration {
+                    id: 6,
+                    duration: 3000,
+                },
+                None,
+            ),
+            (
+                Problem::InvalidDuration {
+                    id: 7,
+                    duration: 2000,
+                },
+                Some(Fix::SetDuration {
+                    id: 7,
+                    duration: 60
+                }),
+            ),
+            (
+                Problem::DuplicateEntries {
+                    title: "Support".to_string(),
+                    entry_ids: vec![1, 5],
+                },
+                None,
+            ),
+            (
+                Problem::DuplicateEntries {
+                    title: "Website".to_string(),
+                    entry_ids: vec![3, 4],
+                },
+                Some(Fix::MergeEntries {
+                    into: 3,
+                    from: vec![4],
+                }),
+            ),
+        ]
+    );
+    assert_eq!(rows(pool).await, before);
+    assert_eq!(count(pool, "entries").await, 5);
+}
+
+#[tokio::test]
+async fn repair_applies_the_fixes_after_a_snapshot() {
+    let path = database_path("doctor-repair");
+    let database = Database::open(&path).await.unwrap();
+    damage(&database).await;
+    let pool = database.pool();
+
+    let report = doctor::repair(&database, Local::now()).await.unwrap();
+    assert!(report.fixed);
+    assert_eq!(report.issues.len(), 9);
+    let snapshot = report.snapshot.unwrap();
+    assert!(snapshot.starts_with("pre-repair-"));
+    let snapshot = connect(&snapshots::directory(&path).join(snapshot)).await;
+    assert_eq!(count(&snapshot, "time_entries").await, 8);
+    snapshot.close().await;
+
+    assert_eq!(
+        rows(pool).await,
+        [
+            (1, 1, 30, "2024-03-01".to_string()),
+            (3, 1, 30, "2024-03-01".to_string()),
+            (4, 1, 60, "2024-03-04".to_string()),
+            (6, 1, 3000, "2024-03-06".to_string()),
+            (7, 1, 60, "2024-03-07".to_string()),
+            (8, 3, 45, "2024-03-08".to_string()),
+        ]
+    );
+    let category: Option<i64> = sqlx::query_scalar("SELECT category_id FROM entries WHERE id = 2")
+        .fetch_one(pool)
+        .await
+        .unwrap();
+    assert_eq!(category, None);
+    let timer: i64 = sqlx::query_scalar("SELECT entry_id FROM active_timers")
+        .fetch_one(pool)
+        .await
+        .unwrap();
+    assert_eq!(timer, 3);
+    assert_eq!(count(pool, "entries").await, 4);
+
+    // Only the problems to fix by hand are left.
+    let left = doctor::check(pool).await.unwrap();
+    assert_eq!(left.len(), 2);
+    assert!(left.iter().all(|issue| issue.fix.is_none()));
+    let report = doctor::repair(&database, Local::now()).await.unwrap();
+    assert!(!report.fixed);
+    assert_eq!(report.snapshot, None);
+}
+
+#[tokio::test]
+async fn healthy_database_has_no_problems() {
+    let database = Database::open(&database_path("doctor-healthy"))
+        .await
+        .unwrap();
+    let pool = database.pool();
+    sqlx::raw_sql(
+        "
+        INSERT INTO entries (id, title) VALUES (1, 'Website'), (2, 'Invoices');
+        INSERT INTO time_entries (entry_id, duration, date) VALUES (1, 90, '2025-01-06'), (2, 15, '2025-01-07');
+        ",
+    )
+    .execute(pool)
+    .await
+    .unwrap();
+    assert!(doctor::check(pool).await.unwrap().is_empty());
+}
@@ -0,0 +1,64 @@
+//! Durations typed on the `timeloop-cli` command line.
+
+use timeloop_lib::cli::duration::{format, parse};
+
+#[test]
+fn durations_are_read_in_minutes() {
+    for (input, minutes) in [
+        ("90", 90),
+        ("45m", 45),
+        ("45 min", 45),
+        ("2h", 120),
+        ("1h30", 90),
+        ("1H30m", 90),
+        ("1.5h", 90),
+        ("1:30", 90),
+        ("1:05", 65),
+        ("0:45", 45),
+        ("1h59", 119),
+        ("1.5h15m", 105),
+    ] {
+        assert_eq!(parse(input), Ok(minutes), "{input}");
+    }
+}
+
+#[test]
+fn nonsense_negative_and_huge_durations_are_refused() {
+    for input in [
+        "",
+        "0",
+        "-5",
+        "1:60",
+        "1:90",
+        "1h90",
+        "1h60m",
+        "1h-30",
+        "1:-5",
+        "-1h",
+        "-1:30",
+        "+5",
+        "1h+5",
+        "-0.5h",
+        "1.h",
+        ".5h",
+        "1..5h",
+        "h",
+        "abc",
+        "1.5",
+        "153722867280912931:0",
+        "1e18h5",
+        "infh",
+        "nanh",
+        "9223372036854775807h",
+        "1h9223372036854775807",
+    ] {
+        assert!(parse(input).is_err(), "{input}");
+    }
+}
+
+#[test]
+fn durations_are_shown_like_the_app() {
+    assert_eq!(format(45), "45min");
+    assert_eq!(format(120), "2h");
+    assert_eq!(format(90), "1h30");
+}
@@ -0,0 +1,482 @@
+//! Time entries read from the files of other tools, quirks included.
+
+use std::fmt::Debug;
+
+use chrono::{DateTime, FixedOffset, Utc};
+use timeloop_lib::db::imports::ImportRow;
+use timeloop_lib::db::TimeRange;
+use timeloop_lib::exchange::csv::{self, CsvColumns};
+use timeloop_lib::exchange::ical::{self, CalendarEvent};
+use timeloop_lib::exchange::{clockify, harvest, timewarrior, toggl};
+use timeloop_lib::Error;
+
+fn range(started_at: &str, ended_at: &str) -> Option<TimeRange> {
+    let parse = |time| DateTime::<FixedOffset>::parse_from_rfc3339(time).unwrap();
+    Some(TimeRange {
+        started_at: parse(started_at),
+        ended_at: parse(ended_at),
+    })
+}
+
+fn row(date: &str, duration: i64, title: &str, category: Option<&str>) -> ImportRow {
+    ImportRow {
+        date: date.to_string(),
+        duration,
+        entry_id: None,
+        entry_title: title.to_string(),
+        category_name: category.map(String::from),
+        note: None,
+        range: None,
+    }
+}
+
+/// The bounds of a row's range on the clock it was read with, which is the
+/// user's for tools that write times without an offset.
+fn wall_clock(row: &ImportRow) -> (String, String) {
+    let range = row.range.unwrap();
+    let time = |time: DateTime<FixedOffset>| time.naive_local().format("%F %R").to_string();
+    (time(range.started_at), time(range.ended_at))
+}
+
+/// The 1-based row and the field of an error on one row of a file.
+fn row_error<T: Debug>(result: Result<T, Error>) -> (usize, &'static str) {
+    match result {
+        Err(Error::Row { row, source }) => match *source {
+            Error::Invalid { field, .. } => (row, field),
+            source => panic!("row {row} failed with {source}"),
+        },
+        other => panic!("expected an error on a row, got {other:?}"),
+    }
+}
+
+#[test]
+fn csv_rows_take_their_date_and_duration_from_the_range() {
+    let rows = csv::parse(
+        "date,start,end,duration,entry,category,note
+2026-10-05,,,1:30, Logo ,Design,Sketches
+,2026-10-06T08:00:00+02:00,2026-10-06T08:45:00+02:00,,Meeting,,
+",
+        &CsvColumns::default(),
+    )
+    .unwrap();
+    assert_eq!(
+        rows,
+        [
+            ImportRow {
+                note: Some("Sketches".to_string()),
+                ..row("2026-10-05", 90, "Logo", Some("Design"))
+            },
+            ImportRow {
+                range: range("2026-10-06T08:00:00+02:00", "2026-10-06T08:45:00+02:00"),
+                ..row("2026-10-06", 45, "Meeting", None)
+            },
+        ]
+    );
+}
+
+#[test]
+fn csv_columns_can_be_renamed() {
+    let columns = CsvColumns {
+        date: "Day".to_string(),
+        duration: "Minutes".to_string(),
+        entry: "Task".to_string(),
+        ..CsvColumns::default()
+    };
+    let rows = csv::parse("Day,Task,Minutes\n2026-10-05,Logo,25\n", &columns).unwrap();
+    assert_eq!(rows, [row("2026-10-05", 25, "Logo", None)]);
+    assert!(matches!(
+        csv::parse("Day,Minutes\n2026-10-05,25\n", &columns),
+        Err(Error::Invalid {
+            field: "columns",
+            ..
+        })
+    ));
+}
+
+#[test]
+fn csv_errors_name_the_row_and_field() {
+    let contents = "date,duration,entry
+2026-10-05,30,Logo
+2026-10-06,half an hour,Logo
+";
+    assert_eq!(
+        row_error(csv::parse(contents, &CsvColumns::default())),
+        (2, "duration")
+    );
+    let contents = "date,start,end,entry
+2026-10-05,2026-10-05T08:00:00+02:00,,Logo
+";
+    assert_eq!(
+        row_error(csv::parse(contents, &CsvColumns::default())),
+        (1, "started_at")
+    );
+    // Too many hours to count in minutes.
+    let contents = "date,duration,entry
+2026-10-05,999999999999999999:00,Logo
+";
+    assert_eq!(
+        row_error(csv::parse(contents, &CsvColumns::default())),
+        (1, "duration")
+    );
+}
+
+#[test]
+fn toggl_csv_titles_fall_back_to_the_task_then_the_project() {
+    let rows = toggl::parse(
+        "User,Email,Client,Project,Task,Description,Billable,Start date,Start time,End date,End time,Duration,Tags
+Ana,ana@example.com,,Website,,Homepage,No,2026-10-05,09:00:00,2026-10-05,10:30:00,01:30:00,\"design, web\"
+Ana,ana@example.com,,Website,Backend,,No,2026-10-05,11:00:00,2026-10-05,11:00:20,00:00:20,
+Ana,ana@example.com,,Website,Backend,,No,2026-10-05,23:30:00,2026-10-06,00:15:00,00:45:00,
+Ana,ana@example.com,,,,,No,2026-10-06,13:00:00,2026-10-06,13:45:00,00:45:00,
+",
+    )
+    .unwrap();
+    // The 20 seconds on the second row round down to nothing.
+    let summary: Vec<_> = rows
+        .iter()
+        .map(|row| {
+            (
+                row.date.as_str(),
+                row.duration,
+                row.entry_title.as_str(),
+                row.category_name.as_deref(),
+                row.note.as_deref(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            (
+                "2026-10-05",
+                90,
+                "Homepage",
+                Some("Website"),
+                Some("Tags: design, web")
+            ),
+            ("2026-10-05", 45, "Backend", Some("Website"), None),
+            ("2026-10-06", 45, "(no description)", None, None),
+        ]
+    );
+    assert_eq!(
+        wall_clock(&rows[1]),
+        (
+            "2026-10-05 23:30".to_string(),
+            "2026-10-06 00:15".to_string()
+        )
+    );
+}
+
+#[test]
+fn toggl_csv_errors_name_the_row_and_field() {
+    let contents = "Description,Start date,Start time,End date,End time
+Homepage,2026-10-05,09:00:00,2026-10-05,10:00:00
+Homepage,2026-10-05,nine,2026-10-05,10:00:00
+";
+    assert_eq!(row_error(toggl::parse(contents)), (2, "start"));
+    assert!(matches!(
+        toggl::parse("Description,Start date,Start time,End date\n"),
+        Err(Error::Invalid {
+            field: "columns",
+            ..
+        })
+    ));
+}
+
+#[test]
+fn toggl_json_skips_running_entries() {
+    let rows = toggl::parse(
+        r#"[
+            {
+                "description": "Homepage",
+                "project": "Website",
+                "start": "2026-10-05T07:00:00Z",
+                "stop": "2026-10-05T08:00:00Z",
+                "tags": ["web"]
+            },
+            {"description": "Still going", "start": "2026-10-05T09:00:00Z", "stop": null}
+        ]"#,
+    )
+    .unwrap();
+    assert_eq!(rows.len(), 1);
+    let range = rows[0].range.unwrap();
+    assert_eq!(
+        range.started_at.to_utc().to_rfc3339(),
+        "2026-10-05T07:00:00+00:00"
+    );
+    assert_eq!(rows[0].duration, 60);
+    assert_eq!(rows[0].entry_title, "Homepage");
+    assert_eq!(rows[0].category_name.as_deref(), Some("Website"));
+    assert_eq!(rows[0].note.as_deref(), Some("Tags: web"));
+
+    let report = r#"{"data": [{"description": "", "project_name": "Website",
+        "start": "2026-10-05T07:00:00+02:00", "end": "2026-10-05T07:30:00+02:00"}]}"#;
+    let rows = toggl::parse(report).unwrap();
+    assert_eq!(rows[0].entry_title, "Website");
+    assert_eq!(rows[0].duration, 30);
+}
+
+#[test]
+fn clockify_titles_fall_back_to_the_task_and_categories_to_the_client() {
+    let rows = clockify::parse(
+        "Project,Client,Description,Task,User,Email,Tags,Billable,Start Date,Start Time,End Date,End Time,Duration (h)
+Website,Acme,Homepage,,Ana,ana@example.com,web,Yes,10/05/2026,09:00 AM,10/05/2026,10:15 AM,1.25
+,Acme,,Support,Ana,ana@example.com,,No,10/06/2026,01:00 PM,10/06/2026,01:00 PM,0.00
+,Acme,,Support,Ana,ana@example.com,,No,10/06/2026,02:00 PM,10/06/2026,02:30 PM,0.50
+",
+    )
+    .unwrap();
+    let summary: Vec<_> = rows
+        .iter()
+        .map(|row| {
+            (
+                row.date.as_str(),
+                row.duration,
+                row.entry_title.as_str(),
+                row.category_name.as_deref(),
+                row.note.as_deref(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            (
+                "2026-10-05",
+                75,
+                "Homepage",
+                Some("Website"),
+                Some("Tags: web")
+            ),
+            ("2026-10-06", 30, "Support", Some("Acme"), None),
+        ]
+    );
+    assert_eq!(
+        wall_clock(&rows[1]),
+        (
+            "2026-10-06 14:00".to_string(),
+            "2026-10-06 14:30".to_string()
+        )
+    );
+
+    let contents = "Description,Start Date,Start Time,End Date,End Time
+Homepage,10/05/2026,09:00,10/05/2026,10:00
+Homepage,10/05/2026,09:00,13/45/2026,10:00
+";
+    assert_eq!(row_error(clockify::parse(contents)), (2, "end"));
+}
+
+#[test]
+fn harvest_hours_are_decimal_or_written_as_a_time() {
+    let rows = harvest::parse(
+        "Date,Client,Project,Project Code,Task,Notes,Hours,Hours Rounded,First Name,Last Name
+2026-10-05,Acme,Website,WEB,Design,Mockups,1.5,1.5,Ana,Lee
+2026-10-05,Acme,,,,Call,\"0,25\",0.25,Ana,Lee
+2026-10-06,Acme,Website,WEB,Design,,2:45,2.75,Ana,Lee
+2026-10-07,Acme,Website,WEB,Design,,0,0,Ana,Lee
+",
+    )
+    .unwrap();
+    assert_eq!(
+        rows,
+        [
+            ImportRow {
+                note: Some("Mockups".to_string()),
+                ..row("2026-10-05", 90, "Design", Some("Website"))
+            },
+            ImportRow {
+                note: Some("Call".to_string()),
+                ..row("2026-10-05", 15, "Acme", Some("Acme"))
+            },
+            row("2026-10-06", 165, "Design", Some("Website")),
+        ]
+    );
+
+    let contents = "Date,Task,Hours
+2026-10-05,Design,1.5
+2026-10-06,Design,lots
+";
+    assert_eq!(row_error(harvest::parse(contents)), (2, "hours"));
+    let contents = "Date,Task,Hours\n2026-10-05,Design,1e300\n";
+    assert_eq!(row_error(harvest::parse(contents)), (1, "hours"));
+    let contents = "Spent Date,Task,Hours\n2026-10-32,Design,1\n";
+    assert_eq!(row_error(harvest::parse(contents)), (1, "date"));
+}
+
+#[test]
+fn timewarrior_tags_make_the_title_and_annotations_the_note() {
+    let rows = timewarrior::parse(
+        r#"inc 20261017T070000Z - 20261017T083000Z # "Client call" category:Acme # "Pricing, \"v2\""
+
+inc 20261017T090000Z - 20261017T091500Z # review code
+inc 20261017T100000Z - 20261017T100000Z # empty
+inc 20261017T110000Z
+"#,
+    )
+    .unwrap();
+    let summary: Vec<_> = rows
+        .iter()
+        .map(|row| {
+            (
+                row.duration,
+                row.entry_title.as_str(),
+                row.category_name.as_deref(),
+                row.note.as_deref(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            (90, "Client call", Some("Acme"), Some("Pricing, \"v2\"")),
+            (15, "review code", None, None),
+        ]
+    );
+    assert_eq!(
+        rows[0].range.unwrap().started_at,
+        range("2026-10-17T07:00:00Z", "2026-10-17T08:30:00Z")
+            .unwrap()
+            .started_at
+    );
+
+    let contents = "inc 20261017T070000Z - 20261017T083000Z
+inc 20261017T070000Z - 2026-10-17 08:30
+";
+    assert_eq!(row_error(timewarrior::parse(contents)), (2, "interval"));
+}
+
+#[test]
+fn timewarrior_merge_keeps_time_tracked_once() {
+    let existing = "inc 20261017T070000Z - 20261017T083000Z # call
+inc 20261017T120000Z - 20261017T130000Z # \"has # inside\" lunch
+";
+    let added = "inc 20261017T070000Z - 20261017T083000Z # \"Client call\"
+inc 20261017T083000Z - 20261017T090000Z # email
+inc 20261017T123000Z - 20261017T124500Z # lunch
+inc 20261017T060000Z - 20261017T063000Z # gym
+";
+    let merged = timewarrior::merge(existing, added);
+    assert_eq!(
+        merged.contents,
+        "inc 20261017T060000Z - 20261017T063000Z # gym
+inc 20261017T070000Z - 20261017T083000Z # call
+inc 20261017T083000Z - 20261017T090000Z # email
+inc 20261017T120000Z - 20261017T130000Z # \"has # inside\" lunch
+"
+    );
+    assert_eq!(merged.overlapping, 1);
+
+    // An open interval is still being tracked.
+    let merged = timewarrior::merge("inc 20261017T140000Z # writing\n", added);
+    assert_eq!(merged.overlapping, 0);
+    let later = "inc 20261017T150000Z - 20261017T151500Z # email\n";
+    let merged = timewarrior::merge("inc 20261017T140000Z # writing\n", later);
+    assert_eq!(
+        (merged.contents.as_str(), merged.overlapping),
+        ("inc 20261017T140000Z # writing\n", 1)
+    );
+}
+
+/// Summaries and UTC starts of events, which do not depend on the user's
+/// time zone.
+fn starts(events: &[CalendarEvent]) -> Vec<(&str, String)> {
+    events
+        .iter()
+        .map(|event| {
+            let start = event.started_at.with_timezone(&Utc);
+            (event.summary.as_str(), start.format("%F %R").to_string())
+        })
+        .collect()
+}
+
+#[test]
+fn ical_lines_are_unfolded_and_times_read_in_their_zone() {
+    let events = ical::events(
+        "BEGIN:VCALENDAR\r
+BEGIN:VEVENT\r
+SUMMARY:Design review with a title long enough to be folded over two\r
+  lines\\, twice\r
+DESCRIPTION:Agenda:\\nmockups\r
+DTSTART;TZID=America/New_York:20261014T090000\r
+DTEND;TZID=America/New_York:20261014T103000\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+SUMMARY:Call\r
+DTSTART:20261015T120000Z\r
+DURATION:PT45M\r
+BEGIN:VALARM\r
+SUMMARY:Reminder\r
+TRIGGER:-PT5M\r
+END:VALARM\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+SUMMARY:Holiday\r
+DTSTART;VALUE=DATE:20261016\r
+DTEND;VALUE=DATE:20261017\r
+END:VEVENT\r
+END:VCALENDAR\r
+",
+        "2026-10-01",
+        "2026-10-31",
+    )
+    .unwrap();
+    assert_eq!(
+        starts(&events),
+        [
+            (
+                "Design review with a title long enough to be folded over two lines, twice",
+                "2026-10-14 13:00".to_string()
+            ),
+            ("Call", "2026-10-15 12:00".to_string()),
+        ]
+    );
+    assert_eq!(events[0].description.as_deref(), Some("Agenda:\nmockups"));
+    assert_eq!((events[0].duration, events[1].duration), (90, 45));
+
+    let contents = "BEGIN:VEVENT\nSUMMARY:Call\nDTSTART:20261015T1200\nEND:VEVENT\n";
+    assert_eq!(
+        row_error(ical::events(contents, "2026-10-01", "2026-10-31")),
+        (3, "date")
+    );
+}
+
+#[test]
+fn ical_weekly_rules_start_with_the_event_and_skip_excluded_dates() {
+    let events = ical::events(
+        "BEGIN:VCALENDAR
+BEGIN:VEVENT
+UID:sync
+SUMMARY:Sync
+DTSTART;TZID=Europe/Paris:20261014T100000
+DTEND;TZID=Europe/Paris:20261014T103000
+RRULE:FREQ=WEEKLY;BYDAY=MO,FR;COUNT=5
+EXDATE;TZID=Europe/Paris:20261019T100000
+END:VEVENT
+BEGIN:VEVENT
+UID:retro
+SUMMARY:Retro
+DTSTART:20261013T150000Z
+DTEND:20261013T160000Z
+RRULE:FREQ=WEEKLY;INTERVAL=2;UNTIL=20261110T150000Z
+END:VEVENT
+END:VCALENDAR
+",
+        "2026-10-01",
+        "2026-12-31",
+    )
+    .unwrap();
+    assert_eq!(
+        starts(&events),
+        [
+            ("Retro", "2026-10-13 15:00".to_string()),
+            // On a Wednesday, and counted as the first of five.
+            ("Sync", "2026-10-14 08:00".to_string()),
+            ("Sync", "2026-10-16 08:00".to_string()),
+            ("Sync", "2026-10-23 08:00".to_string()),
+            // Still 10:00 in Paris once clocks are turned back.
+            ("Sync", "2026-10-26 09:00".to_string()),
+            ("Retro", "2026-10-27 15:00".to_string()),
+            ("Retro", "2026-11-10 15:00".to_string()),
+        ]
+    );
+}
@@ -0,0 +1,28 @@
+-- Schema version 1: categories, entries and time entries with a duration
+-- and a day, as logged from the first releases.
+
+INSERT INTO categories (id, name, color, created_at) VALUES
+    (1, 'Clients', '#ef4444', '2022-01-10 08:12:45'),
+    (2, 'Admin', '#22c55e', '2022-01-10 08:13:02'),
+    (3, 'Learning', '#6366f1', '2022-06-01 19:40:00');
+
+INSERT INTO entries (id, title, category_id, created_at) VALUES
+    (1, 'Website redesign', 1, '2022-01-10 08:14:10'),
+    (2, 'Invoices', 2, '2022-01-31 17:02:33'),
+    (3, 'Rust book', 3, '2022-06-01 19:41:12'),
+    (4, 'Café "Le Zinc" menu', 1, '2023-02-14 10:00:00'),
+    (5, 'Inbox', NULL, '2023-05-02 09:05:51'),
+    (6, 'Never logged', 2, '2024-01-08 11:11:11');
+
+INSERT INTO time_entries (id, entry_id, duration, date, note, created_at) VALUES
+    (1, 1, 120, '2022-01-10', 'Wireframes', '2022-01-10 12:20:00'),
+    (2, 1, 45, '2022-01-11', NULL, '2022-01-11 18:01:00'),
+    (3, 2, 30, '2022-01-31', 'January, February', '2022-01-31 17:35:00'),
+    (4, 3, 60, '2022-06-01', 'Chapters 1
+to 3', '2022-06-01 20:45:00'),
+    (5, 3, 90, '2022-12-31', NULL, '2022-12-31 23:59:59'),
+    (6, 4, 240, '2023-02-14', 'Photos', '2023-02-14 16:30:00'),
+    (7, 5, 15, '2023-05-02', '', '2023-05-02 09:21:00'),
+    (8, 5, 1, '2024-02-29', NULL, '2024-02-29 08:00:00'),
+    (9, 1, 480, '2024-03-31', 'Launch day', '2024-03-31 19:00:00'),
+    (10, 2, 25, '2024-10-27', NULL, '2024-10-27 16:40:00');
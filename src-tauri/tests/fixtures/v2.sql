@@ -0,0 +1,19 @@
+-- Schema version 2: a paused timer next to the logged time.
+
+INSERT INTO categories (id, name, color, created_at) VALUES
+    (1, 'Clients', '#ef4444', '2024-04-02 08:00:00'),
+    (2, 'Admin', '#22c55e', '2024-04-02 08:00:05');
+
+INSERT INTO entries (id, title, category_id, created_at) VALUES
+    (1, 'Support', 1, '2024-04-02 08:01:00'),
+    (2, 'Bookkeeping', 2, '2024-04-02 08:01:30'),
+    (3, 'Planning', NULL, '2024-04-03 09:00:00');
+
+INSERT INTO time_entries (id, entry_id, duration, date, note, created_at) VALUES
+    (1, 1, 50, '2024-04-02', 'Tickets', '2024-04-02 09:00:00'),
+    (2, 2, 35, '2024-04-02', NULL, '2024-04-02 10:00:00'),
+    (3, 1, 75, '2024-04-03', NULL, '2024-04-03 11:30:00'),
+    (4, 3, 20, '2024-04-03', 'Week 14', '2024-04-03 12:00:00');
+
+INSERT INTO active_timers (id, entry_id, note, started_at, resumed_at, accumulated_seconds) VALUES
+    (1, 1, 'Call with ACME', '2024-04-04T07:45:00+00:00', NULL, 1260);
@@ -0,0 +1,18 @@
+-- Schema version 3: time entries with start and end times, on either side
+-- of a daylight saving change, mixed with durations only.
+
+INSERT INTO categories (id, name, color, created_at) VALUES
+    (1, 'Clients', '#ef4444', '2024-09-01 08:00:00');
+
+INSERT INTO entries (id, title, category_id, created_at) VALUES
+    (1, 'Mobile app', 1, '2024-09-01 08:01:00'),
+    (2, 'Standup', NULL, '2024-09-01 08:02:00');
+
+INSERT INTO time_entries (id, entry_id, duration, date, note, created_at, started_at, ended_at, utc_offset) VALUES
+    (1, 1, 150, '2024-10-25', 'Login screen', '2024-10-25 11:30:00', '2024-10-25T07:00:00+00:00', '2024-10-25T09:30:00+00:00', 120),
+    (2, 2, 15, '2024-10-28', NULL, '2024-10-28 08:15:00', '2024-10-28T08:00:00+00:00', '2024-10-28T08:15:00+00:00', 60),
+    (3, 1, 45, '2024-10-28', NULL, '2024-10-28 17:00:00', NULL, NULL, NULL),
+    (4, 1, 90, '2024-10-29', 'Release', '2024-10-29 15:00:00', '2024-10-29T13:30:00+00:00', '2024-10-29T15:00:00+00:00', 60);
+
+INSERT INTO active_timers (id, entry_id, note, started_at, resumed_at, accumulated_seconds) VALUES
+    (1, 2, NULL, '2024-10-30T07:58:00+00:00', '2024-10-30T08:10:00+00:00', 300);
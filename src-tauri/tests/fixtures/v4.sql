@@ -0,0 +1,16 @@
+-- Schema version 4: settings, with customised and disabled shortcuts.
+
+INSERT INTO categories (id, name, color, created_at) VALUES
+    (1, 'Research', '#6366f1', '2025-01-06 08:00:00');
+
+INSERT INTO entries (id, title, category_id, created_at) VALUES
+    (1, 'Literature review', 1, '2025-01-06 08:01:00'),
+    (2, 'Email', NULL, '2025-01-06 08:02:00');
+
+INSERT INTO time_entries (id, entry_id, duration, date, note, created_at, started_at, ended_at, utc_offset) VALUES
+    (1, 1, 180, '2025-01-06', NULL, '2025-01-06 12:00:00', '2025-01-06T08:00:00+00:00', '2025-01-06T11:00:00+00:00', 60),
+    (2, 2, 20, '2025-01-06', NULL, '2025-01-06 13:00:00', NULL, NULL, NULL);
+
+INSERT INTO settings (key, value) VALUES
+    ('shortcut.toggle_timer', 'Ctrl+Alt+T'),
+    ('shortcut.quick_log', '');
@@ -0,0 +1,14 @@
+-- Schema version 5: a running timer with an idle span waiting for an answer.
+
+INSERT INTO entries (id, title, category_id, created_at) VALUES
+    (1, 'Thesis', NULL, '2025-03-03 08:00:00'),
+    (2, 'Break', NULL, '2025-03-03 08:00:30');
+
+INSERT INTO time_entries (id, entry_id, duration, date, note, created_at, started_at, ended_at, utc_offset) VALUES
+    (1, 1, 95, '2025-03-03', 'Chapter 2', '2025-03-03 10:00:00', '2025-03-03T08:25:00+00:00', '2025-03-03T10:00:00+00:00', 60);
+
+INSERT INTO active_timers (id, entry_id, note, started_at, resumed_at, accumulated_seconds, idle_since, idle_until) VALUES
+    (1, 1, NULL, '2025-03-03T13:00:00+00:00', '2025-03-03T13:00:00+00:00', 0, '2025-03-03T13:40:00+00:00', '2025-03-03T14:05:00+00:00');
+
+INSERT INTO settings (key, value) VALUES
+    ('idle.threshold_minutes', '15');
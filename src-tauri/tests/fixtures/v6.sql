@@ -0,0 +1,22 @@
+-- Schema version 6: the same tables with their constraints, after a
+-- category was deleted and its entry left uncategorized.
+
+INSERT INTO categories (id, name, color, created_at) VALUES
+    (1, 'Teaching', '#f59e0b', '2025-09-01 07:30:00'),
+    (3, 'Grading', '#0ea5e9', '2025-09-15 18:00:00');
+
+INSERT INTO entries (id, title, category_id, created_at) VALUES
+    (1, 'Lecture prep', 1, '2025-09-01 07:31:00'),
+    (2, 'Office hours', NULL, '2025-09-02 13:00:00'),
+    (4, 'Midterms', 3, '2025-10-20 09:00:00');
+
+INSERT INTO time_entries (id, entry_id, duration, date, note, created_at, started_at, ended_at, utc_offset) VALUES
+    (1, 1, 110, '2025-09-01', NULL, '2025-09-01 10:00:00', '2025-09-01T07:50:00+00:00', '2025-09-01T09:40:00+00:00', 120),
+    (2, 2, 60, '2025-09-02', 'Room 204', '2025-09-02 15:00:00', NULL, NULL, NULL),
+    (5, 4, 135, '2025-10-21', NULL, '2025-10-21 20:00:00', '2025-10-21T16:00:00+00:00', '2025-10-21T18:15:00+00:00', 120);
+
+INSERT INTO active_timers (id, entry_id, note, started_at, resumed_at, accumulated_seconds) VALUES
+    (1, 4, 'Batch 2', '2025-10-22T12:00:00+00:00', NULL, 2700);
+
+INSERT INTO settings (key, value) VALUES
+    ('idle.threshold_minutes', '0');
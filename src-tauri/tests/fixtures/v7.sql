@@ -0,0 +1,19 @@
+-- Schema version 7: time entries remember the importer that logged them,
+-- next to time logged in the app.
+
+INSERT INTO categories (id, name, color, created_at) VALUES
+    (1, 'Acme', '#10b981', '2026-02-02 08:00:00');
+
+INSERT INTO entries (id, title, category_id, created_at) VALUES
+    (1, 'Support', 1, '2026-02-02 08:00:00'),
+    (2, 'Homepage', 1, '2026-02-02 08:00:00'),
+    (3, 'Planning', NULL, '2026-02-03 09:15:00');
+
+INSERT INTO time_entries (id, entry_id, duration, date, note, created_at, started_at, ended_at, utc_offset, source) VALUES
+    (1, 1, 90, '2026-01-26', 'Tickets', '2026-02-02 08:00:00', NULL, NULL, NULL, 'harvest'),
+    (2, 1, 90, '2026-01-26', 'Tickets', '2026-02-02 08:00:00', NULL, NULL, NULL, 'harvest'),
+    (3, 2, 45, '2026-01-27', 'Tags: web', '2026-02-02 08:00:00', '2026-01-27T08:00:00+00:00', '2026-01-27T08:45:00+00:00', 60, 'toggl'),
+    (4, 3, 30, '2026-02-03', NULL, '2026-02-03 09:45:00', '2026-02-03T09:15:00+00:00', '2026-02-03T09:45:00+00:00', 60, NULL);
+
+INSERT INTO settings (key, value) VALUES
+    ('shortcut.toggle_timer', 'CommandOrControl+Shift+T');
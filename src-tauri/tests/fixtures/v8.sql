@@ -0,0 +1,17 @@
+-- Schema version 8: ranges estimated for time logged before ranges were
+-- stored are flagged, next to ranges logged in the app.
+
+INSERT INTO categories (id, name, color, created_at) VALUES
+    (1, 'Acme', '#10b981', '2026-03-02 08:00:00');
+
+INSERT INTO entries (id, title, category_id, created_at) VALUES
+    (1, 'Support', 1, '2026-03-02 08:00:00'),
+    (2, 'Planning', NULL, '2026-03-02 08:00:00');
+
+INSERT INTO time_entries (id, entry_id, duration, date, note, created_at, started_at, ended_at, utc_offset, source, range_estimated) VALUES
+    (1, 1, 60, '2026-03-02', 'Tickets', '2026-03-02 10:00:00', '2026-03-02T09:00:00+00:00', '2026-03-02T10:00:00+00:00', 60, NULL, 1),
+    (2, 2, 30, '2026-03-02', NULL, '2026-03-02 09:50:00', '2026-03-02T09:30:00+00:00', '2026-03-02T10:00:00+00:00', 60, NULL, 0),
+    (3, 1, 45, '2026-03-03', NULL, '2026-03-03 08:00:00', NULL, NULL, NULL, 'harvest', 0);
+
+INSERT INTO settings (key, value) VALUES
+    ('idle.threshold_minutes', '5');
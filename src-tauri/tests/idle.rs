@@ -0,0 +1,211 @@
+//! Idle detection with a fake source and clock, and what each way of
+//! resolving the idle time leaves logged.
+
+mod common;
+
+use std::sync::Mutex;
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use sqlx::SqlitePool;
+use timeloop_lib::db::entries;
+use timeloop_lib::db::timer::{self, IdleResolution};
+use timeloop_lib::idle::{self, Clock, IdleChange, IdleSource, IdleWatcher};
+use timeloop_lib::Error;
+
+use common::memory;
+
+const THRESHOLD: i64 = 5;
+
+/// Seconds without input, as of the current time.
+struct Input(Mutex<(i64, DateTime<Utc>)>);
+
+impl Input {
+    fn new(now: DateTime<Utc>) -> Self {
+        Self(Mutex::new((0, now)))
+    }
+
+    fn set(&self, idle_seconds: i64, now: DateTime<Utc>) {
+        *self.0.lock().unwrap() = (idle_seconds, now);
+    }
+}
+
+impl IdleSource for &Input {
+    async fn idle_seconds(&self) -> Option<i64> {
+        Some(self.0.lock().unwrap().0)
+    }
+}
+
+impl Clock for &Input {
+    fn now(&self) -> DateTime<Utc> {
+        self.0.lock().unwrap().1
+    }
+}
+
+fn start() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2026, 10, 16, 8, 0, 0).unwrap()
+}
+
+fn at(minutes: i64) -> DateTime<Utc> {
+    start() + Duration::minutes(minutes)
+}
+
+/// Entries and minutes of the time entries logged so far.
+async fn logged(pool: &SqlitePool) -> Vec<(i64, i64)> {
+    sqlx::query_as("SELECT entry_id, duration FROM time_entries ORDER BY id")
+        .fetch_all(pool)
+        .await
+        .unwrap()
+}
+
+/// Starts a timer at `start()` whose user goes away after 10 minutes, is
+/// noticed at 16 and comes back at 40.
+async fn away_from_10_to_40(pool: &SqlitePool, entry_id: i64) {
+    let input = Input::new(start());
+    let watcher = IdleWatcher::new(&input, &input);
+    timer::start(pool, entry_id, None, start()).await.unwrap();
+
+    input.set(30, at(10));
+    assert_eq!(watcher.check(pool, THRESHOLD).await.unwrap(), None);
+    input.set(6 * 60, at(16));
+    assert_eq!(
+        watcher.check(pool, THRESHOLD).await.unwrap(),
+        Some(IdleChange::Started { since: at(10) })
+    );
+    input.set(20 * 60, at(30));
+    assert_eq!(watcher.check(pool, THRESHOLD).await.unwrap(), None);
+    input.set(5, at(40) + Duration::seconds(5));
+    assert_eq!(
+        watcher.check(pool, THRESHOLD).await.unwrap(),
+        Some(IdleChange::Returned {
+            since: at(10),
+            until: at(40),
+        })
+    );
+    assert_eq!(watcher.check(pool, THRESHOLD).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn idle_time_is_marked_once_the_user_is_back() {
+    let pool = memory().await;
+    let website = entries::create(&pool, "Website", None).await.unwrap();
+    let input = Input::new(start());
+    let watcher = IdleWatcher::new(&input, &input);
+    timer::start(&pool, website, None, start()).await.unwrap();
+
+    input.set(6 * 60, at(16));
+    watcher.check(&pool, THRESHOLD).await.unwrap();
+    // Nothing to resolve while the user is still away.
+    assert!(matches!(
+        timer::resolve_idle(&pool, IdleResolution::Keep).await,
+        Err(Error::NotFound("idle time"))
+    ));
+    input.set(0, at(25));
+    watcher.check(&pool, THRESHOLD).await.unwrap();
+    let running = timer::get(&pool).await.unwrap().unwrap();
+    assert_eq!(
+        (running.idle_since, running.idle_until),
+        (Some(at(10)), Some(at(25)))
+    );
+
+    // Away for longer than the timer ran, only the timer's time is idle.
+    timer::stop(&pool, at(30)).await.unwrap();
+    timer::start(&pool, website, None, at(40)).await.unwrap();
+    input.set(60 * 60, at(50));
+    assert_eq!(
+        watcher.check(&pool, THRESHOLD).await.unwrap(),
+        Some(IdleChange::Started { since: at(40) })
+    );
+}
+
+#[tokio::test]
+async fn threshold_of_zero_turns_detection_off() {
+    let pool = memory().await;
+    assert_eq!(idle::threshold_minutes(&pool).await.unwrap(), 5);
+    idle::set_threshold_minutes(&pool, 0).await.unwrap();
+    assert_eq!(idle::threshold_minutes(&pool).await.unwrap(), 0);
+    assert!(idle::set_threshold_minutes(&pool, 24 * 60 + 1)
+        .await
+        .is_err());
+
+    let website = entries::create(&pool, "Website", None).await.unwrap();
+    let input = Input::new(start());
+    let watcher = IdleWatcher::new(&input, &input);
+    timer::start(&pool, website, None, start()).await.unwrap();
+    input.set(3 * 60 * 60, at(200));
+    assert_eq!(watcher.check(&pool, 0).await.unwrap(), None);
+
+    timer::stop(&pool, at(200)).await.unwrap();
+    assert_eq!(logged(&pool).await, [(website, 200)]);
+}
+
+#[tokio::test]
+async fn paused_timer_is_not_idle() {
+    let pool = memory().await;
+    let website = entries::create(&pool, "Website", None).await.unwrap();
+    let input = Input::new(start());
+    let watcher = IdleWatcher::new(&input, &input);
+    input.set(60 * 60, at(60));
+    assert_eq!(watcher.check(&pool, THRESHOLD).await.unwrap(), None);
+
+    timer::start(&pool, website, None, start()).await.unwrap();
+    timer::pause(&pool, at(10)).await.unwrap();
+    assert_eq!(watcher.check(&pool, THRESHOLD).await.unwrap(), None);
+    assert_eq!(timer::get(&pool).await.unwrap().unwrap().idle_since, None);
+
+    timer::resume(&pool, at(60)).await.unwrap();
+    timer::stop(&pool, at(80)).await.unwrap();
+    assert_eq!(logged(&pool).await, [(website, 30)]);
+}
+
+#[tokio::test]
+async fn kept_idle_time_stays_on_the_timer() {
+    let pool = memory().await;
+    let website = entries::create(&pool, "Website", None).await.unwrap();
+    away_from_10_to_40(&pool, website).await;
+
+    assert_eq!(
+        timer::resolve_idle(&pool, IdleResolution::Keep)
+            .await
+            .unwrap(),
+        None
+    );
+    let running = timer::get(&pool).await.unwrap().unwrap();
+    assert_eq!((running.idle_since, running.idle_until), (None, None));
+    timer::stop(&pool, at(50)).await.unwrap();
+    assert_eq!(logged(&pool).await, [(website, 50)]);
+}
+
+#[tokio::test]
+async fn discarded_idle_time_is_taken_off_the_timer() {
+    let pool = memory().await;
+    let website = entries::create(&pool, "Website", None).await.unwrap();
+    away_from_10_to_40(&pool, website).await;
+
+    assert_eq!(
+        timer::resolve_idle(&pool, IdleResolution::Discard)
+            .await
+            .unwrap(),
+        None
+    );
+    timer::stop(&pool, at(50)).await.unwrap();
+    assert_eq!(logged(&pool).await, [(website, 20)]);
+}
+
+#[tokio::test]
+async fn reassigned_idle_time_is_logged_on_the_other_entry() {
+    let pool = memory().await;
+    let website = entries::create(&pool, "Website", None).await.unwrap();
+    let lunch = entries::create(&pool, "Lunch", None).await.unwrap();
+    away_from_10_to_40(&pool, website).await;
+
+    assert!(matches!(
+        timer::resolve_idle(&pool, IdleResolution::Reassign { entry_id: 999 }).await,
+        Err(Error::NotFound("entry"))
+    ));
+    let reassigned = timer::resolve_idle(&pool, IdleResolution::Reassign { entry_id: lunch })
+        .await
+        .unwrap();
+    assert!(reassigned.is_some());
+    timer::stop(&pool, at(50)).await.unwrap();
+    assert_eq!(logged(&pool).await, [(lunch, 30), (website, 20)]);
+}
@@ -0,0 +1,130 @@
+//! Logging imported rows: entries and categories matched by name, and files
+//! imported twice logged once.
+
+mod common;
+
+use timeloop_lib::db::imports::{self, ImportRow};
+use timeloop_lib::db::{reports, time_entries};
+use timeloop_lib::exchange::harvest;
+
+use common::memory;
+
+const HARVEST: &str = "Date,Client,Project,Task,Notes,Hours
+2026-10-05,Acme,Website,Design,Mockups,1.5
+2026-10-05,Acme,Website,Design,Mockups,1.5
+2026-10-06,Acme,Website,Support,,0.5
+";
+
+fn total(rows: &[ImportRow]) -> i64 {
+    rows.iter().map(|row| row.duration).sum()
+}
+
+#[tokio::test]
+async fn imports_create_entries_and_categories_once() {
+    let pool = memory().await;
+    let rows = harvest::parse(HARVEST).unwrap();
+    let report = imports::import(&pool, harvest::SOURCE, &rows, true)
+        .await
+        .unwrap();
+    assert!(report.dry_run);
+    assert_eq!(report.time_entries, 3);
+    assert_eq!(report.total_duration, total(&rows));
+    assert_eq!(report.created_entries, ["Design", "Support"]);
+    assert_eq!(report.created_categories, ["Website"]);
+    assert!(time_entries::list(&pool, 50, 0).await.unwrap().is_empty());
+
+    let report = imports::import(&pool, harvest::SOURCE, &rows, false)
+        .await
+        .unwrap();
+    assert_eq!(report.time_entries, 3);
+    let logged = time_entries::list(&pool, 50, 0).await.unwrap();
+    assert_eq!(logged.len(), 3);
+    assert_eq!(logged[0].category_name.as_deref(), Some("Website"));
+}
+
+#[tokio::test]
+async fn rows_without_a_range_are_not_imported_twice() {
+    let pool = memory().await;
+    let rows = harvest::parse(HARVEST).unwrap();
+    imports::import(&pool, harvest::SOURCE, &rows, false)
+        .await
+        .unwrap();
+
+    let report = imports::import(&pool, harvest::SOURCE, &rows, false)
+        .await
+        .unwrap();
+    assert_eq!(report.time_entries, 0);
+    assert_eq!(report.skipped_duplicates, 3);
+    let minutes = reports::total_for_period(&pool, "2026-10-01", "2026-10-31")
+        .await
+        .unwrap();
+    assert_eq!(minutes, total(&rows));
+
+    // A later export with one more of the same row logs that one only.
+    let mut more = rows.clone();
+    more.push(rows[0].clone());
+    let report = imports::import(&pool, harvest::SOURCE, &more, false)
+        .await
+        .unwrap();
+    assert_eq!((report.time_entries, report.skipped_duplicates), (1, 3));
+}
+
+#[tokio::test]
+async fn time_logged_otherwise_is_not_taken_for_a_duplicate() {
+    let pool = memory().await;
+    let rows = harvest::parse(HARVEST).unwrap();
+    imports::import(&pool, "csv", &rows, false).await.unwrap();
+
+    let report = imports::import(&pool, harvest::SOURCE, &rows, false)
+        .await
+        .unwrap();
+    assert_eq!(report.time_entries, 3);
+    assert_eq!(report.skipped_duplicates, 0);
+}
+
+#[tokio::test]
+async fn entries_of_the_same_title_are_told_apart_by_their_category() {
+    let pool = memory().await;
+    let rows = harvest::parse(
+        "Date,Client,Project,Task,Notes,Hours
+2026-10-05,Acme,Website,Design,,1
+2026-10-05,Initech,Branding,design,,2
+2026-10-06,Acme,Website,Design,,0.5
+",
+    )
+    .unwrap();
+    let report = imports::import(&pool, harvest::SOURCE, &rows, false)
+        .await
+        .unwrap();
+    assert_eq!(report.created_entries, ["Design", "design"]);
+    assert_eq!(report.created_categories, ["Website", "Branding"]);
+
+    let mut logged: Vec<_> = time_entries::list(&pool, 50, 0)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|te| (te.category_name, te.time_entry.duration))
+        .collect();
+    logged.sort();
+    assert_eq!(
+        logged,
+        [
+            (Some("Branding".to_string()), 120),
+            (Some("Website".to_string()), 30),
+            (Some("Website".to_string()), 60),
+        ]
+    );
+
+    // Rows without a category have an entry of their own too.
+    let rows = [ImportRow {
+        date: "2026-10-07".to_string(),
+        duration: 15,
+        entry_id: None,
+        entry_title: "Design".to_string(),
+        category_name: None,
+        note: None,
+        range: None,
+    }];
+    let report = imports::import(&pool, "csv", &rows, false).await.unwrap();
+    assert_eq!(report.created_entries, ["Design"]);
+}
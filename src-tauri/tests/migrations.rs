@@ -0,0 +1,161 @@
+//! Down migrations: a populated database taken back to each older schema
+//! version, then migrated up again.
+
+mod common;
+
+use chrono::Local;
+use sqlx::SqlitePool;
+use timeloop_lib::db::{self, migrations, snapshots, Database};
+
+use common::{connect, count, database_path, has_column, has_table};
+
+async fn populate(pool: &SqlitePool) {
+    sqlx::raw_sql(
+        "
+        INSERT INTO categories (id, name, color) VALUES (1, 'Clients', '#ef4444'), (2, 'Admin', '#22c55e');
+        INSERT INTO entries (id, title, category_id) VALUES (1, 'Website', 1), (2, 'Invoices', 2), (3, 'Reading', NULL);
+        INSERT INTO time_entries (entry_id, duration, date, note, started_at, ended_at, utc_offset) VALUES
+            (1, 90, '2024-03-04', 'Layout', '2024-03-04T09:00:00+01:00', '2024-03-04T10:30:00+01:00', 60),
+            (1, 45, '2024-03-05', NULL, NULL, NULL, NULL),
+            (2, 30, '2024-03-05', 'March', '2024-03-05T14:00:00+01:00', '2024-03-05T14:30:00+01:00', 60),
+            (3, 20, '2024-03-06', NULL, NULL, NULL, NULL),
+            (3, 15, '2024-03-07', NULL, NULL, NULL, NULL);
+        INSERT INTO active_timers (id, entry_id, started_at, resumed_at, accumulated_seconds, idle_since)
+            VALUES (1, 2, '2024-03-08T08:00:00Z', '2024-03-08T08:30:00Z', 600, '2024-03-08T08:40:00Z');
+        INSERT INTO settings (key, value) VALUES ('idle.threshold_minutes', '10');
+        ",
+    )
+    .execute(pool)
+    .await
+    .unwrap();
+}
+
+/// What each schema version has, checked against `version`.
+async fn assert_schema(pool: &SqlitePool, version: i64) {
+    assert_eq!(
+        migrations::applied_versions(pool).await.unwrap(),
+        (1..=version).collect::<Vec<_>>()
+    );
+    assert_eq!(has_table(pool, "active_timers").await, version >= 2);
+    assert_eq!(
+        has_column(pool, "time_entries", "started_at").await,
+        version >= 3
+    );
+    assert_eq!(has_table(pool, "settings").await, version >= 4);
+    if version >= 2 {
+        assert_eq!(
+            has_column(pool, "active_timers", "idle_since").await,
+            version >= 5
+        );
+    }
+    assert_eq!(
+        has_column(pool, "time_entries", "source").await,
+        version >= 7
+    );
+    assert_eq!(
+        has_column(pool, "time_entries", "range_estimated").await,
+        version >= 8
+    );
+}
+
+#[tokio::test]
+async fn every_version_goes_down_and_up_again() {
+    let latest = migrations::latest_version();
+    for version in 1..latest {
+        let path = database_path("downgrade");
+        let database = Database::open(&path).await.unwrap();
+        populate(database.pool()).await;
+        database.pool().close().await;
+
+        let downgrade = db::downgrade(&path, version, Local::now()).await.unwrap();
+        assert_eq!((downgrade.from, downgrade.to), (latest, version));
+
+        let pool = connect(&path).await;
+        assert_schema(&pool, version).await;
+        // Categories, entries and durations are in every version.
+        assert_eq!(count(&pool, "categories").await, 2);
+        assert_eq!(count(&pool, "entries").await, 3);
+        assert_eq!(count(&pool, "time_entries").await, 5);
+        let total: i64 = sqlx::query_scalar("SELECT SUM(duration) FROM time_entries")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(total, 200);
+
+        migrations::run(&pool).await.unwrap();
+        assert_schema(&pool, latest).await;
+        assert_eq!(count(&pool, "time_entries").await, 5);
+        assert_eq!(count(&pool, "active_timers").await, i64::from(version >= 2));
+        assert_eq!(count(&pool, "settings").await, i64::from(version >= 4));
+        pool.close().await;
+    }
+}
+
+#[tokio::test]
+async fn estimated_ranges_are_dropped_going_down() {
+    let path = database_path("downgrade-estimated");
+    let database = Database::open(&path).await.unwrap();
+    populate(database.pool()).await;
+    sqlx::query("UPDATE time_entries SET range_estimated = 1 WHERE id = 1")
+        .execute(database.pool())
+        .await
+        .unwrap();
+    database.pool().close().await;
+
+    db::downgrade(&path, 7, Local::now()).await.unwrap();
+    let pool = connect(&path).await;
+    let ranged: Vec<i64> =
+        sqlx::query_scalar("SELECT id FROM time_entries WHERE started_at IS NOT NULL")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    assert_eq!(ranged, [3]);
+    assert_eq!(count(&pool, "time_entries").await, 5);
+    pool.close().await;
+}
+
+#[tokio::test]
+async fn downgrade_snapshots_the_database_first() {
+    let path = database_path("downgrade-snapshot");
+    let database = Database::open(&path).await.unwrap();
+    populate(database.pool()).await;
+    database.pool().close().await;
+
+    let downgrade = db::downgrade(&path, 1, Local::now()).await.unwrap();
+    let snapshot = snapshots::directory(&path).join(&downgrade.snapshot);
+    let pool = connect(&snapshot).await;
+    assert_schema(&pool, migrations::latest_version()).await;
+    assert_eq!(count(&pool, "time_entries").await, 5);
+    pool.close().await;
+}
+
+#[tokio::test]
+async fn closed_app_database_cannot_write_during_a_downgrade() {
+    let path = database_path("downgrade-closed");
+    let database = Database::open(&path).await.unwrap();
+    populate(database.pool()).await;
+    database.close().await;
+
+    db::downgrade(&path, 2, Local::now()).await.unwrap();
+    let written = sqlx::query("INSERT INTO entries (title) VALUES ('Late')")
+        .execute(database.pool())
+        .await;
+    assert!(written.is_err());
+    let pool = connect(&path).await;
+    assert_schema(&pool, 2).await;
+    pool.close().await;
+}
+
+#[tokio::test]
+async fn downgrade_only_goes_to_older_versions() {
+    let path = database_path("downgrade-invalid");
+    Database::open(&path).await.unwrap().pool().close().await;
+
+    let latest = migrations::latest_version();
+    for version in [0, latest, latest + 1] {
+        assert!(db::downgrade(&path, version, Local::now()).await.is_err());
+    }
+    let pool = connect(&path).await;
+    assert_schema(&pool, latest).await;
+    pool.close().await;
+}
@@ -0,0 +1,253 @@
+//! Time entries whose ranges intersect: found for a period, found for a
+//! range being saved, and refused when asked to.
+
+mod common;
+
+use chrono::{DateTime, FixedOffset};
+use sqlx::SqlitePool;
+use timeloop_lib::db::overlaps::{self, OverlapPolicy};
+use timeloop_lib::db::{entries, time_entries, TimeRange};
+use timeloop_lib::Error;
+
+use common::memory;
+
+fn range(started_at: &str, ended_at: &str) -> TimeRange {
+    let parse = |time| Some(DateTime::<FixedOffset>::parse_from_rfc3339(time).unwrap());
+    TimeRange::from_bounds(parse(started_at), parse(ended_at))
+        .unwrap()
+        .unwrap()
+}
+
+/// Logs `range` on a new entry, whatever it overlaps.
+async fn log(pool: &SqlitePool, title: &str, range: TimeRange) -> i64 {
+    let entry = entries::create(pool, title, None).await.unwrap();
+    let date = range.started_at.date_naive().to_string();
+    time_entries::create(
+        pool,
+        entry,
+        range.minutes(),
+        &date,
+        None,
+        Some(range),
+        OverlapPolicy::Allow,
+    )
+    .await
+    .unwrap()
+}
+
+fn ids(time_entries: &[timeloop_lib::db::TimeEntryWithDetails]) -> Vec<i64> {
+    time_entries.iter().map(|te| te.time_entry.id).collect()
+}
+
+#[test]
+fn bounds_go_together_and_in_order() {
+    let at = |time| Some(DateTime::<FixedOffset>::parse_from_rfc3339(time).unwrap());
+    assert_eq!(TimeRange::from_bounds(None, None).unwrap(), None);
+    assert!(matches!(
+        TimeRange::from_bounds(at("2026-10-17T08:00:00+02:00"), None),
+        Err(Error::Invalid {
+            field: "started_at",
+            ..
+        })
+    ));
+    for ended_at in ["2026-10-17T08:00:00+02:00", "2026-10-17T07:00:00+02:00"] {
+        assert!(matches!(
+            TimeRange::from_bounds(at("2026-10-17T08:00:00+02:00"), at(ended_at)),
+            Err(Error::Invalid {
+                field: "ended_at",
+                ..
+            })
+        ));
+    }
+}
+
+#[tokio::test]
+async fn touching_ranges_do_not_overlap() {
+    let pool = memory().await;
+    let morning = log(
+        &pool,
+        "Morning",
+        range("2026-10-17T08:00:00+02:00", "2026-10-17T12:00:00+02:00"),
+    )
+    .await;
+    log(
+        &pool,
+        "Lunch",
+        range("2026-10-17T12:00:00+02:00", "2026-10-17T13:00:00+02:00"),
+    )
+    .await;
+    // 11:00 UTC is 13:00 in Paris, when lunch ends.
+    log(
+        &pool,
+        "Call",
+        range("2026-10-17T11:00:00+00:00", "2026-10-17T12:00:00+00:00"),
+    )
+    .await;
+
+    assert!(overlaps::find(&pool, "2026-10-17", "2026-10-17")
+        .await
+        .unwrap()
+        .is_empty());
+    let all = time_entries::list(&pool, 50, 0).await.unwrap();
+    assert!(overlaps::pairs(all).is_empty());
+    let afternoon = range("2026-10-17T14:00:00+02:00", "2026-10-17T15:00:00+02:00");
+    assert!(overlaps::conflicting(&pool, afternoon, None)
+        .await
+        .unwrap()
+        .is_empty());
+
+    let late_morning = range("2026-10-17T11:59:00+02:00", "2026-10-17T12:30:00+02:00");
+    let conflicts = overlaps::conflicting(&pool, late_morning, None)
+        .await
+        .unwrap();
+    assert_eq!(ids(&conflicts).len(), 2);
+    assert!(ids(&conflicts).contains(&morning));
+}
+
+#[tokio::test]
+async fn pairs_cover_only_the_shared_time() {
+    let pool = memory().await;
+    let first = log(
+        &pool,
+        "Design",
+        range("2026-10-17T08:00:00+02:00", "2026-10-17T12:00:00+02:00"),
+    )
+    .await;
+    let second = log(
+        &pool,
+        "Call",
+        range("2026-10-17T09:30:00+01:00", "2026-10-17T11:00:00+01:00"),
+    )
+    .await;
+    // Without a range, time entries never overlap.
+    let entry = entries::create(&pool, "Admin", None).await.unwrap();
+    time_entries::create(
+        &pool,
+        entry,
+        600,
+        "2026-10-17",
+        None,
+        None,
+        OverlapPolicy::Refuse,
+    )
+    .await
+    .unwrap();
+
+    let overlaps = overlaps::pairs(time_entries::list(&pool, 50, 0).await.unwrap());
+    assert_eq!(overlaps.len(), 1);
+    let overlap = &overlaps[0];
+    assert_eq!(
+        (overlap.first.time_entry.id, overlap.second.time_entry.id),
+        (first, second)
+    );
+    // 10:30 to 12:00 in Paris.
+    assert_eq!(overlap.minutes, 90);
+    assert_eq!(overlap.started_at.to_rfc3339(), "2026-10-17T08:30:00+00:00");
+}
+
+#[tokio::test]
+async fn ranges_across_midnight_are_found_on_both_days() {
+    let pool = memory().await;
+    let night = log(
+        &pool,
+        "Deploy",
+        range("2026-10-16T23:00:00+02:00", "2026-10-17T01:00:00+02:00"),
+    )
+    .await;
+    let early = log(
+        &pool,
+        "Support",
+        range("2026-10-17T00:30:00+02:00", "2026-10-17T02:00:00+02:00"),
+    )
+    .await;
+
+    for (from, to) in [("2026-10-16", "2026-10-16"), ("2026-10-17", "2026-10-17")] {
+        let found = overlaps::find(&pool, from, to).await.unwrap();
+        assert_eq!(found.len(), 1, "{from}");
+        assert_eq!(
+            (found[0].first.time_entry.id, found[0].second.time_entry.id),
+            (night, early)
+        );
+        assert_eq!(found[0].minutes, 30);
+    }
+    assert!(overlaps::find(&pool, "2026-10-18", "2026-10-20")
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn ranges_across_a_clock_change_compare_the_time_that_went_by() {
+    let pool = memory().await;
+    // Clocks go back from 03:00 to 02:00: 01:30 to 02:30 after the change
+    // lasts two hours.
+    let night = log(
+        &pool,
+        "Night shift",
+        range("2026-10-25T01:30:00+02:00", "2026-10-25T02:30:00+01:00"),
+    )
+    .await;
+    // 02:15 before the change, which the range covers.
+    let before = range("2026-10-25T02:15:00+02:00", "2026-10-25T02:45:00+02:00");
+    assert_eq!(
+        ids(&overlaps::conflicting(&pool, before, None).await.unwrap()),
+        [night]
+    );
+    // 02:30 after the change, when the range ends.
+    let after = range("2026-10-25T02:30:00+01:00", "2026-10-25T03:00:00+01:00");
+    assert!(overlaps::conflicting(&pool, after, None)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn refusing_overlaps_names_the_time_entries_in_the_way() {
+    let pool = memory().await;
+    let morning = log(
+        &pool,
+        "Design",
+        range("2026-10-17T08:00:00+02:00", "2026-10-17T12:00:00+02:00"),
+    )
+    .await;
+    let late = range("2026-10-17T11:00:00+02:00", "2026-10-17T13:00:00+02:00");
+
+    overlaps::enforce(&pool, OverlapPolicy::Allow, Some(late), None)
+        .await
+        .unwrap();
+    overlaps::enforce(&pool, OverlapPolicy::Refuse, None, None)
+        .await
+        .unwrap();
+    assert!(matches!(
+        overlaps::enforce(&pool, OverlapPolicy::Refuse, Some(late), None).await,
+        Err(Error::Overlap(ids)) if ids == [morning]
+    ));
+    // A time entry being edited does not overlap itself.
+    overlaps::enforce(&pool, OverlapPolicy::Refuse, Some(late), Some(morning))
+        .await
+        .unwrap();
+
+    let entry = entries::create(&pool, "Call", None).await.unwrap();
+    let create =
+        |overlap| time_entries::create(&pool, entry, 120, "2026-10-17", None, Some(late), overlap);
+    assert!(matches!(
+        create(OverlapPolicy::Refuse).await,
+        Err(Error::Overlap(_))
+    ));
+    assert_eq!(time_entries::list(&pool, 50, 0).await.unwrap().len(), 1);
+    let call = create(OverlapPolicy::Allow).await.unwrap();
+
+    // Moved clear of the morning, the call is saved whatever the policy.
+    let afternoon = range("2026-10-17T13:00:00+02:00", "2026-10-17T15:00:00+02:00");
+    time_entries::update(
+        &pool,
+        call,
+        120,
+        "2026-10-17",
+        None,
+        Some(afternoon),
+        OverlapPolicy::Refuse,
+    )
+    .await
+    .unwrap();
+}
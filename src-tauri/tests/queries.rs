@@ -0,0 +1,206 @@
+//! Category, entry and time entry queries, and the totals reports are built
+//! from, on a database in memory.
+
+mod common;
+
+use timeloop_lib::db::overlaps::OverlapPolicy;
+use timeloop_lib::db::{categories, entries, reports, time_entries};
+use timeloop_lib::Error;
+
+use common::memory;
+
+#[tokio::test]
+async fn categories_are_created_renamed_and_deleted() {
+    let pool = memory().await;
+    let design = categories::create(&pool, " Design ", "#ff0000")
+        .await
+        .unwrap();
+    let admin = categories::create(&pool, "Admin", "#00ff00").await.unwrap();
+    assert!(matches!(
+        categories::create(&pool, " ", "#ff0000").await,
+        Err(Error::Invalid { .. })
+    ));
+    assert!(matches!(
+        categories::create(&pool, "Sales", "red").await,
+        Err(Error::Invalid { .. })
+    ));
+
+    let names: Vec<String> = categories::list(&pool)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|category| category.name)
+        .collect();
+    assert_eq!(names, ["Admin", "Design"]);
+
+    categories::update(&pool, admin, "Office", "#0000ff")
+        .await
+        .unwrap();
+    assert!(matches!(
+        categories::update(&pool, 99, "Office", "#0000ff").await,
+        Err(Error::NotFound("category"))
+    ));
+
+    let entry = entries::create(&pool, "Logo", Some(design)).await.unwrap();
+    categories::delete(&pool, design).await.unwrap();
+    let entry = entries::get(&pool, entry).await.unwrap().unwrap();
+    assert_eq!(entry.category_id, None);
+    assert_eq!(categories::list(&pool).await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn entries_are_created_found_and_deleted_with_their_time() {
+    let pool = memory().await;
+    let design = categories::create(&pool, "Design", "#ff0000")
+        .await
+        .unwrap();
+    let logo = entries::create(&pool, "  Logo ", Some(design))
+        .await
+        .unwrap();
+    let meeting = entries::create(&pool, "Meeting", None).await.unwrap();
+    assert!(entries::create(&pool, "", None).await.is_err());
+
+    let entry = entries::get(&pool, logo).await.unwrap().unwrap();
+    assert_eq!(entry.title, "Logo");
+    assert_eq!(entry.category_name.as_deref(), Some("Design"));
+    assert_eq!(entries::search(&pool, "eet").await.unwrap()[0].id, meeting);
+
+    entries::update(&pool, meeting, "Team meeting", Some(design))
+        .await
+        .unwrap();
+    assert_eq!(
+        entries::get(&pool, meeting).await.unwrap().unwrap().title,
+        "Team meeting"
+    );
+    assert!(matches!(
+        entries::update(&pool, 99, "Nothing", None).await,
+        Err(Error::NotFound("entry"))
+    ));
+
+    time_entries::create(
+        &pool,
+        logo,
+        30,
+        "2026-10-05",
+        None,
+        None,
+        OverlapPolicy::Allow,
+    )
+    .await
+    .unwrap();
+    entries::delete(&pool, logo).await.unwrap();
+    assert!(entries::get(&pool, logo).await.unwrap().is_none());
+    assert!(time_entries::list(&pool, 50, 0).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn time_entries_are_validated() {
+    let pool = memory().await;
+    let entry = entries::create(&pool, "Logo", None).await.unwrap();
+    for (duration, date) in [(0, "2026-10-05"), (-5, "2026-10-05"), (30, "2026-13-05")] {
+        assert!(matches!(
+            time_entries::create(
+                &pool,
+                entry,
+                duration,
+                date,
+                None,
+                None,
+                OverlapPolicy::Allow
+            )
+            .await,
+            Err(Error::Invalid { .. })
+        ));
+    }
+
+    let id = time_entries::create(
+        &pool,
+        entry,
+        30,
+        "2026-10-05",
+        Some("Sketches"),
+        None,
+        OverlapPolicy::Allow,
+    )
+    .await
+    .unwrap();
+    time_entries::update(
+        &pool,
+        id,
+        45,
+        "2026-10-06",
+        None,
+        None,
+        OverlapPolicy::Allow,
+    )
+    .await
+    .unwrap();
+    let logged = &time_entries::list_for_entry(&pool, entry).await.unwrap()[0];
+    assert_eq!((logged.duration, logged.date.as_str()), (45, "2026-10-06"));
+    assert_eq!(logged.note, None);
+}
+
+#[tokio::test]
+async fn totals_add_up_the_time_of_each_entry() {
+    let pool = memory().await;
+    let logo = entries::create(&pool, "Logo", None).await.unwrap();
+    let meeting = entries::create(&pool, "Meeting", None).await.unwrap();
+    let idle = entries::create(&pool, "Idle", None).await.unwrap();
+    for (entry, duration, date) in [
+        (logo, 90, "2026-10-05"),
+        (logo, 30, "2026-10-12"),
+        (meeting, 45, "2026-10-06"),
+        (meeting, 15, "2026-11-02"),
+    ] {
+        time_entries::create(
+            &pool,
+            entry,
+            duration,
+            date,
+            None,
+            None,
+            OverlapPolicy::Allow,
+        )
+        .await
+        .unwrap();
+    }
+
+    let totals = reports::entry_totals(&pool).await.unwrap();
+    let totals: Vec<(i64, i64, i64)> = totals
+        .iter()
+        .map(|total| (total.id, total.total_duration, total.entry_count))
+        .collect();
+    assert_eq!(totals, [(meeting, 60, 2), (logo, 120, 2)]);
+    let including_empty = reports::entry_totals_including_empty(&pool).await.unwrap();
+    assert_eq!(including_empty.last().unwrap().id, idle);
+
+    let october = reports::entry_totals_for_period(&pool, "2026-10-01", "2026-10-31")
+        .await
+        .unwrap();
+    let october: Vec<(i64, i64)> = october
+        .iter()
+        .map(|total| (total.id, total.total_duration))
+        .collect();
+    assert_eq!(october, [(logo, 120), (meeting, 45)]);
+    assert_eq!(
+        reports::total_for_period(&pool, "2026-10-01", "2026-10-31")
+            .await
+            .unwrap(),
+        165
+    );
+    assert_eq!(
+        reports::time_for_period(&pool, &[meeting, idle], "2026-10-01", "2026-11-30")
+            .await
+            .unwrap(),
+        60
+    );
+    assert_eq!(
+        reports::time_for_period(&pool, &[], "2026-10-01", "2026-11-30")
+            .await
+            .unwrap(),
+        0
+    );
+    assert!(reports::total_for_period(&pool, "october", "2026-10-31")
+        .await
+        .is_err());
+}
@@ -0,0 +1,211 @@
+//! When time entries were worked: ranges checked against durations, and
+//! ranges estimated for time logged before they were stored, which never
+//! count as overlapping.
+
+mod common;
+
+use chrono::{DateTime, FixedOffset};
+use chrono_tz::Europe::Paris;
+use timeloop_lib::db::overlaps::{self, OverlapPolicy};
+use timeloop_lib::db::{entries, time_entries, TimeRange};
+
+use common::memory;
+
+fn range(started_at: &str, ended_at: &str) -> TimeRange {
+    let parse = |time| DateTime::<FixedOffset>::parse_from_rfc3339(time).unwrap();
+    TimeRange {
+        started_at: parse(started_at),
+        ended_at: parse(ended_at),
+    }
+}
+
+#[tokio::test]
+async fn duration_must_match_the_range() {
+    let pool = memory().await;
+    let entry = entries::create(&pool, "Logo", None).await.unwrap();
+    let morning = range("2026-10-17T08:00:00+02:00", "2026-10-17T12:00:00+02:00");
+    let create = |duration, date| {
+        time_entries::create(
+            &pool,
+            entry,
+            duration,
+            date,
+            None,
+            Some(morning),
+            OverlapPolicy::Allow,
+        )
+    };
+    assert!(create(200, "2026-10-17").await.is_err());
+    assert!(create(240, "2026-10-18").await.is_err());
+    let id = create(240, "2026-10-17").await.unwrap();
+
+    let logged = &time_entries::list_for_entry(&pool, entry).await.unwrap()[0];
+    assert_eq!(logged.id, id);
+    assert_eq!(logged.utc_offset, Some(120));
+    assert_eq!(logged.range(), Some(morning));
+}
+
+#[tokio::test]
+async fn time_across_a_clock_change_is_the_time_that_went_by() {
+    let pool = memory().await;
+    let entry = entries::create(&pool, "Night shift", None).await.unwrap();
+    // Clocks go from 02:00 to 03:00: 01:30 to 03:30 on the wall is an hour.
+    let night = range("2026-03-29T01:30:00+01:00", "2026-03-29T03:30:00+02:00");
+    for duration in [120, 60] {
+        time_entries::create(
+            &pool,
+            entry,
+            duration,
+            "2026-03-29",
+            None,
+            Some(night),
+            OverlapPolicy::Allow,
+        )
+        .await
+        .unwrap();
+    }
+    assert!(time_entries::create(
+        &pool,
+        entry,
+        90,
+        "2026-03-29",
+        None,
+        Some(night),
+        OverlapPolicy::Allow,
+    )
+    .await
+    .is_err());
+
+    let durations: Vec<i64> = time_entries::list_for_entry(&pool, entry)
+        .await
+        .unwrap()
+        .iter()
+        .map(|logged| logged.duration)
+        .collect();
+    assert_eq!(durations, [60, 60]);
+}
+
+#[tokio::test]
+async fn backfilled_ranges_use_the_offset_of_their_day() {
+    let pool = memory().await;
+    let entry = entries::create(&pool, "Logo", None).await.unwrap();
+    sqlx::query(
+        "
+        INSERT INTO time_entries (entry_id, duration, date, created_at) VALUES
+            ($1, 30, '2026-01-15', '2026-01-15 10:00:00'),
+            ($1, 30, '2026-07-15', '2026-07-15 10:00:00'),
+            ($1, 30, '2026-07-14', '2026-07-15 10:00:00')
+        ",
+    )
+    .bind(entry)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    // The last one was not created on its day, so its range is unknown.
+    assert_eq!(
+        time_entries::backfill_ranges(&pool, &Paris).await.unwrap(),
+        2
+    );
+    let ranges: Vec<(String, Option<TimeRange>)> = time_entries::list_for_entry(&pool, entry)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|logged| (logged.date.clone(), logged.range()))
+        .collect();
+    assert_eq!(
+        ranges,
+        [
+            (
+                "2026-07-15".to_string(),
+                Some(range(
+                    "2026-07-15T11:30:00+02:00",
+                    "2026-07-15T12:00:00+02:00"
+                ))
+            ),
+            ("2026-07-14".to_string(), None),
+            (
+                "2026-01-15".to_string(),
+                Some(range(
+                    "2026-01-15T10:30:00+01:00",
+                    "2026-01-15T11:00:00+01:00"
+                ))
+            ),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn backfilled_ranges_never_overlap() {
+    let pool = memory().await;
+    let entry = entries::create(&pool, "Logo", None).await.unwrap();
+    sqlx::query(
+        "
+        INSERT INTO time_entries (entry_id, duration, date, created_at) VALUES
+            ($1, 60, '2026-07-15', '2026-07-15 10:00:00'),
+            ($1, 30, '2026-07-16', '2026-07-16 10:00:00')
+        ",
+    )
+    .bind(entry)
+    .execute(&pool)
+    .await
+    .unwrap();
+    time_entries::backfill_ranges(&pool, &Paris).await.unwrap();
+    let estimated = range("2026-07-15T11:00:00+02:00", "2026-07-15T12:00:00+02:00");
+    let logged = time_entries::list_for_entry(&pool, entry).await.unwrap();
+    assert!(logged.iter().all(|logged| logged.range_estimated));
+    assert_eq!(logged[1].range(), Some(estimated));
+
+    // Time logged over the estimate is neither refused nor reported.
+    time_entries::create(
+        &pool,
+        entry,
+        60,
+        "2026-07-15",
+        None,
+        Some(estimated),
+        OverlapPolicy::Refuse,
+    )
+    .await
+    .unwrap();
+    assert!(overlaps::find(&pool, "2026-07-15", "2026-07-15")
+        .await
+        .unwrap()
+        .is_empty());
+    let all = time_entries::list(&pool, 50, 0).await.unwrap();
+    assert!(overlaps::pairs(all).is_empty());
+
+    // Saving an estimate unchanged keeps it one; a new range is logged.
+    let (kept, moved) = (logged[1].id, logged[0].id);
+    time_entries::update(
+        &pool,
+        kept,
+        60,
+        "2026-07-15",
+        Some("Sketches"),
+        Some(estimated),
+        OverlapPolicy::Allow,
+    )
+    .await
+    .unwrap();
+    let afternoon = range("2026-07-16T14:00:00+02:00", "2026-07-16T14:30:00+02:00");
+    time_entries::update(
+        &pool,
+        moved,
+        30,
+        "2026-07-16",
+        None,
+        Some(afternoon),
+        OverlapPolicy::Allow,
+    )
+    .await
+    .unwrap();
+    let estimated: Vec<(i64, bool)> = time_entries::list_for_entry(&pool, entry)
+        .await
+        .unwrap()
+        .iter()
+        .map(|logged| (logged.id, logged.range_estimated))
+        .collect();
+    assert!(estimated.contains(&(kept, true)));
+    assert!(estimated.contains(&(moved, false)));
+}
@@ -0,0 +1,180 @@
+//! Requests answered by `dispatch`, the same over the socket and from the
+//! CLI when the app is not running.
+
+mod common;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde_json::{json, Value};
+use sqlx::SqlitePool;
+use timeloop_lib::db::overlaps::OverlapPolicy;
+use timeloop_lib::db::{entries, time_entries};
+use timeloop_lib::rpc::{self, Started, Stopped, TodayTotal};
+use timeloop_lib::Error;
+
+use common::memory;
+
+/// Noon UTC, on the same local day in every time zone the tests run in.
+fn at(minutes: i64) -> DateTime<Utc> {
+    "2026-10-14T12:00:00Z".parse::<DateTime<Utc>>().unwrap() + TimeDelta::minutes(minutes)
+}
+
+async fn call(pool: &SqlitePool, method: &str, params: Value, minutes: i64) -> Value {
+    rpc::dispatch(pool, method, params, at(minutes))
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn methods_drive_the_timer() {
+    let pool = memory().await;
+    let started: Started = serde_json::from_value(
+        call(
+            &pool,
+            "timer.start",
+            json!({ "title": "Client call", "category": "Clients", "note": "Kickoff" }),
+            0,
+        )
+        .await,
+    )
+    .unwrap();
+    assert_eq!(started.entry_title, "Client call");
+    assert_eq!(started.stopped, None);
+
+    let timer = call(&pool, "timer.get", Value::Null, 5).await;
+    assert_eq!(timer["entry_title"], "Client call");
+    assert_eq!(timer["note"], "Kickoff");
+    assert_eq!(
+        call(&pool, "timer.pause", Value::Null, 10).await,
+        Value::Null
+    );
+    assert!(call(&pool, "timer.get", Value::Null, 15).await["resumed_at"].is_null());
+    assert_eq!(
+        call(&pool, "timer.resume", Value::Null, 20).await,
+        Value::Null
+    );
+
+    // Paused for ten minutes, the timer has logged 25 at 12:35.
+    let stopped: Stopped =
+        serde_json::from_value(call(&pool, "timer.stop", Value::Null, 35).await).unwrap();
+    assert_eq!(stopped.entry_title, "Client call");
+    assert_eq!(stopped.logged, Some(25));
+    assert_eq!(call(&pool, "timer.get", Value::Null, 40).await, Value::Null);
+    assert!(matches!(
+        rpc::dispatch(&pool, "timer.stop", Value::Null, at(40)).await,
+        Err(Error::NotFound("timer"))
+    ));
+
+    // Started by id, then replaced by the entry of the same title.
+    let logo = entries::create(&pool, "Logo", None).await.unwrap();
+    call(&pool, "timer.start", json!({ "entry_id": logo }), 40).await;
+    let started: Started = serde_json::from_value(
+        call(&pool, "timer.start", json!({ "title": "client CALL" }), 50).await,
+    )
+    .unwrap();
+    assert_eq!(started.entry_title, "Client call");
+    assert_eq!(started.stopped.as_deref(), Some("Logo"));
+    assert_eq!(entries::list(&pool).await.unwrap().len(), 2);
+
+    // Less than half a minute logs nothing.
+    let stopped: Stopped = serde_json::from_value(
+        rpc::dispatch(
+            &pool,
+            "timer.stop",
+            Value::Null,
+            at(50) + TimeDelta::seconds(20),
+        )
+        .await
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(stopped.logged, None);
+}
+
+#[tokio::test]
+async fn today_total_counts_the_timer_apart() {
+    let pool = memory().await;
+    let logo = entries::create(&pool, "Logo", None).await.unwrap();
+    time_entries::create(
+        &pool,
+        logo,
+        90,
+        "2026-10-14",
+        None,
+        None,
+        OverlapPolicy::Allow,
+    )
+    .await
+    .unwrap();
+    time_entries::create(
+        &pool,
+        logo,
+        30,
+        "2026-10-13",
+        None,
+        None,
+        OverlapPolicy::Allow,
+    )
+    .await
+    .unwrap();
+    call(&pool, "timer.start", json!({ "entry_id": logo }), 0).await;
+
+    let total: TodayTotal =
+        serde_json::from_value(call(&pool, "today.total", Value::Null, 10).await).unwrap();
+    assert_eq!(total.date.to_string(), "2026-10-14");
+    assert_eq!(total.minutes, 90);
+    assert_eq!(total.timer_seconds, 10 * 60);
+}
+
+#[tokio::test]
+async fn unknown_methods_and_bad_params_are_refused() {
+    let pool = memory().await;
+    let refused = |method: &'static str, params: Value| {
+        let pool = pool.clone();
+        async move {
+            rpc::dispatch(&pool, method, params, at(0))
+                .await
+                .unwrap_err()
+        }
+    };
+
+    for method in ["timer.reset", "today", ""] {
+        assert!(matches!(
+            refused(method, Value::Null).await,
+            Error::NotFound("method")
+        ));
+    }
+    assert!(matches!(
+        refused("timer.start", json!({})).await,
+        Error::Invalid { field: "title", .. }
+    ));
+    assert!(matches!(
+        refused("timer.start", json!({ "entry_id": "one" })).await,
+        Error::Json(_)
+    ));
+    assert!(matches!(
+        refused("timer.start", Value::Null).await,
+        Error::Json(_)
+    ));
+    assert!(matches!(
+        refused("timer.start", json!({ "entry_id": 99 })).await,
+        Error::NotFound("entry")
+    ));
+    for method in ["timer.pause", "timer.resume"] {
+        assert!(matches!(
+            refused(method, Value::Null).await,
+            Error::NotFound("timer")
+        ));
+    }
+    assert!(entries::list(&pool).await.unwrap().is_empty());
+}
+
+#[test]
+fn only_timer_methods_other_than_get_change_it() {
+    for method in rpc::METHODS {
+        assert_eq!(
+            rpc::changes_timer(method),
+            !matches!(method, "timer.get" | "today.total"),
+            "{method}"
+        );
+    }
+}
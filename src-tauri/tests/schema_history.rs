@@ -0,0 +1,467 @@
+//! Fixture databases, one per schema version the app shipped, migrated to
+//! the latest schema.
+//!
+//! `fixtures/vN.sql` holds data as the build shipping version N wrote it. The
+//! fixture is loaded into a database built from the migrations up to N, then
+//! opened like the app does. Every fixture must come out with its data
+//! intact and readable through the queries; a new schema version needs a
+//! fixture of its own.
+
+mod common;
+
+use std::path::PathBuf;
+
+use chrono::NaiveDate;
+use sqlx::SqlitePool;
+use timeloop_lib::db::{
+    categories, entries, migrations, reports, settings, snapshots, time_entries, timer, Database,
+    TimeEntry,
+};
+
+use common::{connect, count, database_path, has_column, has_table};
+
+struct Fixture {
+    version: i64,
+    sql: &'static str,
+    categories: i64,
+    entries: i64,
+    time_entries: i64,
+    /// Sum of the durations.
+    minutes: i64,
+    timer: bool,
+    settings: i64,
+}
+
+const FIXTURES: &[Fixture] = &[
+    Fixture {
+        version: 1,
+        sql: include_str!("fixtures/v1.sql"),
+        categories: 3,
+        entries: 6,
+        time_entries: 10,
+        minutes: 1106,
+        timer: false,
+        settings: 0,
+    },
+    Fixture {
+        version: 2,
+        sql: include_str!("fixtures/v2.sql"),
+        categories: 2,
+        entries: 3,
+        time_entries: 4,
+        minutes: 180,
+        timer: true,
+        settings: 0,
+    },
+    Fixture {
+        version: 3,
+        sql: include_str!("fixtures/v3.sql"),
+        categories: 1,
+        entries: 2,
+        time_entries: 4,
+        minutes: 300,
+        timer: true,
+        settings: 0,
+    },
+    Fixture {
+        version: 4,
+        sql: include_str!("fixtures/v4.sql"),
+        categories: 1,
+        entries: 2,
+        time_entries: 2,
+        minutes: 200,
+        timer: false,
+        settings: 2,
+    },
+    Fixture {
+        version: 5,
+        sql: include_str!("fixtures/v5.sql"),
+        categories: 0,
+        entries: 2,
+        time_entries: 1,
+        minutes: 95,
+        timer: true,
+        settings: 1,
+    },
+    Fixture {
+        version: 6,
+        sql: include_str!("fixtures/v6.sql"),
+        categories: 2,
+        entries: 3,
+        time_entries: 3,
+        minutes: 305,
+        timer: true,
+        settings: 1,
+    },
+    Fixture {
+        version: 7,
+        sql: include_str!("fixtures/v7.sql"),
+        categories: 1,
+        entries: 3,
+        time_entries: 4,
+        minutes: 255,
+        timer: false,
+        settings: 1,
+    },
+    Fixture {
+        version: 8,
+        sql: include_str!("fixtures/v8.sql"),
+        categories: 1,
+        entries: 2,
+        time_entries: 3,
+        minutes: 135,
+        timer: false,
+        settings: 1,
+    },
+];
+
+/// A database at the fixture's schema version holding its data.
+async fn load(fixture: &Fixture) -> PathBuf {
+    let path = database_path(&format!("fixture-v{}", fixture.version));
+    let pool = connect(&path).await;
+    migrations::run_to(&pool, fixture.version).await.unwrap();
+    sqlx::raw_sql(fixture.sql).execute(&pool).await.unwrap();
+    pool.close().await;
+    path
+}
+
+/// The rows of every column the schema at `version` has, to compare the
+/// data before and after migrating.
+async fn rows(pool: &SqlitePool, version: i64) -> Vec<String> {
+    let mut queries = vec![
+        "SELECT 'category|' || id || '|' || name || '|' || color || '|' || created_at
+         FROM categories",
+        "SELECT 'entry|' || id || '|' || title || '|' || COALESCE(category_id, '') || '|'
+             || created_at
+         FROM entries",
+        "SELECT 'time entry|' || id || '|' || entry_id || '|' || duration || '|' || date || '|'
+             || COALESCE(note, '<null>') || '|' || created_at
+         FROM time_entries",
+    ];
+    if version >= 2 {
+        queries.push(
+            "SELECT 'timer|' || entry_id || '|' || COALESCE(note, '') || '|' || started_at || '|'
+                 || COALESCE(resumed_at, '') || '|' || accumulated_seconds
+             FROM active_timers",
+        );
+    }
+    if version >= 3 {
+        queries.push(
+            "SELECT 'range|' || id || '|' || started_at || '|' || ended_at || '|' || utc_offset
+             FROM time_entries WHERE started_at IS NOT NULL",
+        );
+    }
+    if version >= 4 {
+        queries.push("SELECT 'setting|' || key || '|' || value FROM settings");
+    }
+    if version >= 5 {
+        queries.push(
+            "SELECT 'idle|' || COALESCE(idle_since, '') || '|' || COALESCE(idle_until, '')
+             FROM active_timers",
+        );
+    }
+    if version >= 7 {
+        queries.push(
+            "SELECT 'source|' || id || '|' || source FROM time_entries WHERE source IS NOT NULL",
+        );
+    }
+    if version >= 8 {
+        queries.push("SELECT 'estimated|' || id FROM time_entries WHERE range_estimated");
+    }
+    let mut rows = Vec::new();
+    for query in queries {
+        rows.extend(
+            sqlx::query_scalar::<_, String>(query)
+                .fetch_all(pool)
+                .await
+                .unwrap(),
+        );
+    }
+    rows.sort();
+    rows
+}
+
+/// Tables, their columns and indexes, to compare migrated databases with a
+/// fresh one.
+async fn schema(pool: &SqlitePool) -> Vec<String> {
+    sqlx::query_scalar(
+        "
+        SELECT m.type || ' ' || m.name || COALESCE(' ' || c.name || ' ' || c.type
+            || ' notnull=' || c.\"notnull\" || ' default=' || COALESCE(c.dflt_value, ''), '')
+        FROM sqlite_master m
+        LEFT JOIN pragma_table_info(m.name) c ON m.type = 'table'
+        WHERE m.name NOT LIKE 'sqlite_%' AND m.name != '_sqlx_migrations'
+        ORDER BY m.type, m.name, c.cid
+        ",
+    )
+    .fetch_all(pool)
+    .await
+    .unwrap()
+}
+
+/// What holds for any database the app opened, whatever it came from.
+async fn assert_invariants(pool: &SqlitePool) {
+    let latest = migrations::latest_version();
+    assert_eq!(
+        migrations::applied_versions(pool).await.unwrap(),
+        (1..=latest).collect::<Vec<_>>()
+    );
+    let integrity: String = sqlx::query_scalar("PRAGMA integrity_check")
+        .fetch_one(pool)
+        .await
+        .unwrap();
+    assert_eq!(integrity, "ok");
+    let violations = sqlx::query("PRAGMA foreign_key_check")
+        .fetch_all(pool)
+        .await
+        .unwrap();
+    assert!(violations.is_empty(), "foreign key violations");
+
+    let logged = time_entries::list(pool, i64::MAX, 0).await.unwrap();
+    assert_eq!(logged.len() as i64, count(pool, "time_entries").await);
+    for entry in &logged {
+        let entry = &entry.time_entry;
+        assert!(
+            entry.duration > 0,
+            "time entry {} has no duration",
+            entry.id
+        );
+        assert!(
+            NaiveDate::parse_from_str(&entry.date, "%Y-%m-%d").is_ok(),
+            "time entry {} has date {:?}",
+            entry.id,
+            entry.date
+        );
+        let columns = [
+            entry.started_at.is_some(),
+            entry.ended_at.is_some(),
+            entry.utc_offset.is_some(),
+        ];
+        assert!(
+            columns.iter().all(|set| *set == columns[0]),
+            "time entry {} has part of a range",
+            entry.id
+        );
+        if let Some(range) = entry.range() {
+            assert_eq!(range.minutes(), entry.duration, "time entry {}", entry.id);
+            assert_eq!(range.started_at.date_naive().to_string(), entry.date);
+        }
+    }
+
+    assert_eq!(
+        categories::list(pool).await.unwrap().len() as i64,
+        count(pool, "categories").await
+    );
+    assert_eq!(
+        entries::list(pool).await.unwrap().len() as i64,
+        count(pool, "entries").await
+    );
+    let total: i64 = reports::entry_totals(pool)
+        .await
+        .unwrap()
+        .iter()
+        .map(|total| total.total_duration)
+        .sum();
+    let minutes: i64 = sqlx::query_scalar("SELECT COALESCE(SUM(duration), 0) FROM time_entries")
+        .fetch_one(pool)
+        .await
+        .unwrap();
+    assert_eq!(total, minutes);
+
+    let timers = count(pool, "active_timers").await;
+    assert!(timers <= 1);
+    assert_eq!(timer::get(pool).await.unwrap().is_some(), timers == 1);
+}
+
+#[tokio::test]
+async fn every_schema_version_has_a_fixture() {
+    assert_eq!(
+        FIXTURES
+            .iter()
+            .map(|fixture| fixture.version)
+            .collect::<Vec<_>>(),
+        (1..=migrations::latest_version()).collect::<Vec<_>>()
+    );
+}
+
+#[tokio::test]
+async fn fresh_database_has_the_latest_schema() {
+    let database = Database::open(&database_path("fresh")).await.unwrap();
+    let pool = database.pool();
+    assert_invariants(pool).await;
+    for table in [
+        "categories",
+        "entries",
+        "time_entries",
+        "active_timers",
+        "settings",
+    ] {
+        assert!(has_table(pool, table).await, "no {table} table");
+        assert_eq!(count(pool, table).await, 0);
+    }
+    assert!(has_column(pool, "active_timers", "idle_until").await);
+    assert!(has_column(pool, "time_entries", "source").await);
+    assert!(has_column(pool, "time_entries", "range_estimated").await);
+}
+
+#[tokio::test]
+async fn fixtures_migrate_to_the_latest_schema() {
+    let fresh = Database::open(&database_path("fresh-schema"))
+        .await
+        .unwrap();
+    let latest_schema = schema(fresh.pool()).await;
+
+    for fixture in FIXTURES {
+        let path = load(fixture).await;
+        let pool = connect(&path).await;
+        let before = rows(&pool, fixture.version).await;
+        pool.close().await;
+
+        let database = Database::open(&path).await.unwrap();
+        let pool = database.pool();
+        let version = fixture.version;
+        assert_invariants(pool).await;
+        assert_eq!(schema(pool).await, latest_schema, "fixture v{version}");
+        assert_eq!(
+            rows(pool, version).await,
+            before,
+            "fixture v{version} data changed"
+        );
+
+        assert_eq!(count(pool, "categories").await, fixture.categories);
+        assert_eq!(count(pool, "entries").await, fixture.entries);
+        assert_eq!(count(pool, "time_entries").await, fixture.time_entries);
+        let minutes = reports::total_for_period(pool, "0000-01-01", "9999-12-31")
+            .await
+            .unwrap();
+        assert_eq!(minutes, fixture.minutes, "fixture v{version}");
+        assert_eq!(timer::get(pool).await.unwrap().is_some(), fixture.timer);
+        assert_eq!(count(pool, "settings").await, fixture.settings);
+
+        // Older databases were snapshotted before migrating.
+        let taken = snapshots::list(&snapshots::directory(&path)).unwrap();
+        if version < migrations::latest_version() {
+            assert_eq!(taken.len(), 1, "fixture v{version}");
+            assert!(taken[0]
+                .name
+                .starts_with(&format!("pre-migration-v{version}-")));
+        } else {
+            assert!(taken.is_empty());
+        }
+    }
+}
+
+#[tokio::test]
+async fn migrated_fixtures_keep_working() {
+    for fixture in FIXTURES {
+        let database = Database::open(&load(fixture).await).await.unwrap();
+        let pool = database.pool();
+        let entry_id = entries::create(pool, "After the migration", None)
+            .await
+            .unwrap();
+        timer::start(pool, entry_id, None, chrono::Utc::now())
+            .await
+            .unwrap();
+        timer::stop(pool, chrono::Utc::now()).await.unwrap();
+        settings::set(pool, "fixture", "migrated").await.unwrap();
+        assert_eq!(
+            settings::get(pool, "fixture").await.unwrap().as_deref(),
+            Some("migrated")
+        );
+        assert_invariants(pool).await;
+    }
+}
+
+#[tokio::test]
+async fn rows_breaking_the_constraints_are_repaired() {
+    let path = database_path("repair");
+    let pool = connect(&path).await;
+    migrations::run_to(&pool, 5).await.unwrap();
+    sqlx::raw_sql(
+        "
+        PRAGMA foreign_keys = OFF;
+        INSERT INTO categories (id, name) VALUES (1, 'Kept');
+        INSERT INTO entries (id, title, category_id) VALUES
+            (1, 'Categorized', 1), (2, 'Deleted category', 9);
+        INSERT INTO time_entries (id, entry_id, duration, date, created_at) VALUES
+            (1, 1, 30, '2024-03-05', '2024-03-05 10:00:00'),
+            (2, 9, 30, '2024-03-05', '2024-03-05 10:00:00'),
+            (3, 1, 0, '2024-03-05', '2024-03-05 10:00:00'),
+            (4, 2, -15, '2024-03-05', '2024-03-05 10:00:00'),
+            (5, 2, 45, '2024-03-06T09:00:00', '2024-03-06 10:00:00'),
+            (6, 2, 20, '07/03/2024', '2024-03-07 18:30:00'),
+            (7, 1, 10, 'yesterday', 'unknown');
+        INSERT INTO active_timers (id, entry_id, started_at) VALUES (1, 9, '2024-03-08T08:00:00Z');
+        ",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    pool.close().await;
+
+    let database = Database::open(&path).await.unwrap();
+    let pool = database.pool();
+    assert_invariants(pool).await;
+    let entry = entries::get(pool, 2).await.unwrap().unwrap();
+    assert_eq!(entry.category_id, None);
+    let dates: Vec<(i64, String)> = sqlx::query_as("SELECT id, date FROM time_entries ORDER BY id")
+        .fetch_all(pool)
+        .await
+        .unwrap();
+    assert_eq!(
+        dates,
+        [
+            (1, "2024-03-05".to_string()),
+            (5, "2024-03-06".to_string()),
+            (6, "2024-03-07".to_string()),
+        ]
+    );
+    assert!(timer::get(pool).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn constraints_hold_on_every_connection() {
+    let database = Database::open(&database_path("constraints")).await.unwrap();
+    let pool = database.pool();
+    let category_id = categories::create(pool, "Clients", "#ef4444")
+        .await
+        .unwrap();
+    let entry_id = entries::create(pool, "Website", Some(category_id))
+        .await
+        .unwrap();
+    sqlx::query(
+        "INSERT INTO time_entries (entry_id, duration, date) VALUES ($1, 30, '2025-01-06')",
+    )
+    .bind(entry_id)
+    .execute(pool)
+    .await
+    .unwrap();
+
+    for (duration, date) in [(0, "2025-01-06"), (-5, "2025-01-06"), (30, "6 Jan 2025")] {
+        let inserted =
+            sqlx::query("INSERT INTO time_entries (entry_id, duration, date) VALUES ($1, $2, $3)")
+                .bind(entry_id)
+                .bind(duration)
+                .bind(date)
+                .execute(pool)
+                .await;
+        assert!(inserted.is_err(), "{duration} minutes on {date} inserted");
+    }
+    let orphan = sqlx::query(
+        "INSERT INTO time_entries (entry_id, duration, date) VALUES (999, 30, '2025-01-06')",
+    )
+    .execute(pool)
+    .await;
+    assert!(orphan.is_err());
+
+    categories::delete(pool, category_id).await.unwrap();
+    let entry = entries::get(pool, entry_id).await.unwrap().unwrap();
+    assert_eq!(entry.category_id, None);
+
+    entries::delete(pool, entry_id).await.unwrap();
+    let logged: Vec<TimeEntry> = sqlx::query_as("SELECT * FROM time_entries")
+        .fetch_all(pool)
+        .await
+        .unwrap();
+    assert!(logged.is_empty());
+}
@@ -0,0 +1,127 @@
+//! Accelerators written one way whatever their spelling, and bindings
+//! refusing keys another action has.
+
+use timeloop_lib::shortcuts::{normalize, rebind, ShortcutAction, ShortcutBinding};
+use timeloop_lib::Error;
+
+/// Every action on its default shortcut.
+fn defaults() -> Vec<ShortcutBinding> {
+    ShortcutAction::ALL
+        .into_iter()
+        .map(|action| {
+            let default_accelerator = normalize(action.default_accelerator()).unwrap();
+            ShortcutBinding {
+                action,
+                accelerator: Some(default_accelerator.clone()),
+                default_accelerator,
+            }
+        })
+        .collect()
+}
+
+fn accelerator(bindings: &[ShortcutBinding], action: ShortcutAction) -> Option<&str> {
+    bindings
+        .iter()
+        .find(|binding| binding.action == action)
+        .and_then(|binding| binding.accelerator.as_deref())
+}
+
+#[test]
+fn accelerators_are_written_one_way() {
+    let cmd_or_ctrl = if cfg!(target_os = "macos") {
+        "Alt+Super+T"
+    } else {
+        "Ctrl+Alt+T"
+    };
+    for (given, normalized) in [
+        ("shift+ctrl+t", "Ctrl+Shift+T"),
+        ("Control + Option + KeyT", "Ctrl+Alt+T"),
+        ("CmdOrCtrl+Alt+t", cmd_or_ctrl),
+        ("Meta+Digit1", "Super+1"),
+        ("Super+esc", "Super+Escape"),
+        ("Alt+up", "Alt+ArrowUp"),
+        ("Ctrl+/", "Ctrl+Slash"),
+        ("Ctrl+Shift+Ctrl+T", "Ctrl+Shift+T"),
+        ("F13", "F13"),
+        ("shift+f1", "Shift+F1"),
+    ] {
+        assert_eq!(normalize(given).unwrap(), normalized, "{given:?}");
+    }
+}
+
+#[test]
+fn accelerators_need_one_key_and_a_modifier() {
+    for given in [
+        "",
+        "T",
+        "Ctrl+",
+        "Ctrl++T",
+        "Ctrl+Shift",
+        "Ctrl+T+U",
+        "Ctrl+Foo",
+        "F25",
+        "Space",
+    ] {
+        assert!(
+            matches!(
+                normalize(given),
+                Err(Error::Invalid {
+                    field: "accelerator",
+                    ..
+                })
+            ),
+            "{given:?}"
+        );
+    }
+}
+
+#[test]
+fn rebinding_refuses_keys_another_action_has() {
+    let bindings = defaults();
+    let err = rebind(
+        &bindings,
+        ShortcutAction::QuickLog,
+        Some("shift+alt+cmdorctrl+t"),
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("toggle the timer"), "{err}");
+
+    // An action may keep its own keys, spelled another way.
+    let kept = rebind(
+        &bindings,
+        ShortcutAction::ToggleTimer,
+        Some("shift+alt+cmdorctrl+t"),
+    )
+    .unwrap();
+    assert_eq!(kept, bindings);
+
+    let moved = rebind(&bindings, ShortcutAction::QuickLog, Some("ctrl+alt+k")).unwrap();
+    assert_eq!(
+        accelerator(&moved, ShortcutAction::QuickLog),
+        Some("Ctrl+Alt+K")
+    );
+    assert_eq!(
+        accelerator(&moved, ShortcutAction::ToggleTimer),
+        accelerator(&bindings, ShortcutAction::ToggleTimer)
+    );
+}
+
+#[test]
+fn unbound_keys_are_free_for_other_actions() {
+    let bindings = defaults();
+    for blank in [None, Some(""), Some("  ")] {
+        let unbound = rebind(&bindings, ShortcutAction::ToggleTimer, blank).unwrap();
+        assert_eq!(accelerator(&unbound, ShortcutAction::ToggleTimer), None);
+    }
+    let unbound = rebind(&bindings, ShortcutAction::ToggleTimer, None).unwrap();
+    let toggle = bindings[0].default_accelerator.clone();
+    let moved = rebind(&unbound, ShortcutAction::StopTimer, Some(&toggle)).unwrap();
+    assert_eq!(
+        accelerator(&moved, ShortcutAction::StopTimer),
+        Some(toggle.as_str())
+    );
+    assert!(matches!(
+        rebind(&bindings, ShortcutAction::StopTimer, Some("Ctrl+T+U")),
+        Err(Error::Invalid { .. })
+    ));
+}
@@ -0,0 +1,147 @@
+//! Snapshots of a database in a temporary directory: which are kept as they
+//! rotate, and restoring one of them.
+
+mod common;
+
+use std::path::Path;
+
+use chrono::{DateTime, Local, TimeDelta, TimeZone};
+use timeloop_lib::db::snapshots::{self, SnapshotKind};
+use timeloop_lib::db::{entries, Database};
+use timeloop_lib::Error;
+
+use common::database_path;
+
+/// 10:00 on Monday 5 January 2026, the first day of ISO week 2.
+fn monday() -> DateTime<Local> {
+    Local.with_ymd_and_hms(2026, 1, 5, 10, 0, 0).unwrap()
+}
+
+/// Names of the snapshots of `kind`, most recent first.
+fn names(dir: &Path, kind: SnapshotKind) -> Vec<String> {
+    snapshots::list(dir)
+        .unwrap()
+        .into_iter()
+        .filter(|snapshot| snapshot.kind == kind)
+        .map(|snapshot| snapshot.name)
+        .collect()
+}
+
+#[tokio::test]
+async fn rotation_keeps_seven_days_and_four_weeks() {
+    let path = database_path("snapshots-rotate");
+    let database = Database::open(&path).await.unwrap();
+    let dir = snapshots::directory(&path);
+    // Up to Tuesday 3 February, in week 6.
+    for day in 0..30 {
+        let now = monday() + TimeDelta::days(day);
+        snapshots::rotate(database.pool(), &dir, now).await.unwrap();
+        // Later the same day, both snapshots are already there.
+        snapshots::rotate(database.pool(), &dir, now + TimeDelta::hours(8))
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(
+        names(&dir, SnapshotKind::Daily),
+        [
+            "daily-2026-02-03.db",
+            "daily-2026-02-02.db",
+            "daily-2026-02-01.db",
+            "daily-2026-01-31.db",
+            "daily-2026-01-30.db",
+            "daily-2026-01-29.db",
+            "daily-2026-01-28.db",
+        ]
+    );
+    assert_eq!(
+        names(&dir, SnapshotKind::Weekly),
+        [
+            "weekly-2026-W06.db",
+            "weekly-2026-W05.db",
+            "weekly-2026-W04.db",
+            "weekly-2026-W03.db",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn pruning_keeps_the_five_latest_of_each_other_kind() {
+    let path = database_path("snapshots-prune");
+    let database = Database::open(&path).await.unwrap();
+    let dir = snapshots::directory(&path);
+    std::fs::create_dir_all(&dir).unwrap();
+    let kinds = [
+        (SnapshotKind::PreMigration, "pre-migration-v7"),
+        (SnapshotKind::PreRestore, "pre-restore"),
+        (SnapshotKind::PreRepair, "pre-repair"),
+    ];
+    for (_, prefix) in kinds {
+        for minute in 0..8 {
+            let name = format!("{prefix}-20260105T10{minute:02}00000.db");
+            std::fs::write(dir.join(name), "").unwrap();
+        }
+    }
+    // Files that are not snapshots are left alone.
+    for other in ["notes.txt", "daily-2026-01-01.db.partial"] {
+        std::fs::write(dir.join(other), "").unwrap();
+    }
+
+    snapshots::rotate(database.pool(), &dir, monday())
+        .await
+        .unwrap();
+    for (kind, prefix) in kinds {
+        let kept: Vec<String> = (3..8)
+            .rev()
+            .map(|minute| format!("{prefix}-20260105T10{minute:02}00000.db"))
+            .collect();
+        assert_eq!(names(&dir, kind), kept);
+    }
+    assert_eq!(names(&dir, SnapshotKind::Daily), ["daily-2026-01-05.db"]);
+    assert!(dir.join("notes.txt").exists());
+    assert!(dir.join("daily-2026-01-01.db.partial").exists());
+}
+
+#[tokio::test]
+async fn restoring_brings_the_snapshot_back() {
+    let path = database_path("snapshots-restore");
+    let database = Database::open(&path).await.unwrap();
+    let pool = database.pool();
+    let dir = snapshots::directory(&path);
+    entries::create(pool, "Logo", None).await.unwrap();
+    snapshots::rotate(pool, &dir, monday()).await.unwrap();
+    entries::create(pool, "Client call", None).await.unwrap();
+
+    let summary = snapshots::restore(&database, "daily-2026-01-05.db", monday())
+        .await
+        .unwrap();
+    assert_eq!(summary.entries, 1);
+    let titles: Vec<String> = entries::list(pool)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.title)
+        .collect();
+    assert_eq!(titles, ["Logo"]);
+    // What was replaced is kept.
+    assert_eq!(names(&dir, SnapshotKind::PreRestore).len(), 1);
+
+    // Only snapshots `list` returns can be restored.
+    std::fs::write(dir.join("notes.db"), "").unwrap();
+    for name in [
+        "daily-2026-01-06.db",
+        "../timeloop.db",
+        "notes.db",
+        "daily-2026-01-05.db.partial",
+    ] {
+        assert!(
+            matches!(
+                snapshots::restore(&database, name, monday()).await,
+                Err(Error::NotFound("backup"))
+            ),
+            "{name}"
+        );
+    }
+    assert_eq!(names(&dir, SnapshotKind::PreRestore).len(), 1);
+    assert_eq!(entries::list(pool).await.unwrap().len(), 1);
+}
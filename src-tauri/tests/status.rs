@@ -0,0 +1,188 @@
+//! What `timeloop-cli status` prints for each status bar, with the timer
+//! running, paused or stopped.
+
+use chrono::{DateTime, Local, NaiveDate, TimeDelta, Utc};
+use serde_json::{json, Value};
+use timeloop_lib::cli::status::{Status, StatusFormat};
+use timeloop_lib::db::timer::ActiveTimer;
+
+/// Noon UTC, on the same local day in every time zone the tests run in.
+fn noon() -> DateTime<Utc> {
+    "2026-10-14T12:00:00Z".parse().unwrap()
+}
+
+fn minutes(minutes: i64) -> TimeDelta {
+    TimeDelta::minutes(minutes)
+}
+
+fn timer(
+    title: &str,
+    started_at: DateTime<Utc>,
+    resumed_at: Option<DateTime<Utc>>,
+    accumulated_seconds: i64,
+) -> ActiveTimer {
+    ActiveTimer {
+        entry_id: 1,
+        entry_title: title.to_string(),
+        note: None,
+        started_at,
+        resumed_at,
+        accumulated_seconds,
+        idle_since: None,
+        idle_until: None,
+    }
+}
+
+fn running() -> Status {
+    let started_at = noon() - minutes(25);
+    let timer = timer("Client call", started_at, Some(started_at), 0);
+    Status::new(Some(timer), 90, noon())
+}
+
+/// Ran for ten minutes, with markup in its title.
+fn paused() -> Status {
+    let timer = timer("R&D <site>", noon() - minutes(40), None, 10 * 60);
+    Status::new(Some(timer), 90, noon())
+}
+
+fn stopped() -> Status {
+    Status::new(None, 90, noon())
+}
+
+fn json(line: String) -> Value {
+    serde_json::from_str(&line).unwrap()
+}
+
+#[test]
+fn text_and_polybar_are_lines_of_text() {
+    assert_eq!(
+        running().render(StatusFormat::Text),
+        "Client call 0:25\nToday: 1h55"
+    );
+    assert_eq!(
+        paused().render(StatusFormat::Text),
+        "R&D <site> 0:10 (paused)\nToday: 1h40"
+    );
+    assert_eq!(
+        stopped().render(StatusFormat::Text),
+        "No timer\nToday: 1h30"
+    );
+
+    assert_eq!(
+        running().render(StatusFormat::Polybar),
+        "Client call 0:25 · 1h55 today"
+    );
+    assert_eq!(
+        paused().render(StatusFormat::Polybar),
+        "R&D <site> 0:10 (paused) · 1h40 today"
+    );
+    assert_eq!(
+        stopped().render(StatusFormat::Polybar),
+        "No timer · 1h30 today"
+    );
+}
+
+#[test]
+fn waybar_gets_escaped_markup_and_the_state_as_class() {
+    assert_eq!(
+        json(running().render(StatusFormat::Waybar)),
+        json!({
+            "text": "Client call 0:25 · 1h55 today",
+            "tooltip": "Client call 0:25\nToday: 1h55",
+            "alt": "running",
+            "class": "running",
+        })
+    );
+    assert_eq!(
+        json(paused().render(StatusFormat::Waybar)),
+        json!({
+            "text": "R&amp;D &lt;site&gt; 0:10 (paused) · 1h40 today",
+            "tooltip": "R&amp;D &lt;site&gt; 0:10 (paused)\nToday: 1h40",
+            "alt": "paused",
+            "class": "paused",
+        })
+    );
+    assert_eq!(
+        json(stopped().render(StatusFormat::Waybar)),
+        json!({
+            "text": "No timer · 1h30 today",
+            "tooltip": "No timer\nToday: 1h30",
+            "alt": "stopped",
+            "class": "stopped",
+        })
+    );
+}
+
+#[test]
+fn i3blocks_shortens_to_the_timer_and_greys_it_out_when_paused() {
+    assert_eq!(
+        json(running().render(StatusFormat::I3blocks)),
+        json!({
+            "full_text": "Client call 0:25 · 1h55 today",
+            "short_text": "0:25",
+        })
+    );
+    assert_eq!(
+        json(paused().render(StatusFormat::I3blocks)),
+        json!({
+            "full_text": "R&D <site> 0:10 (paused) · 1h40 today",
+            "short_text": "0:10",
+            "color": "#888888",
+        })
+    );
+    assert_eq!(
+        json(stopped().render(StatusFormat::I3blocks)),
+        json!({
+            "full_text": "No timer · 1h30 today",
+            "short_text": "1h30",
+        })
+    );
+}
+
+#[test]
+fn today_counts_the_timer_from_midnight_only() {
+    let midnight = NaiveDate::from_ymd_opt(2026, 10, 14)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+        .and_local_timezone(Local)
+        .unwrap()
+        .to_utc();
+    let now = midnight + minutes(30);
+
+    // Running since 22:00, the timer shows all of it but adds half an hour
+    // to today.
+    let overnight = timer(
+        "Deploy",
+        midnight - minutes(120),
+        Some(midnight - minutes(120)),
+        0,
+    );
+    let status = Status::new(Some(overnight), 0, now);
+    assert_eq!(
+        status.render(StatusFormat::Text),
+        "Deploy 2:30\nToday: 30min"
+    );
+
+    // Paused at 23:00 and resumed at 00:10, only the last 20 minutes are
+    // today's.
+    let resumed = timer(
+        "Deploy",
+        midnight - minutes(120),
+        Some(midnight + minutes(10)),
+        3600,
+    );
+    let status = Status::new(Some(resumed), 15, now);
+    assert_eq!(
+        status.render(StatusFormat::Text),
+        "Deploy 1:20\nToday: 35min"
+    );
+
+    // Paused before midnight, the timer adds nothing.
+    let paused = timer("Deploy", midnight - minutes(120), None, 3600);
+    let status = Status::new(Some(paused), 15, now);
+    assert_eq!(
+        status.render(StatusFormat::Text),
+        "Deploy 1:00 (paused)\nToday: 15min"
+    );
+}
@@ -0,0 +1,127 @@
+//! The timer on a fixed clock: pauses left out of the time logged, and the
+//! time logged when it stops or another timer replaces it.
+
+mod common;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use timeloop_lib::db::{entries, time_entries, timer, TimeEntry};
+use timeloop_lib::Error;
+
+use common::memory;
+
+/// Noon UTC, on the same local day in every time zone the tests run in.
+fn noon() -> DateTime<Utc> {
+    "2026-10-14T12:00:00Z".parse().unwrap()
+}
+
+fn minutes(minutes: i64) -> TimeDelta {
+    TimeDelta::minutes(minutes)
+}
+
+async fn logged_on(pool: &sqlx::SqlitePool, entry_id: i64) -> Vec<TimeEntry> {
+    time_entries::list_for_entry(pool, entry_id).await.unwrap()
+}
+
+#[tokio::test]
+async fn pausing_leaves_the_paused_time_out() {
+    let pool = memory().await;
+    let entry = entries::create(&pool, "Logo", None).await.unwrap();
+    timer::start(&pool, entry, None, noon()).await.unwrap();
+    timer::pause(&pool, noon() + minutes(10)).await.unwrap();
+    // Pausing again does not count the pause.
+    timer::pause(&pool, noon() + minutes(20)).await.unwrap();
+    let paused = timer::get(&pool).await.unwrap().unwrap();
+    assert!(!paused.is_running());
+    assert_eq!(paused.elapsed_seconds(noon() + minutes(25)), 10 * 60);
+
+    timer::resume(&pool, noon() + minutes(30)).await.unwrap();
+    timer::resume(&pool, noon() + minutes(32)).await.unwrap();
+    let running = timer::get(&pool).await.unwrap().unwrap();
+    assert_eq!(running.elapsed_seconds(noon() + minutes(35)), 15 * 60);
+    assert!(!running.is_uninterrupted());
+
+    timer::stop(&pool, noon() + minutes(45)).await.unwrap();
+    let logged = logged_on(&pool, entry).await;
+    assert_eq!(logged.len(), 1);
+    assert_eq!(logged[0].duration, 25);
+    assert_eq!(logged[0].date, "2026-10-14");
+    // With pauses in between, when the time was worked is unknown.
+    assert_eq!(logged[0].range(), None);
+}
+
+#[tokio::test]
+async fn stopping_logs_the_rounded_minutes_and_clears_the_timer() {
+    let pool = memory().await;
+    let entry = entries::create(&pool, "Logo", None).await.unwrap();
+    timer::start(&pool, entry, Some("Sketches"), noon())
+        .await
+        .unwrap();
+    let stopped = timer::stop(&pool, noon() + minutes(44) + TimeDelta::seconds(30))
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(timer::get(&pool).await.unwrap().is_none());
+    assert_eq!(stopped.duration, 45);
+
+    let logged = logged_on(&pool, entry).await;
+    assert_eq!(logged[0].id, stopped.id);
+    assert_eq!(logged[0].duration, 45);
+    assert_eq!(logged[0].note.as_deref(), Some("Sketches"));
+    let range = logged[0].range().unwrap();
+    assert_eq!(range.started_at, noon());
+    assert_eq!(
+        range.ended_at,
+        noon() + minutes(44) + TimeDelta::seconds(30)
+    );
+
+    // Under half a minute, nothing is logged but the timer still stops.
+    timer::start(&pool, entry, None, noon() + minutes(50))
+        .await
+        .unwrap();
+    let stopped = timer::stop(&pool, noon() + minutes(50) + TimeDelta::seconds(29)).await;
+    assert!(stopped.unwrap().is_none());
+    assert!(timer::get(&pool).await.unwrap().is_none());
+    assert!(timer::stop(&pool, noon() + minutes(60))
+        .await
+        .unwrap()
+        .is_none());
+    assert!(matches!(
+        timer::pause(&pool, noon() + minutes(60)).await,
+        Err(Error::NotFound("timer"))
+    ));
+    assert_eq!(time_entries::list(&pool, 50, 0).await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn starting_while_running_logs_the_running_timer_first() {
+    let pool = memory().await;
+    let logo = entries::create(&pool, "Logo", None).await.unwrap();
+    let call = entries::create(&pool, "Client call", None).await.unwrap();
+    assert_eq!(
+        timer::start(&pool, logo, Some("Sketches"), noon())
+            .await
+            .unwrap(),
+        None
+    );
+
+    let replaced = timer::start(&pool, call, None, noon() + minutes(25))
+        .await
+        .unwrap()
+        .unwrap();
+    let logged = logged_on(&pool, logo).await;
+    assert_eq!(logged[0].id, replaced);
+    assert_eq!(logged[0].duration, 25);
+    assert_eq!(logged[0].note.as_deref(), Some("Sketches"));
+
+    let running = timer::get(&pool).await.unwrap().unwrap();
+    assert_eq!(running.entry_id, call);
+    assert_eq!(running.started_at, noon() + minutes(25));
+    assert_eq!(running.elapsed_seconds(noon() + minutes(30)), 5 * 60);
+
+    // A timer on an entry that does not exist replaces nothing.
+    assert!(timer::start(&pool, 99, None, noon() + minutes(40))
+        .await
+        .is_err());
+    assert_eq!(timer::get(&pool).await.unwrap().unwrap().entry_id, call);
+    assert!(logged_on(&pool, call).await.is_empty());
+}
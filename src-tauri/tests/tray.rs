@@ -0,0 +1,138 @@
+//! What the tray menu lists for the recent entries and the timer.
+
+#![cfg(desktop)]
+
+mod common;
+
+use chrono::{Duration, TimeZone, Utc};
+use timeloop_lib::cli::status::Status;
+use timeloop_lib::db::overlaps::OverlapPolicy;
+use timeloop_lib::db::{entries, time_entries, timer};
+use timeloop_lib::tray::{menu, Action, Item, Labels};
+
+use common::memory;
+
+fn entry(entry_id: i64, label: &str, checked: bool) -> Item {
+    Item::Entry {
+        entry_id,
+        label: label.to_string(),
+        checked,
+    }
+}
+
+fn stop(enabled: bool) -> Item {
+    Item::Stop {
+        label: "Stop timer".to_string(),
+        enabled,
+    }
+}
+
+#[tokio::test]
+async fn menu_lists_recent_entries_and_the_timer() {
+    let pool = memory().await;
+    let website = entries::create(&pool, "Website", None).await.unwrap();
+    let support = entries::create(&pool, "Q&A", None).await.unwrap();
+    let call = entries::create(&pool, "Client call", None).await.unwrap();
+    for (entry_id, date) in [(website, "2026-10-16"), (support, "2026-10-17")] {
+        time_entries::create(&pool, entry_id, 20, date, None, None, OverlapPolicy::Allow)
+            .await
+            .unwrap();
+    }
+    let recent = entries::recent(&pool, 8).await.unwrap();
+    let now = Utc.with_ymd_and_hms(2026, 10, 17, 12, 0, 0).unwrap();
+
+    let idle = menu(&recent, &Status::new(None, 20, now), &Labels::default());
+    assert_eq!(
+        idle.items,
+        [
+            entry(support, "Q&&A", false),
+            entry(website, "Website", false),
+            Item::Separator,
+            stop(false),
+            Item::Separator,
+            Item::Show {
+                label: "Show Timeloop".to_string()
+            },
+            Item::Quit {
+                label: "Quit".to_string()
+            },
+        ]
+    );
+
+    timer::start(&pool, website, None, now - Duration::minutes(5))
+        .await
+        .unwrap();
+    let on_recent = menu(
+        &recent,
+        &Status::new(timer::get(&pool).await.unwrap(), 20, now),
+        &Labels::default(),
+    );
+    assert_eq!(
+        on_recent.items[..3],
+        [
+            entry(support, "Q&&A", false),
+            entry(website, "Website", true),
+            Item::Separator,
+        ]
+    );
+    assert_eq!(on_recent.items[3], stop(true));
+
+    // No time was logged on the timer's entry yet.
+    timer::start(&pool, call, None, now - Duration::minutes(65))
+        .await
+        .unwrap();
+    let on_new = menu(
+        &recent,
+        &Status::new(timer::get(&pool).await.unwrap(), 20, now),
+        &Labels::default(),
+    );
+    assert_eq!(
+        on_new.items[..4],
+        [
+            entry(call, "Client call", true),
+            entry(support, "Q&&A", false),
+            entry(website, "Website", false),
+            Item::Separator,
+        ]
+    );
+    assert!(
+        on_new.tooltip.starts_with("Client call 1:05"),
+        "{}",
+        on_new.tooltip
+    );
+}
+
+#[test]
+fn menu_items_use_the_labels_given() {
+    let now = Utc.with_ymd_and_hms(2026, 10, 17, 12, 0, 0).unwrap();
+    let labels = Labels {
+        stop_timer: "Arrêter le chrono".to_string(),
+        show: "Afficher Timeloop".to_string(),
+        quit: "Q&uitter".to_string(),
+    };
+    assert_eq!(
+        menu(&[], &Status::new(None, 0, now), &labels).items,
+        [
+            Item::Stop {
+                label: "Arrêter le chrono".to_string(),
+                enabled: false,
+            },
+            Item::Separator,
+            Item::Show {
+                label: "Afficher Timeloop".to_string()
+            },
+            Item::Quit {
+                label: "Q&&uitter".to_string()
+            },
+        ]
+    );
+}
+
+#[test]
+fn actions_are_read_back_from_their_ids() {
+    for action in [Action::Start(12), Action::Stop, Action::Show, Action::Quit] {
+        assert_eq!(Action::from_id(&action.id()), Some(action));
+    }
+    assert_eq!(Action::from_id("entry:website"), None);
+    assert_eq!(Action::from_id("pause"), None);
+}
@@ -1,19 +1,42 @@
-import { useState } from "react";
+import { useState, useEffect } from "react";
+import { listen } from "@tauri-apps/api/event";
+import { useTranslation } from "react-i18next";
 import { Layout } from "@/components/Layout";
 import { TrackView } from "@/views/TrackView";
 import { CountView } from "@/views/CountView";
 import { EntriesView } from "@/views/EntriesView";
 import { CategoriesView } from "@/views/CategoriesView";
+import { setTrayLabels } from "@/lib/database";
 
 type View = "track" | "count" | "entries" | "categories";
 
 function App() {
+  const { t, i18n } = useTranslation();
   const [currentView, setCurrentView] = useState<View>("track");
+  // Bumped by the quick log shortcut for TrackView to focus its search
+  const [quickLog, setQuickLog] = useState(0);
+
+  useEffect(() => {
+    const unlisten = listen("quick-log", () => {
+      setCurrentView("track");
+      setQuickLog((count) => count + 1);
+    });
+    return () => {
+      unlisten.then((stop) => stop());
+    };
+  }, []);
+
+  // The tray menu is built by the backend, in English until told otherwise
+  useEffect(() => {
+    setTrayLabels(t("tray.stopTimer"), t("tray.show"), t("tray.quit")).catch(
+      (error) => console.error("Error updating the tray menu:", error)
+    );
+  }, [t, i18n.language]);
 
   const renderView = () => {
     switch (currentView) {
       case "track":
-        return <TrackView />;
+        return <TrackView quickLog={quickLog} />;
       case "count":
         return <CountView />;
       case "entries":
@@ -27,7 +27,10 @@ import {
   deleteEntry,
   updateEntry,
   updateTimeEntry,
+  getConflictingTimeEntries,
+  type TimeRange,
 } from "@/lib/database";
+import { toLocalISOString } from "@/lib/utils";
 import type { Category, Entry, TimeEntry } from "@/types";
 
 interface EntryModalProps {
@@ -111,6 +114,7 @@ export function EntryModal({
   const handleAddTime = async () => {
     console.log("handleAddTime called, mode:", inputMode);
     let durationMinutes = 0;
+    let range: TimeRange | null = null;
 
     if (inputMode === "minutes") {
       durationMinutes = parseInt(duration);
@@ -149,6 +153,24 @@ export function EntryModal({
         console.log("Invalid time range");
         return;
       }
+
+      range = {
+        startedAt: toLocalISOString(new Date(`${date}T${startTime}`)),
+        endedAt: toLocalISOString(new Date(`${date}T${endTime}`)),
+      };
+
+      // Warn before double-booking
+      const conflicts = await getConflictingTimeEntries(range);
+      if (
+        conflicts.length > 0 &&
+        !window.confirm(
+          t('entryModal.overlapWarning', {
+            titles: conflicts.map((c) => c.entry_title).join(", "),
+          })
+        )
+      ) {
+        return;
+      }
     }
 
     console.log("Final duration in minutes:", durationMinutes);
@@ -163,7 +185,8 @@ export function EntryModal({
       entry.id,
       durationMinutes,
       date,
-      note.trim() || null
+      note.trim() || null,
+      range
     );
 
     console.log("Time entry created, resetting form");
@@ -217,11 +240,28 @@ export function EntryModal({
     const durationMinutes = parseInt(editDuration);
     if (isNaN(durationMinutes) || durationMinutes <= 0) return;
 
+    // Keep the recorded range only while it still matches the duration and day
+    const original = timeEntries.find((te) => te.id === editingTimeEntryId);
+    const keepRange =
+      original?.started_at &&
+      original.ended_at &&
+      original.duration === durationMinutes &&
+      original.date === editDate;
+    // On the clock it was logged with, not today's
+    const offset = original?.utc_offset ?? undefined;
+    const range: TimeRange | null = keepRange
+      ? {
+          startedAt: toLocalISOString(new Date(original.started_at!), offset),
+          endedAt: toLocalISOString(new Date(original.ended_at!), offset),
+        }
+      : null;
+
     await updateTimeEntry(
       editingTimeEntryId,
       durationMinutes,
       editDate,
-      editNote.trim() || null
+      editNote.trim() || null,
+      range
     );
     
     setEditingTimeEntryId(null);
@@ -285,6 +325,13 @@ export function EntryModal({
     });
   };
 
+  const formatTime = (timestamp: string) => {
+    return new Date(timestamp).toLocaleTimeString(i18n.language, {
+      hour: "2-digit",
+      minute: "2-digit",
+    });
+  };
+
   const formatDuration = (minutes: number) => {
     if (minutes < 60) return `${minutes}min`;
     const hours = Math.floor(minutes / 60);
@@ -560,6 +607,11 @@ export function EntryModal({
                             <span className="text-sm text-[var(--muted-foreground)] ml-2">
                               {formatDate(te.date)}
                             </span>
+                            {te.started_at && te.ended_at && (
+                              <span className="text-sm text-[var(--muted-foreground)] ml-2">
+                                {formatTime(te.started_at)} → {formatTime(te.ended_at)}
+                              </span>
+                            )}
                           </div>
                           {te.note && (
                             <p className="text-sm text-[var(--muted-foreground)] mt-1">
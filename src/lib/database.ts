@@ -1,36 +1,36 @@
-import Database from "@tauri-apps/plugin-sql";
+import { invoke } from "@tauri-apps/api/core";
 import type {
+  ActiveTimer,
+  ApiConfig,
+  CalendarEvent,
   Category,
+  CsvColumns,
+  DatabaseReport,
+  Downgrade,
   Entry,
+  EventSelection,
+  IdleResolution,
+  ImportReport,
+  Overlap,
+  RestoreSummary,
+  ShortcutAction,
+  ShortcutBinding,
+  Snapshot,
   TimeEntry,
   TimeEntryWithDetails,
+  TimewarriorExport,
 } from "@/types";
 
-let db: Database | null = null;
-
-export async function getDatabase(): Promise<Database> {
-  if (!db) {
-    db = await Database.load("sqlite:timeloop.db");
-  }
-  return db;
-}
-
 // Categories
 export async function getCategories(): Promise<Category[]> {
-  const database = await getDatabase();
-  return database.select<Category[]>("SELECT * FROM categories ORDER BY name");
+  return invoke("get_categories");
 }
 
 export async function createCategory(
   name: string,
   color: string = "#6366f1"
 ): Promise<number> {
-  const database = await getDatabase();
-  const result = await database.execute(
-    "INSERT INTO categories (name, color) VALUES ($1, $2)",
-    [name, color]
-  );
-  return result.lastInsertId ?? 0;
+  return invoke("create_category", { name, color });
 }
 
 export async function updateCategory(
@@ -38,83 +38,34 @@ export async function updateCategory(
   name: string,
   color: string
 ): Promise<void> {
-  const database = await getDatabase();
-  await database.execute(
-    "UPDATE categories SET name = $1, color = $2 WHERE id = $3",
-    [name, color, id]
-  );
+  await invoke("update_category", { id, name, color });
 }
 
 export async function deleteCategory(id: number): Promise<void> {
-  const database = await getDatabase();
-  console.log("Deleting category:", id);
-  
-  // First, set category_id to NULL for all entries using this category
-  const updateResult = await database.execute(
-    "UPDATE entries SET category_id = NULL WHERE category_id = $1",
-    [id]
-  );
-  console.log("Update result:", updateResult);
-  
-  // Then delete the category
-  const deleteResult = await database.execute("DELETE FROM categories WHERE id = $1", [id]);
-  console.log("Delete result:", deleteResult);
+  await invoke("delete_category", { id });
 }
 
 // Entries
 export async function getEntries(): Promise<Entry[]> {
-  const database = await getDatabase();
-  return database.select<Entry[]>(`
-    SELECT e.*, c.name as category_name, c.color as category_color
-    FROM entries e
-    LEFT JOIN categories c ON e.category_id = c.id
-    ORDER BY e.title
-  `);
+  return invoke("get_entries");
 }
 
 export async function getEntriesForPeriod(
   startDate: string,
   endDate: string
 ): Promise<Entry[]> {
-  const database = await getDatabase();
-  return database.select<Entry[]>(
-    `
-    SELECT DISTINCT e.*, c.name as category_name, c.color as category_color
-    FROM entries e
-    LEFT JOIN categories c ON e.category_id = c.id
-    INNER JOIN time_entries te ON e.id = te.entry_id
-    WHERE te.date >= $1 AND te.date <= $2
-    ORDER BY e.title
-  `,
-    [startDate, endDate]
-  );
+  return invoke("get_entries_for_period", { startDate, endDate });
 }
 
 export async function searchEntries(query: string): Promise<Entry[]> {
-  const database = await getDatabase();
-  return database.select<Entry[]>(
-    `
-    SELECT e.*, c.name as category_name, c.color as category_color
-    FROM entries e
-    LEFT JOIN categories c ON e.category_id = c.id
-    WHERE e.title LIKE $1
-    ORDER BY e.title
-    LIMIT 10
-  `,
-    [`%${query}%`]
-  );
+  return invoke("search_entries", { query });
 }
 
 export async function createEntry(
   title: string,
   categoryId: number | null
 ): Promise<number> {
-  const database = await getDatabase();
-  const result = await database.execute(
-    "INSERT INTO entries (title, category_id) VALUES ($1, $2)",
-    [title, categoryId]
-  );
-  return result.lastInsertId ?? 0;
+  return invoke("create_entry", { title, categoryId });
 }
 
 export async function updateEntry(
@@ -122,30 +73,15 @@ export async function updateEntry(
   title: string,
   categoryId: number | null
 ): Promise<void> {
-  const database = await getDatabase();
-  await database.execute(
-    "UPDATE entries SET title = $1, category_id = $2 WHERE id = $3",
-    [title, categoryId, id]
-  );
+  await invoke("update_entry", { id, title, categoryId });
 }
 
 export async function deleteEntry(id: number): Promise<void> {
-  const database = await getDatabase();
-  await database.execute("DELETE FROM entries WHERE id = $1", [id]);
+  await invoke("delete_entry", { id });
 }
 
 export async function getEntryById(id: number): Promise<Entry | null> {
-  const database = await getDatabase();
-  const results = await database.select<Entry[]>(
-    `
-    SELECT e.*, c.name as category_name, c.color as category_color
-    FROM entries e
-    LEFT JOIN categories c ON e.category_id = c.id
-    WHERE e.id = $1
-  `,
-    [id]
-  );
-  return results[0] || null;
+  return invoke("get_entry_by_id", { id });
 }
 
 // Time Entries
@@ -153,22 +89,7 @@ export async function getTimeEntries(
   limit: number = 50,
   offset: number = 0
 ): Promise<TimeEntryWithDetails[]> {
-  const database = await getDatabase();
-  return database.select<TimeEntryWithDetails[]>(
-    `
-    SELECT 
-      te.*,
-      e.title as entry_title,
-      c.name as category_name,
-      c.color as category_color
-    FROM time_entries te
-    JOIN entries e ON te.entry_id = e.id
-    LEFT JOIN categories c ON e.category_id = c.id
-    ORDER BY te.date DESC, te.created_at DESC
-    LIMIT $1 OFFSET $2
-  `,
-    [limit, offset]
-  );
+  return invoke("get_time_entries", { limit, offset });
 }
 
 export async function getEntriesWithTotalTime(): Promise<
@@ -184,27 +105,7 @@ export async function getEntriesWithTotalTime(): Promise<
     entry_count: number;
   }>
 > {
-  const database = await getDatabase();
-  return database.select(
-    `
-    SELECT 
-      e.id,
-      e.title,
-      e.category_id,
-      c.name as category_name,
-      c.color as category_color,
-      COALESCE(SUM(te.duration), 0) as total_duration,
-      MIN(te.date) as first_date,
-      MAX(te.date) as last_date,
-      COUNT(te.id) as entry_count
-    FROM entries e
-    LEFT JOIN categories c ON e.category_id = c.id
-    LEFT JOIN time_entries te ON e.id = te.entry_id
-    GROUP BY e.id, e.title, e.category_id, c.name, c.color
-    HAVING COUNT(te.id) > 0
-    ORDER BY MAX(te.date) DESC
-  `
-  );
+  return invoke("get_entries_with_total_time");
 }
 
 export async function getEntriesWithTotalTimeIncludingEmpty(): Promise<
@@ -220,75 +121,80 @@ export async function getEntriesWithTotalTimeIncludingEmpty(): Promise<
     entry_count: number;
   }>
 > {
-  const database = await getDatabase();
-  return database.select(
-    `
-    SELECT 
-      e.id,
-      e.title,
-      e.category_id,
-      c.name as category_name,
-      c.color as category_color,
-      COALESCE(SUM(te.duration), 0) as total_duration,
-      MIN(te.date) as first_date,
-      MAX(te.date) as last_date,
-      COUNT(te.id) as entry_count
-    FROM entries e
-    LEFT JOIN categories c ON e.category_id = c.id
-    LEFT JOIN time_entries te ON e.id = te.entry_id
-    GROUP BY e.id, e.title, e.category_id, c.name, c.color
-    ORDER BY 
-      CASE WHEN MAX(te.date) IS NULL THEN 1 ELSE 0 END,
-      MAX(te.date) DESC,
-      e.title ASC
-  `
-  );
+  return invoke("get_entries_with_total_time_including_empty");
 }
 
 export async function getTimeEntriesForEntry(
   entryId: number
 ): Promise<TimeEntry[]> {
-  const database = await getDatabase();
-  return database.select<TimeEntry[]>(
-    `
-    SELECT * FROM time_entries
-    WHERE entry_id = $1
-    ORDER BY date DESC
-  `,
-    [entryId]
-  );
+  return invoke("get_time_entries_for_entry", { entryId });
+}
+
+// Start and end of a time entry, as ISO 8601 timestamps with the local offset
+export interface TimeRange {
+  startedAt: string;
+  endedAt: string;
 }
 
 export async function createTimeEntry(
   entryId: number,
   duration: number,
   date: string,
-  note: string | null = null
+  note: string | null = null,
+  range: TimeRange | null = null
 ): Promise<number> {
-  const database = await getDatabase();
-  const result = await database.execute(
-    "INSERT INTO time_entries (entry_id, duration, date, note) VALUES ($1, $2, $3, $4)",
-    [entryId, duration, date, note]
-  );
-  return result.lastInsertId ?? 0;
+  return invoke("create_time_entry", {
+    entryId,
+    duration,
+    date,
+    note,
+    startedAt: range?.startedAt ?? null,
+    endedAt: range?.endedAt ?? null,
+  });
 }
 
 export async function updateTimeEntry(
   id: number,
   duration: number,
   date: string,
-  note: string | null
+  note: string | null,
+  range: TimeRange | null = null
 ): Promise<void> {
-  const database = await getDatabase();
-  await database.execute(
-    "UPDATE time_entries SET duration = $1, date = $2, note = $3 WHERE id = $4",
-    [duration, date, note, id]
-  );
+  await invoke("update_time_entry", {
+    id,
+    duration,
+    date,
+    note,
+    startedAt: range?.startedAt ?? null,
+    endedAt: range?.endedAt ?? null,
+  });
+}
+
+// Time entries whose recorded range intersects the given one
+export async function getConflictingTimeEntries(
+  range: TimeRange,
+  excludeId: number | null = null
+): Promise<TimeEntryWithDetails[]> {
+  return invoke("get_conflicting_time_entries", {
+    startedAt: range.startedAt,
+    endedAt: range.endedAt,
+    excludeId,
+  });
+}
+
+export async function findOverlaps(
+  startDate: string,
+  endDate: string
+): Promise<Overlap[]> {
+  return invoke("find_overlaps", { startDate, endDate });
+}
+
+export async function backfillTimeRanges(): Promise<number> {
+  return invoke("backfill_time_ranges");
 }
 
 export async function deleteTimeEntry(id: number): Promise<void> {
-  const database = await getDatabase();
-  await database.execute("DELETE FROM time_entries WHERE id = $1", [id]);
+  await invoke("delete_time_entry", { id });
 }
 
 // Calculate total time for specific entries in a date range
@@ -298,22 +204,7 @@ export async function calculateTimeForPeriod(
   endDate: string
 ): Promise<number> {
   if (entryIds.length === 0) return 0;
-  
-  const database = await getDatabase();
-  const placeholders = entryIds.map((_, i) => `$${i + 3}`).join(", ");
-  
-  const result = await database.select<Array<{ total: number }>>(
-    `
-    SELECT COALESCE(SUM(duration), 0) as total
-    FROM time_entries
-    WHERE entry_id IN (${placeholders})
-      AND date >= $1
-      AND date <= $2
-  `,
-    [startDate, endDate, ...entryIds]
-  );
-  
-  return result[0]?.total || 0;
+  return invoke("calculate_time_for_period", { entryIds, startDate, endDate });
 }
 
 // Get entries with total time filtered by date range
@@ -333,29 +224,192 @@ export async function getEntriesWithTotalTimeForPeriod(
     entry_count: number;
   }>
 > {
-  const database = await getDatabase();
-  return database.select(
-    `
-    SELECT 
-      e.id,
-      e.title,
-      e.category_id,
-      c.name as category_name,
-      c.color as category_color,
-      COALESCE(SUM(te.duration), 0) as total_duration,
-      MIN(te.date) as first_date,
-      MAX(te.date) as last_date,
-      COUNT(te.id) as entry_count
-    FROM entries e
-    LEFT JOIN categories c ON e.category_id = c.id
-    LEFT JOIN time_entries te ON e.id = te.entry_id 
-      AND te.date >= $1 
-      AND te.date <= $2
-    GROUP BY e.id, e.title, e.category_id, c.name, c.color
-    HAVING COUNT(te.id) > 0
-    ORDER BY MAX(te.date) DESC
-  `,
-    [startDate, endDate]
-  );
+  return invoke("get_entries_with_total_time_for_period", {
+    startDate,
+    endDate,
+  });
+}
+
+// Timer
+export async function getActiveTimer(): Promise<ActiveTimer | null> {
+  return invoke("get_active_timer");
+}
+
+export async function startTimer(
+  entryId: number,
+  note: string | null = null
+): Promise<number | null> {
+  return invoke("start_timer", { entryId, note });
+}
+
+export async function pauseTimer(): Promise<void> {
+  await invoke("pause_timer");
+}
+
+export async function resumeTimer(): Promise<void> {
+  await invoke("resume_timer");
+}
+
+export async function stopTimer(): Promise<number | null> {
+  return invoke("stop_timer");
+}
+
+// Returns the time entry logged when reassigning
+export async function resolveIdle(
+  resolution: IdleResolution
+): Promise<number | null> {
+  return invoke("resolve_idle", { resolution });
+}
+
+// Minutes without input before the timer asks about idle time; 0 turns it off
+export async function getIdleThreshold(): Promise<number> {
+  return invoke("get_idle_threshold");
+}
+
+export async function setIdleThreshold(minutes: number): Promise<void> {
+  await invoke("set_idle_threshold", { minutes });
+}
+
+// Import and export
+export async function exportCsv(
+  startDate: string,
+  endDate: string
+): Promise<string> {
+  return invoke("export_csv", { startDate, endDate });
+}
+
+export async function importCsv(
+  contents: string,
+  dryRun: boolean,
+  columns: CsvColumns | null = null
+): Promise<ImportReport> {
+  return invoke("import_csv", { contents, columns, dryRun });
+}
+
+// Toggl Track detailed report, CSV or JSON
+export async function importToggl(
+  contents: string,
+  dryRun: boolean
+): Promise<ImportReport> {
+  return invoke("import_toggl", { contents, dryRun });
+}
+
+// Clockify detailed report, CSV
+export async function importClockify(
+  contents: string,
+  dryRun: boolean
+): Promise<ImportReport> {
+  return invoke("import_clockify", { contents, dryRun });
 }
 
+// Harvest detailed time report, CSV
+export async function importHarvest(
+  contents: string,
+  dryRun: boolean
+): Promise<ImportReport> {
+  return invoke("import_harvest", { contents, dryRun });
+}
+
+// Timewarrior data files (data/YYYY-MM.data), concatenated
+export async function importTimewarrior(
+  contents: string,
+  dryRun: boolean
+): Promise<ImportReport> {
+  return invoke("import_timewarrior", { contents, dryRun });
+}
+
+// One data file per month; time entries without start and end times are skipped
+export async function exportTimewarrior(
+  startDate: string,
+  endDate: string
+): Promise<TimewarriorExport> {
+  return invoke("export_timewarrior", { startDate, endDate });
+}
+
+// iCalendar file with one event per time entry
+export async function exportIcal(
+  startDate: string,
+  endDate: string
+): Promise<string> {
+  return invoke("export_ical", { startDate, endDate });
+}
+
+// Events of an iCalendar file starting between the two dates
+export async function listCalendarEvents(
+  contents: string,
+  startDate: string,
+  endDate: string
+): Promise<CalendarEvent[]> {
+  return invoke("list_calendar_events", { contents, startDate, endDate });
+}
+
+export async function importCalendarEvents(
+  events: EventSelection[],
+  dryRun: boolean
+): Promise<ImportReport> {
+  return invoke("import_calendar_events", { events, dryRun });
+}
+
+// JSON document with every category, entry and time entry
+export async function exportBackup(): Promise<string> {
+  return invoke("export_backup");
+}
+
+// Replaces all data with that of the backup
+export async function importBackup(contents: string): Promise<RestoreSummary> {
+  return invoke("import_backup", { contents });
+}
+
+// Automatic snapshots of the database file
+export async function listBackups(): Promise<Snapshot[]> {
+  return invoke("list_backups");
+}
+
+export async function restoreBackup(name: string): Promise<RestoreSummary> {
+  return invoke("restore_backup", { name });
+}
+
+// With fix, applies the fixes after snapshotting the database
+export async function checkDatabase(fix = false): Promise<DatabaseReport> {
+  return invoke("check_database", { fix });
+}
+
+// Takes the schema back to an older version for an older build; the app quits afterwards
+export async function downgradeDatabase(version: number): Promise<Downgrade> {
+  return invoke("downgrade_database", { version });
+}
+
+// Local HTTP API
+export async function getApiConfig(): Promise<ApiConfig> {
+  return invoke("get_api_config");
+}
+
+export async function setApiEnabled(enabled: boolean): Promise<ApiConfig> {
+  return invoke("set_api_enabled", { enabled });
+}
+
+export async function resetApiToken(): Promise<ApiConfig> {
+  return invoke("reset_api_token");
+}
+
+// Global keyboard shortcuts
+export async function getShortcuts(): Promise<ShortcutBinding[]> {
+  return invoke("get_shortcuts");
+}
+
+// A null accelerator removes the action's shortcut
+export async function setShortcut(
+  action: ShortcutAction,
+  accelerator: string | null
+): Promise<ShortcutBinding[]> {
+  return invoke("set_shortcut", { action, accelerator });
+}
+
+// Words the tray menu's own items in the current language
+export async function setTrayLabels(
+  stopTimer: string,
+  show: string,
+  quit: string
+): Promise<void> {
+  await invoke("set_tray_labels", { stopTimer, show, quit });
+}
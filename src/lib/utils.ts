@@ -5,3 +5,18 @@ export function cn(...inputs: ClassValue[]) {
   return twMerge(clsx(inputs));
 }
 
+// ISO 8601 timestamp on the local clock, e.g. "2026-10-17T08:00:00+02:00",
+// or on the clock `offset` minutes east of UTC
+export function toLocalISOString(
+  date: Date,
+  offset = -date.getTimezoneOffset()
+): string {
+  const pad = (n: number) => String(Math.floor(Math.abs(n))).padStart(2, "0");
+  const clock = new Date(date.getTime() + offset * 60_000);
+  const sign = offset >= 0 ? "+" : "-";
+  return (
+    `${clock.getUTCFullYear()}-${pad(clock.getUTCMonth() + 1)}-${pad(clock.getUTCDate())}` +
+    `T${pad(clock.getUTCHours())}:${pad(clock.getUTCMinutes())}:${pad(clock.getUTCSeconds())}` +
+    `${sign}${pad(offset / 60)}:${pad(offset % 60)}`
+  );
+}
@@ -20,6 +20,10 @@ export interface TimeEntry {
   date: string;
   note: string | null;
   created_at: string;
+  started_at: string | null;
+  ended_at: string | null;
+  utc_offset: number | null;
+  range_estimated: boolean;
   entry?: Entry;
 }
 
@@ -29,3 +33,141 @@ export interface TimeEntryWithDetails extends TimeEntry {
   category_color: string | null;
 }
 
+
+export interface ActiveTimer {
+  entry_id: number;
+  entry_title: string;
+  note: string | null;
+  started_at: string;
+  resumed_at: string | null;
+  accumulated_seconds: number;
+  // Idle span while the timer ran; once idle_until is set it waits for resolveIdle
+  idle_since: string | null;
+  idle_until: string | null;
+}
+
+export type IdleResolution =
+  | { action: "keep" }
+  | { action: "discard" }
+  | { action: "reassign"; entry_id: number };
+
+export interface Overlap {
+  first: TimeEntryWithDetails;
+  second: TimeEntryWithDetails;
+  started_at: string;
+  ended_at: string;
+  minutes: number;
+}
+
+export interface Snapshot {
+  name: string;
+  kind: "daily" | "weekly" | "pre-migration" | "pre-restore" | "pre-repair";
+  taken_at: string;
+  size: number;
+}
+
+// Schema downgrade, with the snapshot taken beforehand
+export interface Downgrade {
+  from: number;
+  to: number;
+  snapshot: string;
+}
+
+// A problem found in the database, with the fix applied for it, if any
+export type DatabaseProblem =
+  | { kind: "corrupted"; message: string }
+  | { kind: "missing_reference"; table: string; id: number; parent: string }
+  | { kind: "orphaned_time_entry"; id: number; entry_id: number }
+  | { kind: "invalid_date"; id: number; date: string }
+  | { kind: "invalid_duration"; id: number; duration: number }
+  | { kind: "duplicate_entries"; title: string; entry_ids: number[] };
+
+export type DatabaseFix =
+  | { action: "delete_time_entry"; id: number }
+  | { action: "set_date"; id: number; date: string }
+  | { action: "set_duration"; id: number; duration: number }
+  | { action: "uncategorize"; entry_id: number }
+  | { action: "delete_timer" }
+  | { action: "merge_entries"; into: number; from: number[] };
+
+export type DatabaseIssue = DatabaseProblem & { fix: DatabaseFix | null };
+
+export interface DatabaseReport {
+  issues: DatabaseIssue[];
+  fixed: boolean;
+  snapshot: string | null;
+}
+
+export interface RestoreSummary {
+  categories: number;
+  entries: number;
+  time_entries: number;
+}
+
+export interface ImportReport {
+  dry_run: boolean;
+  time_entries: number;
+  total_duration: number;
+  created_entries: string[];
+  matched_entries: string[];
+  created_categories: string[];
+  matched_categories: string[];
+  skipped_duplicates: number;
+}
+
+// Occurrence of an event read from an iCalendar file, times on the user's clock
+export interface CalendarEvent {
+  uid: string | null;
+  summary: string;
+  description: string | null;
+  location: string | null;
+  started_at: string;
+  ended_at: string;
+  duration: number;
+}
+
+// Event to log on an existing entry, or on the entry titled entry_title
+// (the event summary by default), created in category_name if missing
+export interface EventSelection extends CalendarEvent {
+  entry_id?: number;
+  entry_title?: string;
+  category_name?: string;
+}
+
+// Timewarrior data file, named YYYY-MM.data
+export interface DataFile {
+  name: string;
+  contents: string;
+}
+
+export interface TimewarriorExport {
+  files: DataFile[];
+  skipped: number;
+}
+
+// Header of the CSV column holding each field; omitted fields keep their default name
+export interface CsvColumns {
+  date?: string;
+  start?: string;
+  end?: string;
+  duration?: string;
+  entry?: string;
+  category?: string;
+  note?: string;
+}
+
+// Local HTTP API settings, kept in api.json in the app data directory
+export interface ApiConfig {
+  enabled: boolean;
+  port: number;
+  token: string;
+}
+
+export type ShortcutAction = "toggle_timer" | "stop_timer" | "quick_log";
+
+// Accelerators are normalized, e.g. "Ctrl+Alt+Shift+T"; null means no shortcut
+export interface ShortcutBinding {
+  action: ShortcutAction;
+  accelerator: string | null;
+  default_accelerator: string;
+}
@@ -1,6 +1,7 @@
 import { useState, useEffect, useCallback, useRef } from "react";
 import { useTranslation } from "react-i18next";
-import { Plus, Search, List, Layers, Edit2, Trash2, AlertTriangle } from "lucide-react";
+import { listen } from "@tauri-apps/api/event";
+import { Plus, Search, List, Layers, Edit2, Trash2, AlertTriangle, Play, Pause, Square } from "lucide-react";
 import { Input } from "@/components/ui/input";
 import { Button } from "@/components/ui/button";
 import {
@@ -29,12 +30,29 @@ import {
   getEntryById,
   deleteEntry,
   deleteTimeEntry,
+  getActiveTimer,
+  startTimer,
+  pauseTimer,
+  resumeTimer,
+  stopTimer,
+  resolveIdle,
 } from "@/lib/database";
-import type { Category, Entry, TimeEntryWithDetails } from "@/types";
+import type {
+  ActiveTimer,
+  Category,
+  Entry,
+  IdleResolution,
+  TimeEntryWithDetails,
+} from "@/types";
 
 type ViewMode = "detailed" | "grouped";
 
-export function TrackView() {
+interface TrackViewProps {
+  // Changes each time the quick log shortcut is pressed
+  quickLog?: number;
+}
+
+export function TrackView({ quickLog }: TrackViewProps) {
   const { t, i18n } = useTranslation();
   const [categories, setCategories] = useState<Category[]>([]);
   const [timeEntries, setTimeEntries] = useState<TimeEntryWithDetails[]>([]);
@@ -49,22 +67,91 @@ export function TrackView() {
   const [isNewEntry, setIsNewEntry] = useState(false);
   const [deleteDialogOpen, setDeleteDialogOpen] = useState(false);
   const [deleteTarget, setDeleteTarget] = useState<{ type: 'entry' | 'timeEntry', id: number } | null>(null);
+  const [activeTimer, setActiveTimer] = useState<ActiveTimer | null>(null);
+  const [now, setNow] = useState(Date.now());
+  const [idleEntryId, setIdleEntryId] = useState<string>("");
 
   const loadData = useCallback(async () => {
-    const [cats, entries, grouped] = await Promise.all([
+    const [cats, entries, grouped, timer] = await Promise.all([
       getCategories(),
       getTimeEntries(),
       getEntriesWithTotalTimeIncludingEmpty(),
+      getActiveTimer(),
     ]);
     setCategories(cats);
     setTimeEntries(entries);
     setGroupedEntries(grouped);
+    setActiveTimer(timer);
   }, []);
 
   useEffect(() => {
     loadData();
   }, [loadData]);
 
+  // The timer can also be driven from the tray, shortcuts and timeloop-cli
+  useEffect(() => {
+    const unlisten = listen("timer-changed", () => loadData());
+    return () => {
+      unlisten.then((stop) => stop());
+    };
+  }, [loadData]);
+
+  // Tick every second while the timer runs
+  useEffect(() => {
+    if (!activeTimer?.resumed_at) return;
+    const interval = setInterval(() => setNow(Date.now()), 1000);
+    return () => clearInterval(interval);
+  }, [activeTimer]);
+
+  const handleStartTimer = async (entryId: number) => {
+    await startTimer(entryId);
+    await loadData();
+  };
+
+  const handleToggleTimer = async () => {
+    if (activeTimer?.resumed_at) {
+      await pauseTimer();
+    } else {
+      await resumeTimer();
+    }
+    await loadData();
+  };
+
+  const handleStopTimer = async () => {
+    await stopTimer();
+    await loadData();
+  };
+
+  const handleResolveIdle = async (resolution: IdleResolution) => {
+    await resolveIdle(resolution);
+    setIdleEntryId("");
+    await loadData();
+  };
+
+  const idleMinutes = (timer: ActiveTimer) =>
+    timer.idle_since && timer.idle_until
+      ? Math.round(
+          (new Date(timer.idle_until).getTime() - new Date(timer.idle_since).getTime()) / 60000
+        )
+      : 0;
+
+  const formatTime = (dateStr: string) =>
+    new Date(dateStr).toLocaleTimeString(i18n.language, {
+      hour: "2-digit",
+      minute: "2-digit",
+    });
+
+  const timerElapsed = (timer: ActiveTimer) => {
+    const running = timer.resumed_at
+      ? Math.max(0, Math.floor((now - new Date(timer.resumed_at).getTime()) / 1000))
+      : 0;
+    const seconds = timer.accumulated_seconds + running;
+    const h = Math.floor(seconds / 3600);
+    const m = Math.floor((seconds % 3600) / 60);
+    const s = seconds % 60;
+    return `${h}:${String(m).padStart(2, "0")}:${String(s).padStart(2, "0")}`;
+  };
+
   useEffect(() => {
     const search = async () => {
       if (searchQuery.length > 0) {
@@ -173,6 +260,11 @@ export function TrackView() {
   const inputRef = useRef<HTMLInputElement>(null);
   const dropdownRef = useRef<HTMLDivElement>(null);
 
+  // The quick log shortcut focuses the search, which opens it
+  useEffect(() => {
+    if (quickLog) inputRef.current?.focus();
+  }, [quickLog]);
+
   // Close dropdown when clicking outside
   useEffect(() => {
     const handleClickOutside = (event: MouseEvent) => {
@@ -218,6 +310,32 @@ export function TrackView() {
           </div>
         </div>
 
+        {/* Running timer */}
+        {activeTimer && (
+          <div className="flex items-center gap-3 bg-[var(--muted)] rounded-lg px-3 py-2">
+            <span className="flex-1 font-medium truncate">{activeTimer.entry_title}</span>
+            <span className="font-mono tabular-nums">{timerElapsed(activeTimer)}</span>
+            <Button
+              variant="ghost"
+              size="icon"
+              className="h-8 w-8 shrink-0"
+              title={activeTimer.resumed_at ? t('track.pauseTimer') : t('track.resumeTimer')}
+              onClick={handleToggleTimer}
+            >
+              {activeTimer.resumed_at ? <Pause className="h-4 w-4" /> : <Play className="h-4 w-4" />}
+            </Button>
+            <Button
+              variant="ghost"
+              size="icon"
+              className="h-8 w-8 shrink-0"
+              title={t('track.stopTimer')}
+              onClick={handleStopTimer}
+            >
+              <Square className="h-4 w-4" />
+            </Button>
+          </div>
+        )}
+
         <div className="flex gap-2">
           <div className="flex-1 relative">
             <Search className="absolute left-3 top-1/2 -translate-y-1/2 h-4 w-4 text-[var(--muted-foreground)] pointer-events-none" />
@@ -386,6 +504,15 @@ export function TrackView() {
                   
                   {/* Colonne 3: Actions */}
                   <div className="flex gap-1">
+                    <Button
+                      variant="ghost"
+                      size="icon"
+                      className="h-8 w-8 shrink-0"
+                      title={t('track.startTimer')}
+                      onClick={() => handleStartTimer(entry.id)}
+                    >
+                      <Play className="h-4 w-4" />
+                    </Button>
                     <Button
                       variant="ghost"
                       size="icon"
@@ -516,6 +643,57 @@ export function TrackView() {
         />
       )}
 
+      {/* Idle time on the timer, once the user is back */}
+      <Dialog open={!!activeTimer?.idle_until}>
+        <DialogContent>
+          <DialogHeader>
+            <DialogTitle>{t('track.idleTitle')}</DialogTitle>
+            <DialogDescription>
+              {activeTimer?.idle_since && activeTimer.idle_until &&
+                t('track.idleDescription', {
+                  duration: formatDuration(idleMinutes(activeTimer)),
+                  since: formatTime(activeTimer.idle_since),
+                  until: formatTime(activeTimer.idle_until),
+                  entry: activeTimer.entry_title,
+                })}
+            </DialogDescription>
+          </DialogHeader>
+          <div className="flex gap-2">
+            <Select value={idleEntryId} onValueChange={setIdleEntryId}>
+              <SelectTrigger className="flex-1">
+                <SelectValue placeholder={t('track.idleReassignPlaceholder')} />
+              </SelectTrigger>
+              <SelectContent>
+                {groupedEntries
+                  .filter((entry) => entry.id !== activeTimer?.entry_id)
+                  .map((entry) => (
+                    <SelectItem key={entry.id} value={entry.id.toString()}>
+                      {entry.title}
+                    </SelectItem>
+                  ))}
+              </SelectContent>
+            </Select>
+            <Button
+              variant="outline"
+              disabled={!idleEntryId}
+              onClick={() =>
+                handleResolveIdle({ action: "reassign", entry_id: parseInt(idleEntryId) })
+              }
+            >
+              {t('track.idleReassign')}
+            </Button>
+          </div>
+          <DialogFooter>
+            <Button variant="outline" onClick={() => handleResolveIdle({ action: "discard" })}>
+              {t('track.idleDiscard')}
+            </Button>
+            <Button onClick={() => handleResolveIdle({ action: "keep" })}>
+              {t('track.idleKeep')}
+            </Button>
+          </DialogFooter>
+        </DialogContent>
+      </Dialog>
+
       {/* Delete confirmation dialog */}
       <Dialog open={deleteDialogOpen} onOpenChange={setDeleteDialogOpen}>
         <DialogContent>